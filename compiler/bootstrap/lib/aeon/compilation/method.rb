@@ -76,21 +76,28 @@ module Aeon
 
           next unless default
 
-          name = arg.children[0]
-          local_idx = body.locals.get(name)
-          exists_reg = body.next_register
-
-          jump_to = body.label
+          local_idx = body.locals.get(arg.children[0])
+          code = code_for_default_argument(body, arg.children[0], default)
 
-          body.instruct(default.line, default.column) do |ins|
-            ins.local_exists exists_reg, local_idx
-            ins.goto_if_true jump_to, exists_reg
-            ins.set_local    local_idx, @compiler.process(default, body)
-            ins.mark_label   jump_to
-          end
+          body.default_arguments << [local_idx, code]
         end
       end
 
+      # Default values are evaluated by the VM in the binding of the method,
+      # thus the code object shares the local variables of the method body.
+      def code_for_default_argument(body, name, default)
+        code = CompiledCode.new("<default #{name}>", @code.file, default.line,
+                                type: body.type, locals: body.locals)
+
+        code.outer_scope = body.outer_scope
+
+        value = @compiler.process(default, code)
+
+        code.return([value], default.line, default.column)
+
+        code
+      end
+
       def add_implicit_return(code)
         ins = code.instructions.last
 
@@ -20,7 +20,7 @@ module Aeon
           rec = implicit_receiver
         end
 
-        @code.send_literal([target, rec, name_idx, rest, *args], line, column)
+        @code.send_literal([target, rec, name_idx, rest, 0, *args], line, column)
 
         target
       end
@@ -2,12 +2,13 @@ module Aeon
   class CompiledCode
     attr_reader :name, :file, :line, :arguments, :required_arguments,
       :rest_argument, :integers, :floats, :strings, :code_objects,
-      :register, :locals, :instructions, :register, :labels, :type
+      :register, :locals, :instructions, :register, :labels, :type,
+      :default_arguments
 
     attr_accessor :outer_scope
 
     def initialize(name, file, line, arguments = 0, required_arguments = 0,
-                   rest_argument: false, type: nil)
+                   rest_argument: false, type: nil, locals: Literals.new)
       @name = name
       @file = file
       @line = line
@@ -15,12 +16,13 @@ module Aeon
       @required_arguments = required_arguments
       @rest_argument = rest_argument
 
-      @locals = Literals.new
+      @locals = locals
       @instructions = []
       @integers = Literals.new
       @floats = Literals.new
       @strings = Literals.new
       @code_objects = Literals.new
+      @default_arguments = []
 
       @label = 0
       @labels = {}
@@ -94,6 +96,7 @@ module Aeon
       end
 
       @code_objects.to_a.each(&:resolve_labels)
+      @default_arguments.each { |(_, code)| code.resolve_labels }
     end
 
     def instruct(line, column)
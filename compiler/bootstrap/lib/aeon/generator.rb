@@ -1,7 +1,7 @@
 module Aeon
   class Generator
     SIGNATURE = 'aeon'.bytes
-    VERSION   = 1
+    VERSION   = 3
 
     def generate(code)
       sig = SIGNATURE.map { |num| u8(num) }.join('')
@@ -62,6 +62,12 @@ module Aeon
         u32(ins.column)
     end
 
+    def default_argument(pair)
+      index, code = pair
+
+      u32(index) + compiled_code(code)
+    end
+
     def compiled_code(code)
       string(code.name) +
         string(code.file) +
@@ -69,6 +75,7 @@ module Aeon
         i32(code.arguments) +
         u32(code.required_arguments) +
         boolean(code.rest_argument) +
+        array(code.default_arguments, :default_argument) +
         array(code.locals.to_a, :string) +
         array(code.instructions, :instruction) +
         array(code.integers.to_a, :i64) +
@@ -112,7 +112,34 @@ module Aeon
       get_current_pid: 107,
       set_parent_local: 108,
       get_parent_local: 109,
-      get_binding_of_caller: 110
+      get_binding_of_caller: 110,
+      receive_process_message_timeout: 111,
+      receive_matching_process_message: 112,
+      link_process: 113,
+      monitor_process: 114,
+      trap_exits: 115,
+      register_process: 116,
+      unregister_process: 117,
+      whereis_process: 118,
+      send_named_process_message: 119,
+      get_process_prototype: 120,
+      process_exit: 121,
+      process_kill: 122,
+      process_status: 123,
+      process_mailbox_size: 124,
+      process_list: 125,
+      process_heap_info: 126,
+      process_priority: 127,
+      set_process_priority: 128,
+      process_sleep: 129,
+      send_after: 130,
+      time_monotonic_nanos: 131,
+      time_wall_clock: 132,
+      time_utc_timestamp: 133,
+      freeze: 134,
+      spawn_closure: 135,
+      spawn_process_with_arguments: 136,
+      join_process: 137
     }
 
     # Instructions where the register containing a value to return is the last
@@ -0,0 +1,141 @@
+//! Benchmark for the process scheduler
+//!
+//! This benchmark spawns a number of processes with a skewed workload: half of
+//! the processes run a long loop, the other half finish right away. A process
+//! running a loop is never suspended, thus any processes queued up on the same
+//! thread have to wait for it to finish unless they are stolen by another
+//! thread.
+//!
+//! The benchmark runs the workload both with and without work stealing.
+extern crate libaeon;
+extern crate time;
+
+use std::sync::Arc;
+
+use libaeon::compiled_code::{CompiledCode, RcCompiledCode};
+use libaeon::config::Config;
+use libaeon::instruction::{Instruction, InstructionType};
+use libaeon::virtual_machine::{VirtualMachine, VirtualMachineState};
+
+/// The name the main process is registered under.
+const MAIN_NAME: &'static str = "scheduler-benchmark";
+
+/// The number of heavy and light processes to spawn.
+const PROCESS_PAIRS: i64 = 32;
+
+/// The number of iterations performed by a heavy process.
+const HEAVY_ITERATIONS: i64 = 200_000;
+
+/// The number of threads to use for running processes.
+const PROCESS_THREADS: usize = 4;
+
+fn instruction(ins_type: InstructionType, arguments: Vec<u32>) -> Instruction {
+    Instruction::new(ins_type, arguments, 1, 1)
+}
+
+/// Returns a process that counts up to the given number, then notifies the
+/// main process.
+fn worker(name: &str, iterations: i64) -> RcCompiledCode {
+    let mut code =
+        CompiledCode::new(name.to_string(),
+                          "bench.aeon".to_string(),
+                          1,
+                          vec![instruction(InstructionType::SetInteger,
+                                           vec![0, 0]),
+                               instruction(InstructionType::SetInteger,
+                                           vec![1, 1]),
+                               instruction(InstructionType::SetInteger,
+                                           vec![2, 2]),
+                               instruction(InstructionType::IntegerSmaller,
+                                           vec![3, 0, 2]),
+                               instruction(InstructionType::GotoIfFalse,
+                                           vec![7, 3]),
+                               instruction(InstructionType::IntegerAdd,
+                                           vec![0, 0, 1]),
+                               instruction(InstructionType::Goto, vec![3]),
+                               instruction(InstructionType::SetString,
+                                           vec![4, 0]),
+                               instruction(InstructionType::SendNamedProcessMessage,
+                                           vec![5, 4, 0])]);
+
+    code.integer_literals = vec![0, 1, iterations];
+    code.string_literals = vec![MAIN_NAME.to_string()];
+
+    Arc::new(code)
+}
+
+/// Returns the main process, which spawns all workers and waits for them to
+/// finish.
+fn main_process() -> RcCompiledCode {
+    let mut code =
+        CompiledCode::new("main".to_string(),
+                          "bench.aeon".to_string(),
+                          1,
+                          vec![instruction(InstructionType::SetString,
+                                           vec![0, 0]),
+                               instruction(InstructionType::GetCurrentPid,
+                                           vec![1]),
+                               instruction(InstructionType::RegisterProcess,
+                                           vec![2, 0, 1]),
+                               instruction(InstructionType::SetInteger,
+                                           vec![3, 0]),
+                               instruction(InstructionType::SetInteger,
+                                           vec![4, 1]),
+                               instruction(InstructionType::SetInteger,
+                                           vec![5, 2]),
+                               // Spawn the workers.
+                               instruction(InstructionType::SpawnLiteralProcess,
+                                           vec![6, 0]),
+                               instruction(InstructionType::SpawnLiteralProcess,
+                                           vec![6, 1]),
+                               instruction(InstructionType::IntegerAdd,
+                                           vec![3, 3, 4]),
+                               instruction(InstructionType::IntegerSmaller,
+                                           vec![7, 3, 5]),
+                               instruction(InstructionType::GotoIfTrue,
+                                           vec![6, 7]),
+                               // Wait for all workers to finish.
+                               instruction(InstructionType::SetInteger,
+                                           vec![3, 0]),
+                               instruction(InstructionType::IntegerAdd,
+                                           vec![5, 5, 5]),
+                               instruction(InstructionType::ReceiveProcessMessage,
+                                           vec![8]),
+                               instruction(InstructionType::IntegerAdd,
+                                           vec![3, 3, 4]),
+                               instruction(InstructionType::IntegerSmaller,
+                                           vec![7, 3, 5]),
+                               instruction(InstructionType::GotoIfTrue,
+                                           vec![13, 7])]);
+
+    code.integer_literals = vec![0, 1, PROCESS_PAIRS];
+    code.string_literals = vec![MAIN_NAME.to_string()];
+    code.code_objects = vec![worker("heavy", HEAVY_ITERATIONS),
+                             worker("light", 1)];
+
+    Arc::new(code)
+}
+
+fn run(work_stealing: bool) {
+    let mut config = Config::new();
+
+    config.set_process_threads(PROCESS_THREADS);
+    config.set_gc_threads(1);
+    config.set_work_stealing(work_stealing);
+
+    let vm = VirtualMachine::new(VirtualMachineState::new(config));
+    let start = time::precise_time_ns();
+
+    vm.start(main_process()).unwrap();
+
+    let duration = (time::precise_time_ns() - start) as f64 / 1000000.0;
+
+    println!("Work stealing {}: {:.2} ms",
+             if work_stealing { "enabled" } else { "disabled" },
+             duration);
+}
+
+fn main() {
+    run(false);
+    run(true);
+}
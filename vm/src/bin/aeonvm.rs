@@ -35,11 +35,34 @@ fn main() {
 
     options.optopt("", "gcthreads", "The number of GC threads to use", "INT");
 
+    options.optopt("",
+                   "mark-threads",
+                   "The number of threads to use for marking a single heap",
+                   "INT");
+
+    options.optopt("",
+                   "gc-pause-budget",
+                   "The maximum time in microseconds of a young GC pause",
+                   "INT");
+
     options.optopt("",
                    "reductions",
                    "The number of reductions that can take place",
                    "INT");
 
+    options.optopt("",
+                   "max-depth",
+                   "The maximum call depth of a process",
+                   "INT");
+
+    options.optflag("",
+                    "fail-on-process-errors",
+                    "Exits with an error status when any process fails");
+
+    options.optflag("",
+                    "no-work-stealing",
+                    "Disables stealing of processes between threads");
+
     options.optflag("", "type-sizes", "Prints the size of various VM types");
 
     let matches = match options.parse(&args[1..]) {
@@ -104,10 +127,30 @@ fn main() {
             config.set_gc_threads(gc_threads.parse::<usize>().unwrap());
         }
 
+        if let Some(threads) = matches.opt_str("mark-threads") {
+            config.set_mark_threads(threads.parse::<usize>().unwrap());
+        }
+
+        if let Some(budget) = matches.opt_str("gc-pause-budget") {
+            config.set_gc_pause_budget(budget.parse::<u64>().unwrap());
+        }
+
         if let Some(reductions) = matches.opt_str("reductions") {
             config.set_reductions(reductions.parse::<usize>().unwrap());
         }
 
+        if let Some(depth) = matches.opt_str("max-depth") {
+            config.set_max_call_depth(depth.parse::<usize>().unwrap());
+        }
+
+        if matches.opt_present("fail-on-process-errors") {
+            config.set_fail_on_process_errors(true);
+        }
+
+        if matches.opt_present("no-work-stealing") {
+            config.set_work_stealing(false);
+        }
+
         if matches.opt_present("I") {
             for dir in matches.opt_strs("I") {
                 config.add_directory(dir);
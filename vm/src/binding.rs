@@ -4,6 +4,8 @@
 //! local variables and "self") of a certain execution context.
 use std::sync::Arc;
 use std::cell::UnsafeCell;
+use std::io;
+use immix::copy_object::{CopyObject, CopyMap};
 use object_pointer::{ObjectPointer, ObjectPointerPointer};
 
 pub struct Binding {
@@ -14,7 +16,11 @@ pub struct Binding {
     ///
     /// Local variables must **not** be modified concurrently as access is not
     /// synchronized due to 99% of all operations being process-local.
-    pub locals: UnsafeCell<Vec<ObjectPointer>>,
+    ///
+    /// Slots are set to None when a local with a higher index is defined
+    /// before the local itself, for example when an optional argument is
+    /// skipped using keyword arguments.
+    pub locals: UnsafeCell<Vec<Option<ObjectPointer>>>,
 
     /// The parent binding, if any.
     pub parent: Option<RcBinding>,
@@ -51,7 +57,7 @@ impl Binding {
     pub fn get_local(&self, index: usize) -> Result<ObjectPointer, String> {
         self.locals()
             .get(index)
-            .cloned()
+            .and_then(|local| local.clone())
             .ok_or_else(|| format!("Undefined local variable index {}", index))
     }
 
@@ -59,17 +65,18 @@ impl Binding {
     pub fn set_local(&self, index: usize, value: ObjectPointer) {
         let mut locals = self.locals_mut();
 
-        if locals.get(index).is_some() {
-            // Existing locals should be overwritten without shifting values.
-            locals[index] = value;
-        } else {
-            locals.insert(index, value);
+        // Any locals between the last defined local and the new one are left
+        // undefined.
+        if index >= locals.len() {
+            locals.resize(index + 1, None);
         }
+
+        locals[index] = Some(value);
     }
 
     /// Returns true if the local variable exists.
     pub fn local_exists(&self, index: usize) -> bool {
-        self.locals().get(index).is_some()
+        self.locals().get(index).map_or(false, |local| local.is_some())
     }
 
     /// Returns the parent binding.
@@ -99,15 +106,54 @@ impl Binding {
     }
 
     /// Returns an immutable reference to this binding's local variables.
-    pub fn locals(&self) -> &Vec<ObjectPointer> {
+    pub fn locals(&self) -> &Vec<Option<ObjectPointer>> {
         unsafe { &*self.locals.get() }
     }
 
     /// Returns a mutable reference to this binding's local variables.
-    pub fn locals_mut(&self) -> &mut Vec<ObjectPointer> {
+    pub fn locals_mut(&self) -> &mut Vec<Option<ObjectPointer>> {
         unsafe { &mut *self.locals.get() }
     }
 
+    /// Performs a deep copy of this binding and its parents, copying all
+    /// objects to the given allocator.
+    ///
+    /// Objects already copied are looked up in the given map, allowing
+    /// bindings that refer to themselves to be copied.
+    pub fn copy_to<T: CopyObject>(&self,
+                                  allocator: &mut T,
+                                  copies: &mut CopyMap)
+                                  -> Result<RcBinding, io::Error> {
+        let self_object =
+            try!(allocator.copy_object_with_map(self.self_object, copies));
+
+        let mut locals = Vec::with_capacity(self.locals().len());
+
+        for local in self.locals().iter() {
+            let local_copy = match *local {
+                Some(pointer) => {
+                    Some(try!(allocator.copy_object_with_map(pointer, copies)))
+                }
+                None => None,
+            };
+
+            locals.push(local_copy);
+        }
+
+        let parent = match self.parent {
+            Some(ref parent) => Some(try!(parent.copy_to(allocator, copies))),
+            None => None,
+        };
+
+        let bind = Binding {
+            self_object: self_object,
+            locals: UnsafeCell::new(locals),
+            parent: parent,
+        };
+
+        Ok(Arc::new(bind))
+    }
+
     /// Pushes all pointers in this binding into the supplied vector.
     pub fn push_pointers(&self, pointers: &mut Vec<ObjectPointerPointer>) {
         self.push_pointers_from_binding(pointers, self);
@@ -127,7 +173,9 @@ impl Binding {
         pointers.push(binding.self_object.pointer());
 
         for local in binding.locals().iter() {
-            pointers.push(local.pointer());
+            if let Some(ref pointer) = *local {
+                pointers.push(pointer.pointer());
+            }
         }
     }
 }
@@ -135,7 +183,10 @@ impl Binding {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use immix::global_allocator::GlobalAllocator;
+    use immix::local_allocator::LocalAllocator;
     use object_pointer::{ObjectPointer, RawObjectPointer};
+    use object_value;
 
     #[test]
     fn test_with_parent() {
@@ -173,6 +224,19 @@ mod tests {
         assert_eq!(binding.locals().len(), 1);
     }
 
+    #[test]
+    fn test_set_local_with_gap() {
+        let ptr = ObjectPointer::null();
+        let binding = Binding::new(ptr);
+
+        binding.set_local(2, ptr);
+
+        assert_eq!(binding.locals().len(), 3);
+        assert!(binding.get_local(0).is_err());
+        assert_eq!(binding.local_exists(1), false);
+        assert!(binding.local_exists(2));
+    }
+
     #[test]
     fn test_local_exists_non_existing_local() {
         let ptr = ObjectPointer::null();
@@ -276,4 +340,47 @@ mod tests {
         assert!(*pointers[2].get() == self_obj1);
         assert!(*pointers[3].get() == local1);
     }
+
+    #[test]
+    fn test_copy_to() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let mut alloc = LocalAllocator::new(global_alloc);
+
+        let self_obj = alloc.allocate_empty();
+        let local = alloc.allocate_empty();
+        let parent = Binding::new(self_obj);
+        let binding = Binding::with_parent(self_obj, parent.clone());
+
+        parent.set_local(0, local);
+        binding.set_local(1, local);
+
+        let copy = binding.copy_to(&mut alloc, &mut CopyMap::new()).unwrap();
+        let parent_copy = copy.parent().unwrap();
+
+        assert!(copy.self_object() != self_obj);
+        assert_eq!(copy.local_exists(0), false);
+        assert!(copy.get_local(1).unwrap() != local);
+        assert!(parent_copy.get_local(0).unwrap() != local);
+    }
+
+    #[test]
+    fn test_copy_to_with_cycle() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let mut alloc = LocalAllocator::new(global_alloc);
+
+        let self_obj = alloc.allocate_empty();
+        let binding = Binding::new(self_obj);
+        let binding_obj = alloc
+            .allocate_without_prototype(object_value::binding(binding.clone()));
+
+        // This is what "let b = binding" produces.
+        binding.set_local(0, binding_obj);
+
+        let copy = binding.copy_to(&mut alloc, &mut CopyMap::new()).unwrap();
+        let local_copy = copy.get_local(0).unwrap();
+        let inner_copy = local_copy.get().value.as_binding();
+
+        assert!(local_copy != binding_obj);
+        assert!(inner_copy.get_local(0).unwrap() == local_copy);
+    }
 }
@@ -79,9 +79,16 @@ macro_rules! read_code_vector {
     );
 }
 
+macro_rules! read_default_argument_vector {
+    ($byte_type: ident, $bytes: expr) => (
+        try!(read_vector::<(usize, RcCompiledCode), $byte_type>($bytes,
+                                                    read_default_argument));
+    );
+}
+
 const SIGNATURE_BYTES: [u8; 4] = [97, 101, 111, 110]; // "aeon"
 
-const VERSION: u8 = 1;
+const VERSION: u8 = 3;
 
 #[derive(Debug)]
 pub enum ParserError {
@@ -249,6 +256,7 @@ fn read_compiled_code<T: Read>(bytes: &mut Bytes<T>)
     let args = try!(read_u32(bytes));
     let req_args = try!(read_u32(bytes));
     let rest_arg = try!(read_u8(bytes)) == 1;
+    let default_args = read_default_argument_vector!(T, bytes);
 
     let locals = read_string_vector!(T, bytes);
     let instructions = read_instruction_vector!(T, bytes);
@@ -264,6 +272,7 @@ fn read_compiled_code<T: Read>(bytes: &mut Bytes<T>)
         arguments: args,
         required_arguments: req_args,
         rest_argument: rest_arg,
+        default_arguments: default_args,
         locals: locals,
         instructions: instructions,
         integer_literals: int_literals,
@@ -275,6 +284,14 @@ fn read_compiled_code<T: Read>(bytes: &mut Bytes<T>)
     Ok(Arc::new(code_obj))
 }
 
+fn read_default_argument<T: Read>(bytes: &mut Bytes<T>)
+                                  -> ParserResult<(usize, RcCompiledCode)> {
+    let index = try!(read_u32(bytes)) as usize;
+    let code = try!(read_compiled_code(bytes));
+
+    Ok((index, code))
+}
+
 #[cfg(test)]
 mod tests {
     use instruction::InstructionType;
@@ -400,6 +417,7 @@ mod tests {
         pack_u32!(0, buffer); // arguments
         pack_u32!(0, buffer); // required arguments
         pack_u8!(0, buffer); // rest argument
+        pack_u64!(0, buffer); // default arguments
         pack_u64!(0, buffer); // locals
         pack_u64!(0, buffer); // instructions
         pack_u64!(0, buffer); // integer literals
@@ -629,6 +647,23 @@ mod tests {
         pack_u32!(3, buffer); // arguments
         pack_u32!(2, buffer); // required args
         pack_u8!(1, buffer); // rest argument
+
+        pack_u64!(1, buffer); // default arguments
+        pack_u32!(1, buffer); // argument index
+        pack_string!("default", buffer);
+        pack_string!("test.aeon", buffer);
+        pack_u32!(4, buffer); // line
+        pack_u32!(0, buffer); // arguments
+        pack_u32!(0, buffer); // required arguments
+        pack_u8!(0, buffer); // rest argument
+        pack_u64!(0, buffer); // default arguments
+        pack_u64!(0, buffer); // locals
+        pack_u64!(0, buffer); // instructions
+        pack_u64!(0, buffer); // integer literals
+        pack_u64!(0, buffer); // float literals
+        pack_u64!(0, buffer); // string literals
+        pack_u64!(0, buffer); // code objects
+
         pack_u64!(0, buffer); // locals
 
         pack_u64!(1, buffer); // instructions
@@ -658,6 +693,10 @@ mod tests {
         assert_eq!(object.required_arguments, 2);
         assert_eq!(object.rest_argument, true);
 
+        assert_eq!(object.default_arguments.len(), 1);
+        assert_eq!(object.default_arguments[0].0, 1);
+        assert_eq!(object.default_arguments[0].1.name, "default".to_string());
+
         assert_eq!(object.locals.len(), 0);
 
         assert_eq!(object.instructions.len(), 1);
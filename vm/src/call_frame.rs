@@ -17,6 +17,10 @@ pub struct CallFrame {
 
     /// An optional parent CallFrame.
     pub parent: Option<Box<CallFrame>>,
+
+    /// The number of frames that were elided due to tail calls made from this
+    /// frame.
+    pub tail_calls: usize,
 }
 
 /// Struct for iterating over all the call frames in a call stack.
@@ -31,6 +35,7 @@ impl CallFrame {
             code: code,
             line: line,
             parent: None,
+            tail_calls: 0,
         }
     }
 
@@ -47,6 +52,13 @@ impl CallFrame {
         &self.code.file
     }
 
+    /// Returns true if this frame and the given frame refer to the same line
+    /// in the same code.
+    pub fn same_location(&self, other: &CallFrame) -> bool {
+        self.line == other.line && self.tail_calls == other.tail_calls &&
+        self.file() == other.file() && self.name() == other.name()
+    }
+
     pub fn parent(&self) -> Option<&Box<CallFrame>> {
         self.parent.as_ref()
     }
@@ -101,6 +113,7 @@ mod tests {
         assert_eq!(frame.name(), &"foo".to_string());
         assert_eq!(frame.file(), &"test.aeon".to_string());
         assert_eq!(frame.line, 1);
+        assert_eq!(frame.tail_calls, 0);
     }
 
     #[test]
@@ -113,6 +126,17 @@ mod tests {
         assert_eq!(frame.line, 1);
     }
 
+    #[test]
+    fn test_same_location() {
+        let code = compiled_code();
+        let frame1 = CallFrame::new(code.clone(), 1);
+        let frame2 = CallFrame::new(code.clone(), 1);
+        let frame3 = CallFrame::new(code, 2);
+
+        assert!(frame1.same_location(&frame2));
+        assert_eq!(frame1.same_location(&frame3), false);
+    }
+
     #[test]
     fn test_set_parent() {
         let code = compiled_code();
@@ -0,0 +1,80 @@
+//! Clocks for reading the current time
+//!
+//! Processes read the current time using the Clock stored in the VM
+//! configuration. By default this is the system clock, but a fake clock that
+//! always returns the same time can be used instead to produce reproducible
+//! results (e.g. when running tests).
+//!
+//! Timers (e.g. those used for sleeping) don't use these clocks, as a fake
+//! clock would prevent them from ever expiring.
+
+use time::{self, Timespec, Tm};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clock {
+    /// A clock that uses the time of the operating system.
+    System,
+
+    /// A clock that always returns the given time, in nanoseconds since the
+    /// Unix epoch.
+    Fake(u64),
+}
+
+impl Clock {
+    /// Returns the value of a monotonic clock in nanoseconds.
+    ///
+    /// The returned value is only useful for measuring the time between two
+    /// points in time.
+    pub fn monotonic_nanos(&self) -> u64 {
+        match *self {
+            Clock::System => time::precise_time_ns(),
+            Clock::Fake(nanos) => nanos,
+        }
+    }
+
+    /// Returns the time elapsed since the Unix epoch.
+    pub fn wall_clock(&self) -> Timespec {
+        match *self {
+            Clock::System => time::get_time(),
+            Clock::Fake(nanos) => {
+                Timespec::new((nanos / NANOS_PER_SEC) as i64,
+                              (nanos % NANOS_PER_SEC) as i32)
+            }
+        }
+    }
+
+    /// Returns the current time in UTC.
+    pub fn utc(&self) -> Tm {
+        time::at_utc(self.wall_clock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monotonic_nanos_fake() {
+        let clock = Clock::Fake(10);
+
+        assert_eq!(clock.monotonic_nanos(), 10);
+        assert_eq!(clock.monotonic_nanos(), 10);
+    }
+
+    #[test]
+    fn test_wall_clock_fake() {
+        let clock = Clock::Fake(1_500_000_000_250);
+
+        assert_eq!(clock.wall_clock(), Timespec::new(1500, 250));
+    }
+
+    #[test]
+    fn test_utc_fake() {
+        let clock = Clock::Fake(86_400_000_000_000);
+
+        assert_eq!(format!("{}", clock.utc().rfc3339()),
+                   "1970-01-02T00:00:00Z");
+    }
+}
@@ -40,6 +40,10 @@ pub struct CompiledCode {
     /// Whether a rest argument is defined.
     pub rest_argument: bool,
 
+    /// The CompiledCode objects to run for arguments that were not given,
+    /// along with the local variable index of the argument.
+    pub default_arguments: Vec<(usize, RcCompiledCode)>,
+
     /// List of local variable names.
     pub locals: Vec<String>,
 
@@ -83,6 +87,7 @@ impl CompiledCode {
             arguments: 0,
             required_arguments: 0,
             rest_argument: false,
+            default_arguments: Vec::new(),
             locals: Vec::new(),
             instructions: instructions,
             integer_literals: Vec::new(),
@@ -125,6 +130,14 @@ impl CompiledCode {
             .cloned()
             .ok_or_else(|| format!("Undefined code object {}", index))
     }
+
+    /// Returns the local variable index of the argument with the given name.
+    pub fn argument_index(&self, name: &String) -> Option<usize> {
+        self.locals
+            .iter()
+            .take(self.arguments as usize)
+            .position(|local| local == name)
+    }
 }
 
 #[cfg(test)]
@@ -207,4 +220,16 @@ mod tests {
 
         assert!(code.code_object(0).is_ok());
     }
+
+    #[test]
+    fn test_argument_index() {
+        let mut code = new_compiled_code();
+
+        code.arguments = 2;
+        code.locals = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+
+        assert_eq!(code.argument_index(&"b".to_string()), Some(1));
+        assert!(code.argument_index(&"c".to_string()).is_none());
+        assert!(code.argument_index(&"d".to_string()).is_none());
+    }
 }
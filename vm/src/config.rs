@@ -8,6 +8,8 @@
 use num_cpus;
 use std::path::PathBuf;
 
+use clock::Clock;
+
 /// Structure containing the configuration settings for the virtual machine.
 pub struct Config {
     /// The directories to search in for extra bytecode files to run.
@@ -21,9 +23,42 @@ pub struct Config {
     /// of CPU cores.
     pub gc_threads: usize,
 
+    /// The number of threads to use for marking the heap of a single process,
+    /// including the GC thread performing the collection. The additional
+    /// threads are started once and shared by all GC threads. Only processes
+    /// with a large heap are marked using multiple threads. Defaults to the
+    /// number of CPU cores.
+    pub mark_threads: usize,
+
+    /// The maximum time (in microseconds) a young collection may suspend a
+    /// process for. When set, young collections mark the heap incrementally
+    /// across several pauses. Collections that promote objects or collect the
+    /// mature generation still use a single pause. Defaults to None.
+    pub gc_pause_budget: Option<u64>,
+
     /// The number of reductions a process can perform before being suspended.
     /// Defaults to 1000.
     pub reductions: usize,
+
+    /// The maximum number of nested calls a process can perform before a
+    /// stack overflow error is raised. Defaults to 10 000.
+    pub max_call_depth: usize,
+
+    /// When set to true the VM exits with an error status if any process
+    /// fails, instead of only when the main process fails. Defaults to false.
+    pub fail_on_process_errors: bool,
+
+    /// When set to true idle threads take processes from the queues of other
+    /// threads. Defaults to true.
+    pub work_stealing: bool,
+
+    /// The clock processes use for reading the current time. Defaults to the
+    /// system clock.
+    ///
+    /// This clock is only used by the instructions that read the time.
+    /// Deadlines for sleeping, receive timeouts, and delayed messages always
+    /// use the system clock, as a fake clock never advances.
+    pub clock: Clock,
 }
 
 impl Config {
@@ -34,7 +69,13 @@ impl Config {
             directories: Vec::new(),
             process_threads: cpu_count,
             gc_threads: cpu_count,
+            mark_threads: cpu_count,
+            gc_pause_budget: None,
             reductions: 1000,
+            max_call_depth: 10_000,
+            fail_on_process_errors: false,
+            work_stealing: true,
+            clock: Clock::System,
         }
     }
 
@@ -58,16 +99,51 @@ impl Config {
         }
     }
 
+    pub fn set_mark_threads(&mut self, threads: usize) {
+        if threads == 0 {
+            self.mark_threads = 1;
+        } else {
+            self.mark_threads = threads;
+        }
+    }
+
+    pub fn set_gc_pause_budget(&mut self, micros: u64) {
+        if micros > 0 {
+            self.gc_pause_budget = Some(micros);
+        }
+    }
+
     pub fn set_reductions(&mut self, reductions: usize) {
         if reductions > 0 {
             self.reductions = reductions;
         }
     }
+
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        if depth > 0 {
+            self.max_call_depth = depth;
+        }
+    }
+
+    pub fn set_fail_on_process_errors(&mut self, fail: bool) {
+        self.fail_on_process_errors = fail;
+    }
+
+    pub fn set_work_stealing(&mut self, enabled: bool) {
+        self.work_stealing = enabled;
+    }
+
+    /// Uses a fake clock that always returns the given time, in nanoseconds
+    /// since the Unix epoch.
+    pub fn set_fake_clock(&mut self, nanos: u64) {
+        self.clock = Clock::Fake(nanos);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clock::Clock;
 
     #[test]
     fn test_new() {
@@ -76,7 +152,13 @@ mod tests {
         assert_eq!(config.directories.len(), 0);
         assert!(config.process_threads >= 1);
         assert!(config.gc_threads >= 1);
+        assert!(config.mark_threads >= 1);
+        assert!(config.gc_pause_budget.is_none());
         assert_eq!(config.reductions, 1000);
+        assert_eq!(config.max_call_depth, 10_000);
+        assert_eq!(config.fail_on_process_errors, false);
+        assert!(config.work_stealing);
+        assert_eq!(config.clock, Clock::System);
     }
 
     #[test]
@@ -106,6 +188,32 @@ mod tests {
         assert_eq!(config.gc_threads, 5);
     }
 
+    #[test]
+    fn test_set_mark_threads() {
+        let mut config = Config::new();
+
+        config.set_mark_threads(5);
+
+        assert_eq!(config.mark_threads, 5);
+
+        config.set_mark_threads(0);
+
+        assert_eq!(config.mark_threads, 1);
+    }
+
+    #[test]
+    fn test_set_gc_pause_budget() {
+        let mut config = Config::new();
+
+        config.set_gc_pause_budget(0);
+
+        assert!(config.gc_pause_budget.is_none());
+
+        config.set_gc_pause_budget(500);
+
+        assert_eq!(config.gc_pause_budget, Some(500));
+    }
+
     #[test]
     fn test_set_reductions() {
         let mut config = Config::new();
@@ -114,4 +222,40 @@ mod tests {
 
         assert_eq!(config.reductions, 5);
     }
+
+    #[test]
+    fn test_set_max_call_depth() {
+        let mut config = Config::new();
+
+        config.set_max_call_depth(5);
+
+        assert_eq!(config.max_call_depth, 5);
+    }
+
+    #[test]
+    fn test_set_work_stealing() {
+        let mut config = Config::new();
+
+        config.set_work_stealing(false);
+
+        assert_eq!(config.work_stealing, false);
+    }
+
+    #[test]
+    fn test_set_fake_clock() {
+        let mut config = Config::new();
+
+        config.set_fake_clock(10);
+
+        assert_eq!(config.clock, Clock::Fake(10));
+    }
+
+    #[test]
+    fn test_set_fail_on_process_errors() {
+        let mut config = Config::new();
+
+        config.set_fail_on_process_errors(true);
+
+        assert!(config.fail_on_process_errors);
+    }
 }
@@ -28,6 +28,10 @@ pub static IO_INTERRUPTED: u16 = 18;
 
 pub static STRING_INVALID_UTF8: u16 = 1;
 
+pub static PROCESS_RECEIVE_TIMEOUT: u16 = 1;
+pub static PROCESS_NOT_ALIVE: u16 = 2;
+pub static PROCESS_FAILED: u16 = 3;
+
 /// Returns a VM error name for a Rust IO error.
 pub fn from_io_error(error: io::Error) -> u16 {
     match error.kind() {
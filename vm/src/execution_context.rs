@@ -26,6 +26,10 @@ pub struct ExecutionContext {
 
     /// The register to store this context's return value in.
     pub return_register: Option<usize>,
+
+    /// The local variable to store this context's return value in. This is
+    /// used when evaluating default argument values.
+    pub return_local: Option<usize>,
 }
 
 /// Struct for iterating over an ExecutionContext and its parent contexts.
@@ -45,6 +49,7 @@ impl ExecutionContext {
             parent: None,
             instruction_index: 0,
             return_register: return_register,
+            return_local: None,
         }
     }
 
@@ -68,6 +73,22 @@ impl ExecutionContext {
         ExecutionContext::new(binding, code, return_register)
     }
 
+    /// Returns a new ExecutionContext for evaluating the default value of an
+    /// argument.
+    ///
+    /// The context shares the binding of the method the argument belongs to,
+    /// and stores its return value in the argument's local variable.
+    pub fn for_default_argument(binding: RcBinding,
+                                code: RcCompiledCode,
+                                local: usize)
+                                -> ExecutionContext {
+        let mut context = ExecutionContext::new(binding, code, None);
+
+        context.return_local = Some(local);
+
+        context
+    }
+
     pub fn set_parent(&mut self, parent: Box<ExecutionContext>) {
         self.parent = Some(parent);
     }
@@ -180,6 +201,7 @@ mod tests {
 
         assert!(context.return_register.is_some());
         assert_eq!(context.return_register.unwrap(), 4);
+        assert!(context.return_local.is_none());
     }
 
     #[test]
@@ -195,6 +217,18 @@ mod tests {
         assert_eq!(context.return_register.unwrap(), 4);
     }
 
+    #[test]
+    fn test_for_default_argument() {
+        let binding = new_binding();
+        let code = new_compiled_code();
+        let context =
+            ExecutionContext::for_default_argument(binding.clone(), code, 2);
+
+        assert!(context.return_register.is_none());
+        assert_eq!(context.return_local, Some(2));
+        assert!(context.binding.parent().is_none());
+    }
+
     #[test]
     fn test_with_binding() {
         let binding = new_binding();
@@ -0,0 +1,141 @@
+//! Incremental marking of the young generation.
+//!
+//! When a pause budget is configured, young collections mark the heap in
+//! several slices instead of a single pause. In between slices the process
+//! continues running, and a write barrier records every object written into
+//! the heap. These objects are traced during the next slice, ensuring an
+//! object stored in an already marked object is not missed.
+//!
+//! Once the worklist is empty the roots are traced once more, marking any
+//! objects only reachable from (for example) registers written to in between
+//! slices. Only after this the collection is finished and unused blocks are
+//! reclaimed.
+//!
+//! Objects are never moved during an incremental collection, as the process
+//! may still refer to them using their old location.
+
+use time;
+
+use object_pointer::ObjectPointer;
+
+/// The number of objects to mark before checking if the pause budget has been
+/// exhausted.
+const DEADLINE_CHECK_INTERVAL: usize = 128;
+
+pub struct IncrementalMark {
+    /// The objects that still need to be traced.
+    pub worklist: Vec<ObjectPointer>,
+
+    /// The number of objects marked so far.
+    pub marked: usize,
+
+    /// The number of slices performed so far, not counting the final trace
+    /// of the roots.
+    pub slices: usize,
+}
+
+impl IncrementalMark {
+    pub fn new(roots: Vec<ObjectPointer>) -> IncrementalMark {
+        IncrementalMark {
+            worklist: roots,
+            marked: 0,
+            slices: 0,
+        }
+    }
+
+    /// Schedules an object for tracing.
+    pub fn push(&mut self, pointer: ObjectPointer) {
+        self.worklist.push(pointer);
+    }
+
+    /// Traces objects until the worklist is empty or the deadline (in
+    /// nanoseconds) has passed.
+    ///
+    /// Returns true if all objects in the worklist have been traced.
+    pub fn trace(&mut self, deadline: Option<u64>) -> bool {
+        let mut children = Vec::new();
+        let mut traced = 0;
+
+        while let Some(pointer) = self.worklist.pop() {
+            if pointer.is_marked() {
+                continue;
+            }
+
+            pointer.mark();
+
+            self.marked += 1;
+            traced += 1;
+
+            pointer.get().push_pointers(&mut children);
+
+            for child in children.drain(..) {
+                self.worklist.push(*child.get());
+            }
+
+            if traced % DEADLINE_CHECK_INTERVAL == 0 {
+                if let Some(deadline) = deadline {
+                    if time::precise_time_ns() >= deadline {
+                        return self.worklist.is_empty();
+                    }
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use immix::global_allocator::GlobalAllocator;
+    use immix::local_allocator::LocalAllocator;
+
+    fn local_allocator() -> LocalAllocator {
+        LocalAllocator::new(GlobalAllocator::without_preallocated_blocks())
+    }
+
+    #[test]
+    fn test_push() {
+        let mut allocator = local_allocator();
+        let mut mark = IncrementalMark::new(Vec::new());
+
+        mark.push(allocator.allocate_empty());
+
+        assert_eq!(mark.worklist.len(), 1);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut allocator = local_allocator();
+        let parent = allocator.allocate_empty();
+        let child = allocator.allocate_empty();
+
+        parent.get_mut().add_attribute("child".to_string(), child);
+
+        let mut mark = IncrementalMark::new(vec![parent, parent]);
+
+        assert!(mark.trace(None));
+        assert!(parent.is_marked());
+        assert!(child.is_marked());
+        assert_eq!(mark.marked, 2);
+    }
+
+    #[test]
+    fn test_trace_with_expired_deadline() {
+        let mut allocator = local_allocator();
+        let mut roots = Vec::new();
+
+        for _ in 0..(DEADLINE_CHECK_INTERVAL * 2) {
+            roots.push(allocator.allocate_empty());
+        }
+
+        let mut mark = IncrementalMark::new(roots);
+
+        assert_eq!(mark.trace(Some(0)), false);
+        assert_eq!(mark.marked, DEADLINE_CHECK_INTERVAL);
+
+        assert!(mark.trace(None));
+        assert_eq!(mark.marked, DEADLINE_CHECK_INTERVAL * 2);
+    }
+}
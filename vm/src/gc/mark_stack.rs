@@ -0,0 +1,294 @@
+//! Work-stealing mark stacks for parallel marking.
+//!
+//! Every thread that takes part in marking owns a single stack of pointers to
+//! trace. A thread mostly works on a private worklist, and moves part of it
+//! into its own shared stack when that stack runs dry. Threads that run out of
+//! work first empty their own stack, then steal half of the stack of another
+//! thread.
+//!
+//! Marking has finished once every thread ran out of work and all stacks are
+//! empty. This is tracked using a counter of the threads still active: a
+//! thread only pushes values into its stack while it's active, thus once the
+//! counter reaches zero no new work can show up.
+//!
+//! The thread that starts marking is active from the start. Other threads
+//! join while marking is in progress, and can no longer join once the counter
+//! reaches zero.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use object_pointer::ObjectPointerPointer;
+
+/// The minimum number of values a worklist must contain before values are
+/// moved into the shared stack of a thread.
+const SHARE_THRESHOLD: usize = 32;
+
+pub struct MarkStacks {
+    stacks: Vec<Mutex<Vec<ObjectPointerPointer>>>,
+
+    /// The number of threads that still have (or may obtain) work.
+    active: AtomicUsize,
+}
+
+impl MarkStacks {
+    /// Returns a new set of stacks for the given number of threads.
+    ///
+    /// Only the first thread is considered active, the others have to call
+    /// `join()` before taking part.
+    pub fn new(threads: usize) -> MarkStacks {
+        MarkStacks {
+            stacks: (0..threads).map(|_| Mutex::new(Vec::new())).collect(),
+            active: AtomicUsize::new(1),
+        }
+    }
+
+    /// Marks a thread as active, allowing it to take part in marking.
+    ///
+    /// The return value is `false` if marking has already finished.
+    pub fn join(&self) -> bool {
+        let mut active = self.active.load(Ordering::Acquire);
+
+        loop {
+            if active == 0 {
+                return false;
+            }
+
+            let current = self.active
+                .compare_and_swap(active, active + 1, Ordering::AcqRel);
+
+            if current == active {
+                return true;
+            }
+
+            active = current;
+        }
+    }
+
+    /// Returns the number of threads these stacks are used by.
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    /// Pushes a value on to the stack of the given thread.
+    pub fn push(&self, thread: usize, value: ObjectPointerPointer) {
+        unlock!(self.stacks[thread]).push(value);
+    }
+
+    /// Moves half of the worklist into the stack of the given thread, if that
+    /// stack is empty.
+    ///
+    /// This ensures other threads can steal work from a thread that spends a
+    /// lot of time tracing a large object graph.
+    pub fn share(&self,
+                 thread: usize,
+                 worklist: &mut Vec<ObjectPointerPointer>) {
+        if worklist.len() < SHARE_THRESHOLD {
+            return;
+        }
+
+        let mut stack = unlock!(self.stacks[thread]);
+
+        if stack.is_empty() {
+            let half = worklist.len() / 2;
+
+            stack.extend(worklist.drain(0..half));
+        }
+    }
+
+    /// Fills the worklist of the given thread with new values to trace.
+    ///
+    /// The returned value is `true` if new work was found, and `false` if
+    /// marking has finished.
+    pub fn refill(&self,
+                  thread: usize,
+                  worklist: &mut Vec<ObjectPointerPointer>)
+                  -> bool {
+        loop {
+            if self.steal(thread, worklist) {
+                return true;
+            }
+
+            self.active.fetch_sub(1, Ordering::AcqRel);
+
+            loop {
+                if self.active.load(Ordering::Acquire) == 0 {
+                    return false;
+                }
+
+                if self.has_work() {
+                    self.active.fetch_add(1, Ordering::AcqRel);
+                    break;
+                }
+
+                thread::yield_now();
+            }
+        }
+    }
+
+    /// Takes all values of the thread's own stack, or steals half of the
+    /// values of another thread.
+    fn steal(&self,
+             thread: usize,
+             worklist: &mut Vec<ObjectPointerPointer>)
+             -> bool {
+        {
+            let mut own = unlock!(self.stacks[thread]);
+
+            if !own.is_empty() {
+                worklist.extend(own.drain(..));
+
+                return true;
+            }
+        }
+
+        for offset in 1..self.stacks.len() {
+            let victim = (thread + offset) % self.stacks.len();
+            let mut stack = unlock!(self.stacks[victim]);
+
+            if !stack.is_empty() {
+                let half = (stack.len() + 1) / 2;
+
+                worklist.extend(stack.drain(0..half));
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn has_work(&self) -> bool {
+        self.stacks.iter().any(|stack| !unlock!(stack).is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use object_pointer::ObjectPointer;
+
+    #[test]
+    fn test_len() {
+        assert_eq!(MarkStacks::new(4).len(), 4);
+    }
+
+    #[test]
+    fn test_join() {
+        let stacks = MarkStacks::new(2);
+
+        assert!(stacks.join());
+        assert_eq!(stacks.active.load(Ordering::Acquire), 2);
+    }
+
+    #[test]
+    fn test_join_after_marking_finished() {
+        let stacks = MarkStacks::new(2);
+        let mut worklist = Vec::new();
+
+        assert_eq!(stacks.refill(0, &mut worklist), false);
+        assert_eq!(stacks.join(), false);
+    }
+
+    #[test]
+    fn test_refill_own_stack() {
+        let stacks = MarkStacks::new(2);
+        let pointer = ObjectPointer::null();
+        let mut worklist = Vec::new();
+
+        stacks.push(0, pointer.pointer());
+
+        assert!(stacks.refill(0, &mut worklist));
+        assert_eq!(worklist.len(), 1);
+    }
+
+    #[test]
+    fn test_refill_steals_half() {
+        let stacks = MarkStacks::new(2);
+        let pointer = ObjectPointer::null();
+        let mut worklist = Vec::new();
+
+        for _ in 0..4 {
+            stacks.push(1, pointer.pointer());
+        }
+
+        assert!(stacks.refill(0, &mut worklist));
+        assert_eq!(worklist.len(), 2);
+    }
+
+    #[test]
+    fn test_refill_without_work() {
+        let stacks = MarkStacks::new(1);
+        let mut worklist = Vec::new();
+
+        assert_eq!(stacks.refill(0, &mut worklist), false);
+    }
+
+    #[test]
+    fn test_share() {
+        let stacks = MarkStacks::new(2);
+        let pointer = ObjectPointer::null();
+        let mut worklist = Vec::new();
+
+        for _ in 0..SHARE_THRESHOLD {
+            worklist.push(pointer.pointer());
+        }
+
+        stacks.share(0, &mut worklist);
+
+        assert_eq!(worklist.len(), SHARE_THRESHOLD / 2);
+        assert!(stacks.has_work());
+    }
+
+    #[test]
+    fn test_share_below_threshold() {
+        let stacks = MarkStacks::new(2);
+        let pointer = ObjectPointer::null();
+        let mut worklist = vec![pointer.pointer()];
+
+        stacks.share(0, &mut worklist);
+
+        assert_eq!(worklist.len(), 1);
+        assert_eq!(stacks.has_work(), false);
+    }
+
+    #[test]
+    fn test_refill_terminates_across_threads() {
+        let stacks = Arc::new(MarkStacks::new(4));
+        let pointer = ObjectPointer::null();
+
+        for _ in 0..100 {
+            stacks.push(0, pointer.pointer());
+        }
+
+        let handles: Vec<_> = (0..4)
+            .map(|index| {
+                let stacks = stacks.clone();
+
+                thread::spawn(move || {
+                    let mut worklist = Vec::new();
+                    let mut processed = 0;
+
+                    if index > 0 && !stacks.join() {
+                        return processed;
+                    }
+
+                    while stacks.refill(index, &mut worklist) {
+                        processed += worklist.drain(..).count();
+                    }
+
+                    processed
+                })
+            })
+            .collect();
+
+        let total = handles.into_iter()
+            .fold(0, |total, handle| total + handle.join().unwrap());
+
+        assert_eq!(total, 100);
+    }
+}
@@ -1,2 +1,5 @@
+pub mod incremental;
+pub mod mark_stack;
+pub mod parallel_mark;
 pub mod request;
 pub mod thread;
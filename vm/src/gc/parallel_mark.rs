@@ -0,0 +1,405 @@
+//! Marking of a single heap using multiple threads.
+//!
+//! Processes with a large heap are marked by several threads at once, each
+//! using a work-stealing mark stack. An object is traced by the thread that
+//! first manages to atomically set its mark bit. This thread is also the only
+//! one that evacuates or promotes the object, ensuring an object is never
+//! moved twice.
+//!
+//! Threads that lose the race for an object may still hold a pointer to its
+//! old location. These pointers are stored and updated once all threads have
+//! finished, at which point all forwarding pointers are in place.
+//!
+//! The GC thread performing the collection always takes part in marking. The
+//! remaining threads are taken from a pool of mark threads shared by all GC
+//! threads. Mark threads busy with another collection simply join later, or
+//! not at all if marking finished in the mean time.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use gc::mark_stack::MarkStacks;
+use gc::thread::{evacuate, promote_mature};
+use immix::block::Block;
+use object::ObjectStatus;
+use object_pointer::{ObjectPointer, ObjectPointerPointer};
+use process::RcProcess;
+use queue::Queue;
+
+pub type RcParallelMark = Arc<ParallelMark>;
+
+pub struct ParallelMark {
+    process: RcProcess,
+    stacks: MarkStacks,
+
+    /// A lock to acquire when evacuating or promoting objects, as the
+    /// allocator of a process can only be used by one thread at a time.
+    allocation_lock: Mutex<()>,
+
+    /// Pointers to objects that were moved by another thread.
+    unresolved: Mutex<Vec<ObjectPointerPointer>>,
+
+    /// The addresses of the blocks of which the objects are promoted.
+    ///
+    /// Buckets are modified while objects are being evacuated, thus which
+    /// objects to move is determined before marking starts.
+    promoted_blocks: HashSet<usize>,
+
+    /// The addresses of the blocks of which the objects are evacuated.
+    evacuated_blocks: HashSet<usize>,
+
+    /// The index of the mark stack to use for the next joining thread.
+    next_thread: AtomicUsize,
+
+    /// The number of mark threads currently helping.
+    helping: AtomicUsize,
+
+    marked: AtomicUsize,
+    evacuated: AtomicUsize,
+    promoted: AtomicUsize,
+}
+
+impl ParallelMark {
+    /// Prepares marking the heap of a process.
+    ///
+    /// The process' heap must already be prepared for the collection.
+    pub fn new(process: RcProcess, threads: usize) -> RcParallelMark {
+        let mut promoted_blocks = HashSet::new();
+        let mut evacuated_blocks = HashSet::new();
+
+        {
+            let ref allocator = process.local_data().allocator;
+
+            let buckets = allocator.young_generation
+                .iter()
+                .chain(Some(&allocator.mature_generation));
+
+            for bucket in buckets {
+                let blocks =
+                    bucket.blocks.iter().chain(bucket.recyclable_blocks.iter());
+
+                for block in blocks {
+                    if bucket.promote {
+                        promoted_blocks.insert(block_address(block));
+                    } else if block.fragmented {
+                        evacuated_blocks.insert(block_address(block));
+                    }
+                }
+            }
+        }
+
+        Arc::new(ParallelMark {
+            process: process,
+            stacks: MarkStacks::new(threads),
+            allocation_lock: Mutex::new(()),
+            unresolved: Mutex::new(Vec::new()),
+            promoted_blocks: promoted_blocks,
+            evacuated_blocks: evacuated_blocks,
+            next_thread: AtomicUsize::new(1),
+            helping: AtomicUsize::new(0),
+            marked: AtomicUsize::new(0),
+            evacuated: AtomicUsize::new(0),
+            promoted: AtomicUsize::new(0),
+        })
+    }
+
+    /// Marks all objects reachable from the given pointers.
+    ///
+    /// The calling thread takes part in marking, the remaining threads are
+    /// requested from the mark threads using the given queue.
+    ///
+    /// The return value is a tuple containing the following numbers:
+    ///
+    /// * The number of marked objects
+    /// * The number of evacuated objects
+    /// * The number of promoted objects
+    pub fn run(mark: RcParallelMark,
+               roots: Vec<ObjectPointerPointer>,
+               helpers: &Queue<RcParallelMark>)
+               -> (usize, usize, usize) {
+        let threads = mark.stacks.len();
+
+        for (index, pointer) in roots.into_iter().enumerate() {
+            mark.stacks.push(index % threads, pointer);
+        }
+
+        for _ in 1..threads {
+            helpers.push(mark.clone());
+        }
+
+        mark.trace(0);
+
+        // Threads that joined may still be storing their results.
+        while mark.helping.load(Ordering::Acquire) > 0 {
+            thread::yield_now();
+        }
+
+        mark.resolve_moved_pointers();
+
+        (mark.marked.load(Ordering::Acquire),
+         mark.evacuated.load(Ordering::Acquire),
+         mark.promoted.load(Ordering::Acquire))
+    }
+
+    /// Takes part in marking, unless marking has already finished.
+    ///
+    /// This method is called by the mark threads.
+    pub fn help(&self) {
+        self.helping.fetch_add(1, Ordering::AcqRel);
+
+        let thread = self.next_thread.fetch_add(1, Ordering::AcqRel);
+
+        if thread < self.stacks.len() && self.stacks.join() {
+            self.trace(thread);
+        }
+
+        self.helping.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Traces objects until all mark stacks are empty.
+    pub fn trace(&self, thread: usize) {
+        let mut worklist = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut marked = 0;
+        let mut evacuated = 0;
+        let mut promoted = 0;
+
+        while self.stacks.refill(thread, &mut worklist) {
+            while let Some(pointer_pointer) = worklist.pop() {
+                let pointer = pointer_pointer.get_mut();
+
+                if !pointer.try_mark() {
+                    // Another thread is responsible for this object. If it
+                    // moves the object we can only update our pointer once
+                    // every thread has finished.
+                    if self.will_move(pointer) {
+                        unresolved.push(pointer_pointer);
+                    }
+
+                    continue;
+                }
+
+                match self.status(pointer) {
+                    ObjectStatus::Resolve => {
+                        pointer.resolve_forwarding_pointer()
+                    }
+                    ObjectStatus::Promote => {
+                        let _lock = unlock!(self.allocation_lock);
+
+                        promote_mature(&self.process, pointer);
+
+                        promoted += 1;
+                    }
+                    ObjectStatus::Evacuate => {
+                        let _lock = unlock!(self.allocation_lock);
+
+                        evacuate(&self.process, pointer);
+
+                        evacuated += 1;
+                    }
+                    ObjectStatus::OK => {}
+                }
+
+                pointer.mark_atomic();
+
+                marked += 1;
+
+                pointer.get().push_pointers(&mut worklist);
+
+                self.stacks.share(thread, &mut worklist);
+            }
+        }
+
+        self.marked.fetch_add(marked, Ordering::AcqRel);
+        self.evacuated.fetch_add(evacuated, Ordering::AcqRel);
+        self.promoted.fetch_add(promoted, Ordering::AcqRel);
+
+        unlock!(self.unresolved).extend(unresolved);
+    }
+
+    /// Returns the status of an object marked by the current thread.
+    ///
+    /// Unlike `ObjectPointer::status()` this doesn't read the bucket of the
+    /// object, as another thread may be evacuating objects into it.
+    fn status(&self, pointer: &ObjectPointer) -> ObjectStatus {
+        if pointer.is_forwarded() {
+            return ObjectStatus::Resolve;
+        }
+
+        let block = block_address(pointer.block());
+
+        if self.promoted_blocks.contains(&block) {
+            ObjectStatus::Promote
+        } else if self.evacuated_blocks.contains(&block) {
+            ObjectStatus::Evacuate
+        } else {
+            ObjectStatus::OK
+        }
+    }
+
+    /// Returns true if the object will be moved by the thread that marked it.
+    ///
+    /// This doesn't read the object itself, as the object may be moved by
+    /// another thread at the same time.
+    fn will_move(&self, pointer: &ObjectPointer) -> bool {
+        let block = block_address(pointer.block());
+
+        self.promoted_blocks.contains(&block) ||
+        self.evacuated_blocks.contains(&block)
+    }
+
+    /// Updates all pointers to objects that were moved by another thread.
+    ///
+    /// This method must only be called after all threads finished tracing.
+    fn resolve_moved_pointers(&self) {
+        for pointer_pointer in unlock!(self.unresolved).drain(..) {
+            let pointer = pointer_pointer.get();
+
+            if pointer.is_forwarded() {
+                pointer.resolve_forwarding_pointer();
+            }
+        }
+    }
+}
+
+/// Returns the address of a block, used to identify it.
+fn block_address(block: &Block) -> usize {
+    block as *const Block as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gc::thread::tests::process;
+    use std::thread;
+
+    /// Marks the given roots using the calling thread and three mark threads.
+    fn run(mark: RcParallelMark,
+           roots: Vec<ObjectPointerPointer>)
+           -> (usize, usize, usize) {
+        let helpers = Arc::new(Queue::new());
+
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let helpers = helpers.clone();
+
+                thread::spawn(move || helpers.pop().help())
+            })
+            .collect();
+
+        let result = ParallelMark::run(mark, roots, &helpers);
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        result
+    }
+
+    fn prepare(process: &RcProcess) {
+        let mut local_data = process.local_data_mut();
+
+        for bucket in local_data.allocator.young_generation.iter_mut() {
+            bucket.prepare_for_collection();
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let (_perm_alloc, process) = process();
+        let shared = process.allocate_empty();
+        let mut roots = Vec::new();
+
+        for index in 0..64 {
+            let pointer = process.allocate_empty();
+
+            pointer.get_mut().add_attribute(index.to_string(), shared);
+
+            roots.push(pointer);
+        }
+
+        prepare(&process);
+
+        let pointers = roots.iter().map(|pointer| pointer.pointer()).collect();
+        let mark = ParallelMark::new(process.clone(), 4);
+        let (marked, evacuated, promoted) = run(mark, pointers);
+
+        // The shared object must only be traced once, even though every root
+        // refers to it.
+        assert_eq!(marked, 65);
+        assert_eq!(evacuated, 0);
+        assert_eq!(promoted, 0);
+        assert!(shared.is_marked());
+    }
+
+    #[test]
+    fn test_run_with_promotion() {
+        let (_perm_alloc, process) = process();
+        let shared = process.allocate_empty();
+        let mut roots = Vec::new();
+
+        for index in 0..64 {
+            let pointer = process.allocate_empty();
+
+            pointer.get_mut().add_attribute(index.to_string(), shared);
+
+            roots.push(pointer);
+        }
+
+        shared.block_mut().bucket_mut().unwrap().promote = true;
+
+        prepare(&process);
+
+        let pointers = roots.iter().map(|pointer| pointer.pointer()).collect();
+        let mark = ParallelMark::new(process.clone(), 4);
+        let (marked, _, promoted) = run(mark, pointers);
+
+        assert_eq!(marked, 65);
+        assert_eq!(promoted, 65);
+
+        // All roots and the attributes referring to the shared object must
+        // have been updated to the promoted objects.
+        let new_shared = roots[0]
+            .get()
+            .lookup_attribute(&"0".to_string())
+            .unwrap();
+
+        assert!(new_shared.is_mature());
+
+        for (index, pointer) in roots.iter().enumerate() {
+            assert!(pointer.is_mature());
+
+            let attribute =
+                pointer.get().lookup_attribute(&index.to_string()).unwrap();
+
+            assert!(attribute == new_shared);
+        }
+    }
+
+    #[test]
+    fn test_will_move() {
+        let (_perm_alloc, process) = process();
+        let pointer = process.allocate_empty();
+        let mark = ParallelMark::new(process.clone(), 1);
+
+        assert_eq!(mark.will_move(&pointer), false);
+
+        // Blocks fragmented after marking started are not evacuated.
+        pointer.block_mut().set_fragmented();
+
+        assert_eq!(mark.will_move(&pointer), false);
+        assert!(ParallelMark::new(process.clone(), 1).will_move(&pointer));
+    }
+
+    #[test]
+    fn test_will_move_with_promotion() {
+        let (_perm_alloc, process) = process();
+        let pointer = process.allocate_empty();
+
+        pointer.block_mut().bucket_mut().unwrap().promote = true;
+
+        assert!(ParallelMark::new(process.clone(), 1).will_move(&pointer));
+    }
+}
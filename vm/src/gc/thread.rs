@@ -1,13 +1,20 @@
 //! Threads for garbage collecting memory.
 use time;
 
-use gc::request::Request;
+use gc::incremental::IncrementalMark;
+use gc::parallel_mark::ParallelMark;
+use gc::request::{Generation, Request};
 use object_pointer::ObjectPointer;
 use object::ObjectStatus;
 use process::RcProcess;
 use virtual_machine::RcVirtualMachineState;
 use immix::block::BYTES_PER_OBJECT;
 
+/// The number of young blocks a process must use before its heap is marked
+/// using multiple threads. For smaller heaps the cost of coordinating the
+/// marking threads outweighs the time saved.
+const PARALLEL_MARK_THRESHOLD: usize = 256;
+
 /// Structure containing the state of a single GC thread.
 pub struct Thread {
     pub vm_state: RcVirtualMachineState,
@@ -27,31 +34,57 @@ impl Thread {
     }
 
     pub fn process_request(&self, request: Request) {
+        match request.generation {
+            Generation::Young => self.collect_heap(request),
+            Generation::Mailbox => self.collect_mailbox(&request.process),
+        }
+    }
+
+    /// Collects the young and (if needed) mature generation of a process.
+    fn collect_heap(&self, request: Request) {
         let ref process = request.process;
 
-        // If the process finished execution in the mean time we don't need
-        // to run a GC cycle for it. Once we pass this check the process may
-        // still finish prior to collection. This check is simply in place
-        // to prevent collecting a process that finished before handling the
-        // current GC request.
+        // If the process terminated in the mean time we don't need to run a GC
+        // cycle for it. A process that terminates while a GC request is
+        // pending leaves releasing its memory to the GC thread, as the memory
+        // may still be in use until the request is handled.
         if !process.is_alive() {
+            process.release_memory();
             return;
         }
 
         process.request_gc_suspension();
 
+        // The process may have terminated while we were waiting for it to
+        // suspend itself.
+        if !process.is_alive() {
+            process.release_memory();
+            return;
+        }
+
         let start_time = time::precise_time_ns();
         let collect_mature = process.should_collect_mature_generation();
 
+        if self.collect_incrementally(process, collect_mature) {
+            self.collect_young_incrementally(request, start_time);
+            return;
+        }
+
         self.prepare_collection(process, collect_mature);
 
-        let (marked, evacuated, promoted) = self.mark(process);
+        let (marked, evacuated, promoted) = if self.mark_in_parallel(process) {
+            self.mark_parallel(process)
+        } else {
+            self.mark(process)
+        };
 
         process.increment_young_ages();
 
         self.update_collection_thresholds(process, collect_mature);
         self.reclaim_blocks(process, collect_mature);
 
+        process.collected(collect_mature);
+
         let duration = time::precise_time_ns() - start_time;
         let bytes = (marked + evacuated + promoted) * BYTES_PER_OBJECT;
         let mb_sec = ((bytes / 1024 / 1024) as f64 /
@@ -70,6 +103,199 @@ impl Thread {
         request.thread.reschedule(request.process.clone());
     }
 
+    /// Returns true if the young generation of a process should be collected
+    /// incrementally.
+    ///
+    /// Objects can't be moved during an incremental collection, thus
+    /// collections that promote objects or collect the mature generation are
+    /// performed in a single pause.
+    fn collect_incrementally(&self, process: &RcProcess, mature: bool) -> bool {
+        if self.vm_state.config.gc_pause_budget.is_none() {
+            return false;
+        }
+
+        if process.is_marking() {
+            return true;
+        }
+
+        let local_data = process.local_data();
+        let promote = local_data.allocator
+            .young_generation
+            .iter()
+            .any(|bucket| bucket.promote);
+
+        !mature && !promote
+    }
+
+    /// Performs a single slice of an incremental collection.
+    ///
+    /// The process is rescheduled after every slice, and the write barrier
+    /// keeps track of objects written in between slices. The slice that
+    /// traces the last object also finishes the collection.
+    fn collect_young_incrementally(&self, request: Request, start_time: u64) {
+        let ref process = request.process;
+        let budget = self.vm_state.config.gc_pause_budget.unwrap_or(0);
+        let deadline = start_time + (budget * 1000);
+
+        if !process.is_marking() {
+            self.prepare_incremental_collection(process);
+        }
+
+        let finished = {
+            let mark = process.incremental_mark_mut().as_mut().unwrap();
+
+            mark.slices += 1;
+            mark.trace(Some(deadline))
+        };
+
+        if finished {
+            let mark = self.finish_incremental_collection(process);
+            let duration = time::precise_time_ns() - start_time;
+
+            println!("Finished GC (incremental: {} slices) with a final \
+                      pause of {} ms, {} marked",
+                     mark.slices,
+                     (duration as f64) / 1000000.0,
+                     mark.marked);
+        }
+
+        request.thread.reschedule(request.process.clone());
+    }
+
+    /// Prepares the heap of a process for an incremental collection.
+    fn prepare_incremental_collection(&self, process: &RcProcess) {
+        let mut roots: Vec<ObjectPointer> =
+            process.roots().iter().map(|pointer| *pointer.get()).collect();
+
+        {
+            let mut local_data = process.local_data_mut();
+
+            roots.extend(local_data.remembered_set.iter().cloned());
+
+            let ref mut allocator = local_data.allocator;
+
+            for bucket in allocator.young_generation.iter_mut() {
+                for block in bucket.blocks
+                    .iter_mut()
+                    .chain(bucket.recyclable_blocks.iter_mut()) {
+                    block.reset_bitmaps();
+                }
+            }
+
+            for block in allocator.mature_generation.blocks.iter_mut() {
+                block.reset_bitmaps();
+            }
+
+            // The line maps of existing blocks are incomplete until marking
+            // has finished, so the process has to allocate into new blocks in
+            // between slices.
+            let global_allocator = allocator.global_allocator();
+
+            allocator.eden_space_mut()
+                .allocate_into_new_blocks(&global_allocator);
+        }
+
+        *process.incremental_mark_mut() = Some(IncrementalMark::new(roots));
+    }
+
+    /// Finishes an incremental collection and reclaims unused blocks.
+    ///
+    /// The roots are traced once more as they may refer to objects allocated
+    /// in between slices. This final trace is not bounded by the pause budget.
+    fn finish_incremental_collection(&self,
+                                     process: &RcProcess)
+                                     -> IncrementalMark {
+        let mut mark = process.incremental_mark_mut().take().unwrap();
+
+        for pointer in process.roots() {
+            mark.push(*pointer.get());
+        }
+
+        {
+            let mut remembered_set = process.remembered_set_mut();
+
+            for pointer in remembered_set.iter() {
+                mark.push(*pointer);
+            }
+
+            mark.trace(None);
+
+            remembered_set.clear();
+        }
+
+        process.increment_young_ages();
+
+        self.update_collection_thresholds(process, false);
+        self.reclaim_blocks(process, false);
+
+        process.collected(false);
+
+        mark
+    }
+
+    /// Collects the mailbox heap of a process.
+    ///
+    /// The mailbox heap is collected while holding the lock of the mailbox
+    /// allocator, which prevents messages from being sent or received during
+    /// the collection. As a result there's no need to suspend the process.
+    fn collect_mailbox(&self, process: &RcProcess) {
+        // Terminated processes release the mailbox heap themselves, unlike the
+        // local heap there's no pending request that may still be using it.
+        if !process.is_alive() {
+            return;
+        }
+
+        let start_time = time::precise_time_ns();
+
+        let marked = {
+            let mut allocator = unlock!(process.mailbox_allocator);
+
+            allocator.prepare_for_collection();
+
+            let marked = self.mark_mailbox(process);
+
+            allocator.reclaim_blocks();
+
+            marked
+        };
+
+        let duration = time::precise_time_ns() - start_time;
+
+        println!("Finished GC (mailbox) in {} ms, {} marked",
+                 (duration as f64) / 1000000.0,
+                 marked);
+    }
+
+    /// Marks all reachable objects in the mailbox heap.
+    ///
+    /// Objects in the mailbox heap are not moved, thus only the number of
+    /// marked objects is returned.
+    fn mark_mailbox(&self, process: &RcProcess) -> usize {
+        let roots = process.mailbox_roots();
+        let mut objects: Vec<_> =
+            roots.iter().map(|pointer| pointer.pointer()).collect();
+
+        let mut marked = 0;
+
+        while let Some(pointer_pointer) = objects.pop() {
+            let pointer = pointer_pointer.get();
+
+            // Messages may refer to permanent objects, which are not part of
+            // the mailbox heap.
+            if !pointer.get().generation().is_mailbox() || pointer.is_marked() {
+                continue;
+            }
+
+            pointer.mark();
+
+            marked += 1;
+
+            pointer.get().push_pointers(&mut objects);
+        }
+
+        marked
+    }
+
     fn prepare_collection(&self, process: &RcProcess, mature: bool) {
         let mut local_data = process.local_data_mut();
 
@@ -93,9 +319,13 @@ impl Thread {
 
     /// Reclaims any unused blocks.
     fn reclaim_blocks(&self, process: &RcProcess, mature: bool) {
-        let mut local_data = process.local_data_mut();
+        process.local_data_mut().allocator.reclaim_blocks(mature);
 
-        local_data.allocator.reclaim_blocks(mature);
+        // Finding the frozen heaps still in use requires tracing the entire
+        // heap, which we only do when collecting the mature generation.
+        if mature {
+            process.release_unused_frozen_heaps();
+        }
     }
 
     /// Marks all reachable objects.
@@ -126,12 +356,12 @@ impl Thread {
             match pointer.status() {
                 ObjectStatus::Resolve => pointer.resolve_forwarding_pointer(),
                 ObjectStatus::Promote => {
-                    self.promote_mature(process, pointer);
+                    promote_mature(process, pointer);
 
                     promoted += 1;
                 }
                 ObjectStatus::Evacuate => {
-                    self.evacuate(process, pointer);
+                    evacuate(process, pointer);
 
                     evacuated += 1;
                 }
@@ -152,45 +382,36 @@ impl Thread {
         (marked, evacuated, promoted)
     }
 
-    /// Promotes an object to the mature generation.
-    ///
-    /// The pointer to promote is updated to point to the new location.
-    fn promote_mature(&self, process: &RcProcess, pointer: &mut ObjectPointer) {
-        pointer.unmark_for_finalization();
-
-        let mut local_data = process.local_data_mut();
-        let mut old_obj = pointer.get_mut();
-        let mut new_obj = old_obj.take();
-
-        new_obj.set_mature();
-
-        let new_pointer = local_data.allocator.allocate_mature(new_obj);
-
-        old_obj.forward_to(new_pointer);
-
-        pointer.resolve_forwarding_pointer();
+    /// Returns true if the heap of a process should be marked using multiple
+    /// threads.
+    fn mark_in_parallel(&self, process: &RcProcess) -> bool {
+        self.vm_state.config.mark_threads > 1 &&
+        process.local_data().allocator.young_block_count() >=
+        PARALLEL_MARK_THRESHOLD
     }
 
-    // Evacuates a pointer.
-    //
-    // The pointer to evacuate is updated to point to the new location.
-    fn evacuate(&self, process: &RcProcess, pointer: &mut ObjectPointer) {
-        pointer.unmark_for_finalization();
+    /// Marks all reachable objects using multiple threads.
+    ///
+    /// The return value is the same as the return value of `mark()`.
+    fn mark_parallel(&self, process: &RcProcess) -> (usize, usize, usize) {
+        let mut roots = process.roots();
+        let mut remembered_set = process.remembered_set_mut();
 
-        // When evacuating an object we must ensure we evacuate the object into
-        // the same bucket.
-        let local_data = process.local_data_mut();
-        let mut bucket = pointer.block_mut().bucket_mut().unwrap();
+        for pointer in remembered_set.iter() {
+            roots.push(pointer.pointer());
+        }
 
-        let mut old_obj = pointer.get_mut();
-        let new_obj = old_obj.take();
+        let mark = ParallelMark::new(process.clone(),
+                                     self.vm_state.config.mark_threads);
 
-        let (_, new_pointer) =
-            bucket.allocate(&local_data.allocator.global_allocator, new_obj);
+        let result =
+            ParallelMark::run(mark, roots, &self.vm_state.mark_requests);
 
-        old_obj.forward_to(new_pointer);
+        // Just like mark() we can only clear the remembered set once all
+        // objects have been traced.
+        remembered_set.clear();
 
-        pointer.resolve_forwarding_pointer();
+        result
     }
 
     fn update_collection_thresholds(&self, process: &RcProcess, mature: bool) {
@@ -204,8 +425,49 @@ impl Thread {
     }
 }
 
+/// Promotes an object to the mature generation.
+///
+/// The pointer to promote is updated to point to the new location.
+pub fn promote_mature(process: &RcProcess, pointer: &mut ObjectPointer) {
+    pointer.unmark_for_finalization();
+
+    let mut local_data = process.local_data_mut();
+    let mut old_obj = pointer.get_mut();
+    let mut new_obj = old_obj.take();
+
+    new_obj.set_mature();
+
+    let new_pointer = local_data.allocator.allocate_mature(new_obj);
+
+    old_obj.forward_to(new_pointer);
+
+    pointer.resolve_forwarding_pointer();
+}
+
+/// Evacuates a pointer.
+///
+/// The pointer to evacuate is updated to point to the new location.
+pub fn evacuate(process: &RcProcess, pointer: &mut ObjectPointer) {
+    pointer.unmark_for_finalization();
+
+    // When evacuating an object we must ensure we evacuate the object into
+    // the same bucket.
+    let local_data = process.local_data_mut();
+    let mut bucket = pointer.block_mut().bucket_mut().unwrap();
+
+    let mut old_obj = pointer.get_mut();
+    let new_obj = old_obj.take();
+
+    let (_, new_pointer) =
+        bucket.allocate(&local_data.allocator.global_allocator, new_obj);
+
+    old_obj.forward_to(new_pointer);
+
+    pointer.resolve_forwarding_pointer();
+}
+
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use super::*;
     use compiled_code::CompiledCode;
     use config::Config;
@@ -215,13 +477,17 @@ mod tests {
     use object::Object;
     use object_value;
     use process::{Process, RcProcess};
+    use thread::Thread as VmThread;
     use virtual_machine::{VirtualMachineState, RcVirtualMachineState};
 
     fn vm_state() -> RcVirtualMachineState {
         VirtualMachineState::new(Config::new())
     }
 
-    fn process() -> (PermanentAllocator, RcProcess) {
+    /// Returns a new process, along with the allocator of its "self" object.
+    ///
+    /// This function is also used by the tests of other GC modules.
+    pub fn process() -> (PermanentAllocator, RcProcess) {
         let global_alloc = GlobalAllocator::without_preallocated_blocks();
         let mut perm_alloc = PermanentAllocator::new(global_alloc.clone());
         let self_obj = perm_alloc.allocate_empty();
@@ -238,6 +504,188 @@ mod tests {
         Thread::new(vm_state())
     }
 
+    fn incremental_gc_thread() -> Thread {
+        let mut config = Config::new();
+
+        config.set_gc_pause_budget(1_000_000);
+
+        Thread::new(VirtualMachineState::new(config))
+    }
+
+    #[test]
+    fn test_process_request_with_terminated_process() {
+        let (_perm_alloc, process) = process();
+        let thread = gc_thread();
+        let global_alloc = process.local_data().allocator.global_allocator();
+
+        process.allocate_empty();
+        process.finished();
+
+        let request = Request::new(Generation::Young,
+                                   VmThread::new(false, None),
+                                   process.clone());
+
+        thread.process_request(request);
+
+        assert_eq!(unlock!(global_alloc.blocks).len(), 1);
+    }
+
+    #[test]
+    fn test_process_request_mailbox() {
+        let (_perm_alloc, process) = process();
+        let thread = gc_thread();
+        let global_alloc = process.local_data().allocator.global_allocator();
+
+        // Fill the first block with garbage, ensuring the message ends up in
+        // the second block.
+        {
+            let mut allocator = unlock!(process.mailbox_allocator);
+
+            for _ in 0..OBJECTS_PER_BLOCK {
+                allocator.allocate(Object::new(object_value::none()));
+            }
+        }
+
+        let message =
+            process.delay_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(unlock!(process.mailbox_allocator).block_count(), 2);
+
+        let request = Request::new(Generation::Mailbox,
+                                   VmThread::new(false, None),
+                                   process.clone());
+
+        thread.process_request(request);
+
+        assert!(message.is_marked());
+        assert_eq!(unlock!(process.mailbox_allocator).block_count(), 1);
+        assert_eq!(unlock!(global_alloc.blocks).len(), 1);
+    }
+
+    #[test]
+    fn test_mark_mailbox() {
+        let (_perm_alloc, process) = process();
+        let thread = gc_thread();
+
+        let message = process.allocate_empty();
+        let child = process.allocate_empty();
+
+        message.get_mut().add_attribute("child".to_string(), child);
+
+        process.send_message(message).unwrap();
+        process.send_message(process.allocate_empty()).unwrap();
+
+        unlock!(process.mailbox_allocator).prepare_for_collection();
+
+        assert_eq!(thread.mark_mailbox(&process), 3);
+    }
+
+    #[test]
+    fn test_process_request_incremental() {
+        let (_perm_alloc, process) = process();
+        let thread = incremental_gc_thread();
+        let pointer = process.allocate_empty();
+
+        process.set_register(0, pointer);
+
+        let request = Request::new(Generation::Young,
+                                   VmThread::new(false, None),
+                                   process.clone());
+
+        thread.process_request(request);
+
+        assert!(pointer.is_marked());
+        assert_eq!(process.is_marking(), false);
+        assert_eq!(process.heap_info().young_collections, 1);
+    }
+
+    #[test]
+    fn test_collect_incrementally() {
+        let (_perm_alloc, process) = process();
+
+        assert_eq!(gc_thread().collect_incrementally(&process, false), false);
+
+        let thread = incremental_gc_thread();
+
+        assert!(thread.collect_incrementally(&process, false));
+        assert_eq!(thread.collect_incrementally(&process, true), false);
+
+        process.local_data_mut().allocator.young_generation[0].promote = true;
+
+        assert_eq!(thread.collect_incrementally(&process, false), false);
+    }
+
+    #[test]
+    fn test_prepare_incremental_collection() {
+        let (_perm_alloc, process) = process();
+        let thread = incremental_gc_thread();
+        let pointer = process.allocate_empty();
+
+        pointer.mark();
+        process.set_register(0, pointer);
+
+        thread.prepare_incremental_collection(&process);
+
+        assert!(process.is_marking());
+        assert_eq!(pointer.is_marked(), false);
+
+        let mut local_data = process.local_data_mut();
+        let eden = local_data.allocator.eden_space_mut();
+
+        assert!(eden.new_blocks_only);
+        assert_eq!(eden.blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_finish_incremental_collection() {
+        let (_perm_alloc, process) = process();
+        let thread = incremental_gc_thread();
+
+        thread.prepare_incremental_collection(&process);
+
+        // Objects allocated and written in between slices must survive the
+        // collection.
+        let pointer = process.allocate_empty();
+
+        process.set_register(0, pointer);
+
+        let mark = thread.finish_incremental_collection(&process);
+
+        assert!(pointer.is_marked());
+        assert!(mark.marked >= 1);
+        assert_eq!(process.is_marking(), false);
+        assert_eq!(process.heap_info().young_collections, 1);
+    }
+
+    #[test]
+    fn test_mark_in_parallel_with_small_heap() {
+        let (_perm_alloc, process) = process();
+        let thread = gc_thread();
+
+        process.allocate_empty();
+
+        assert_eq!(thread.mark_in_parallel(&process), false);
+    }
+
+    #[test]
+    fn test_mark_parallel() {
+        let (_perm_alloc, process) = process();
+        let thread = gc_thread();
+        let pointer = process.allocate_empty();
+
+        process.set_register(0, pointer);
+
+        thread.prepare_collection(&process, false);
+
+        let (marked, evacuated, promoted) = thread.mark_parallel(&process);
+
+        // The object in the register and the "self" object of the binding.
+        assert_eq!(marked, 2);
+        assert_eq!(evacuated, 0);
+        assert_eq!(promoted, 0);
+        assert!(pointer.is_marked());
+    }
+
     #[test]
     fn test_prepare_collection() {
         let (_perm_alloc, process) = process();
@@ -4,6 +4,13 @@
 //! in use. An ObjectMap is used for marking objects and can hold at most 1024
 //! entries while a LineMap is used for marking lines and can hold at most 256
 //! entries.
+//!
+//! The entries of a bitmap are stored as atomic booleans. This allows
+//! multiple GC threads to mark objects in the same block at the same time
+//! using `set_atomic`.
+
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// The number of entries in an object map.
 const OBJECT_ENTRIES: usize = 1024;
@@ -12,17 +19,16 @@ const OBJECT_ENTRIES: usize = 1024;
 const LINE_ENTRIES: usize = 256;
 
 pub struct ObjectMap {
-    values: [bool; OBJECT_ENTRIES],
+    values: [AtomicBool; OBJECT_ENTRIES],
 }
 
 pub struct LineMap {
-    values: [bool; LINE_ENTRIES],
+    values: [AtomicBool; LINE_ENTRIES],
 }
 
 pub trait Bitmap {
     fn max_entries(&self) -> usize;
-    fn values(&self) -> &[bool];
-    fn values_mut(&mut self) -> &mut [bool];
+    fn values(&self) -> &[AtomicBool];
 
     /// Sets the given index in the bitmap.
     ///
@@ -32,7 +38,22 @@ pub trait Bitmap {
     ///
     ///     bitmap.set(4);
     fn set(&mut self, index: usize) {
-        self.values_mut()[index] = true;
+        self.values()[index].store(true, Ordering::Relaxed);
+    }
+
+    /// Atomically sets the given index in the bitmap.
+    ///
+    /// The return value is `true` if the index was not yet set, meaning the
+    /// caller is the one that set it.
+    ///
+    /// # Examples
+    ///
+    ///     let bitmap = ObjectMap::new();
+    ///
+    ///     bitmap.set_atomic(4); // => true
+    ///     bitmap.set_atomic(4); // => false
+    fn set_atomic(&self, index: usize) -> bool {
+        !self.values()[index].swap(true, Ordering::AcqRel)
     }
 
     /// Unsets the given index in the bitmap.
@@ -44,7 +65,7 @@ pub trait Bitmap {
     ///     bitmap.set(4);
     ///     bitmap.unset(4);
     fn unset(&mut self, index: usize) {
-        self.values_mut()[index] = false;
+        self.values()[index].store(false, Ordering::Relaxed);
     }
 
     /// Returns `true` if a given index is set.
@@ -59,15 +80,13 @@ pub trait Bitmap {
     ///
     ///     bitmap.is_set(1); // => true
     fn is_set(&self, index: usize) -> bool {
-        self.values()[index]
+        self.values()[index].load(Ordering::Acquire)
     }
 
     /// Returns `true` if the bitmap is full, `false` otherwise
     fn is_full(&self) -> bool {
-        let empty = false;
-
         for value in self.values().iter() {
-            if value == &empty {
+            if !value.load(Ordering::Acquire) {
                 return false;
             }
         }
@@ -77,10 +96,8 @@ pub trait Bitmap {
 
     /// Returns true if the bitmap is empty.
     fn is_empty(&self) -> bool {
-        let set = true;
-
         for value in self.values().iter() {
-            if value == &set {
+            if value.load(Ordering::Acquire) {
                 return false;
             }
         }
@@ -97,11 +114,10 @@ pub trait Bitmap {
 
     /// The number of indexes set in the bitmap.
     fn len(&self) -> usize {
-        let set = true;
         let mut count = 0;
 
         for value in self.values().iter() {
-            if value == &set {
+            if value.load(Ordering::Acquire) {
                 count += 1;
             }
         }
@@ -113,28 +129,26 @@ pub trait Bitmap {
 impl ObjectMap {
     /// Returns a new, empty object bitmap.
     pub fn new() -> ObjectMap {
-        ObjectMap { values: [false; OBJECT_ENTRIES] }
+        // AtomicBool has the same in-memory representation as bool.
+        ObjectMap {
+            values: unsafe { mem::transmute([false; OBJECT_ENTRIES]) },
+        }
     }
 }
 
 impl LineMap {
     /// Returns a new, empty line bitmap.
     pub fn new() -> LineMap {
-        LineMap { values: [false; LINE_ENTRIES] }
+        LineMap { values: unsafe { mem::transmute([false; LINE_ENTRIES]) } }
     }
 }
 
 impl Bitmap for ObjectMap {
     #[inline(always)]
-    fn values(&self) -> &[bool] {
+    fn values(&self) -> &[AtomicBool] {
         &self.values
     }
 
-    #[inline(always)]
-    fn values_mut(&mut self) -> &mut [bool] {
-        &mut self.values
-    }
-
     fn max_entries(&self) -> usize {
         OBJECT_ENTRIES
     }
@@ -142,15 +156,10 @@ impl Bitmap for ObjectMap {
 
 impl Bitmap for LineMap {
     #[inline(always)]
-    fn values(&self) -> &[bool] {
+    fn values(&self) -> &[AtomicBool] {
         &self.values
     }
 
-    #[inline(always)]
-    fn values_mut(&mut self) -> &mut [bool] {
-        &mut self.values
-    }
-
     fn max_entries(&self) -> usize {
         LINE_ENTRIES
     }
@@ -170,6 +179,15 @@ mod tests {
         assert!(object_map.is_set(1));
     }
 
+    #[test]
+    fn test_object_map_set_atomic() {
+        let object_map = ObjectMap::new();
+
+        assert!(object_map.set_atomic(1));
+        assert_eq!(object_map.set_atomic(1), false);
+        assert!(object_map.is_set(1));
+    }
+
     #[test]
     fn test_object_map_unset() {
         let mut object_map = ObjectMap::new();
@@ -244,6 +262,15 @@ mod tests {
         assert!(line_map.is_set(1));
     }
 
+    #[test]
+    fn test_line_map_set_atomic() {
+        let line_map = LineMap::new();
+
+        assert!(line_map.set_atomic(1));
+        assert_eq!(line_map.set_atomic(1), false);
+        assert!(line_map.is_set(1));
+    }
+
     #[test]
     fn test_line_map_unset() {
         let mut line_map = LineMap::new();
@@ -40,6 +40,10 @@ pub struct Bucket {
 
     /// The objects in this bucket should be promoted to the mature generation.
     pub promote: bool,
+
+    /// When set, objects are only allocated into new blocks instead of
+    /// recyclable blocks.
+    pub new_blocks_only: bool,
 }
 
 unsafe impl Send for Bucket {}
@@ -59,6 +63,7 @@ impl Bucket {
             available_histogram: Histogram::new(),
             mark_histogram: Histogram::new(),
             promote: false,
+            new_blocks_only: false,
         }
     }
 
@@ -87,6 +92,11 @@ impl Bucket {
         }
     }
 
+    /// Returns the number of blocks in this bucket.
+    pub fn block_count(&self) -> usize {
+        self.blocks.len() + self.recyclable_blocks.len()
+    }
+
     pub fn add_block(&mut self, block: Box<Block>) {
         self.current_block = &*block as *const Block as *mut Block;
 
@@ -124,7 +134,13 @@ impl Bucket {
         let found_hole = self.find_available_hole();
 
         if !found_hole {
-            if let Some(block) = self.recyclable_blocks.pop() {
+            let recycled = if self.new_blocks_only {
+                None
+            } else {
+                self.recyclable_blocks.pop()
+            };
+
+            if let Some(block) = recycled {
                 self.add_block(block);
             } else {
                 self.add_block(global_allocator.request_block());
@@ -134,6 +150,18 @@ impl Bucket {
         (!found_hole, self.current_block_mut().unwrap().bump_allocate(object))
     }
 
+    /// Starts allocating objects into new blocks only.
+    ///
+    /// This is used while an incremental collection rebuilds the line maps of
+    /// the existing blocks, as these line maps can't be used for finding holes
+    /// until marking has finished. Recycling blocks is enabled again when
+    /// reclaiming blocks.
+    pub fn allocate_into_new_blocks(&mut self,
+                                    global_allocator: &RcGlobalAllocator) {
+        self.new_blocks_only = true;
+        self.add_block(global_allocator.request_block());
+    }
+
     /// Returns true if this bucket contains blocks that need to be evacuated.
     pub fn should_evacuate(&self) -> bool {
         if self.recyclable_blocks.len() > 0 {
@@ -196,10 +224,29 @@ impl Bucket {
         // At this point "self.blocks" only contains either full or fragmented
         // blocks.
         self.current_block = ptr::null::<Block>() as *mut Block;
+        self.new_blocks_only = false;
 
         reclaim
     }
 
+    /// Removes all blocks from this bucket.
+    ///
+    /// The blocks are reset so they can be re-used by other allocators.
+    pub fn take_blocks(&mut self) -> Vec<Box<Block>> {
+        let mut blocks = Vec::new();
+
+        for mut block in self.blocks
+            .drain(0..)
+            .chain(self.recyclable_blocks.drain(0..)) {
+            block.reset();
+            blocks.push(block);
+        }
+
+        self.current_block = ptr::null::<Block>() as *mut Block;
+
+        blocks
+    }
+
     /// Prepares this bucket for a collection.
     ///
     /// Returns true if evacuation is needed for this bucket.
@@ -349,6 +396,16 @@ mod tests {
         assert!(bucket.current_block_mut().is_some());
     }
 
+    #[test]
+    fn test_block_count() {
+        let mut bucket = Bucket::new();
+
+        bucket.add_block(Block::new());
+        bucket.recyclable_blocks.push(Block::new());
+
+        assert_eq!(bucket.block_count(), 2);
+    }
+
     #[test]
     fn test_add_block() {
         let mut bucket = Bucket::new();
@@ -445,6 +502,42 @@ mod tests {
                 unsafe { bucket.blocks[0].start_address().offset(5) });
     }
 
+    #[test]
+    fn test_allocate_into_new_blocks() {
+        let global_alloc = global_allocator();
+        let mut bucket = Bucket::new();
+
+        let (_, pointer) =
+            bucket.allocate(&global_alloc, Object::new(object_value::none()));
+
+        pointer.mark();
+
+        bucket.reclaim_blocks();
+        bucket.allocate_into_new_blocks(&global_alloc);
+
+        assert_eq!(bucket.blocks.len(), 1);
+        assert_eq!(bucket.recyclable_blocks.len(), 1);
+
+        // Fill up the current block, forcing the allocator to find another
+        // block.
+        {
+            let block = bucket.current_block_mut().unwrap();
+
+            block.free_pointer = block.end_pointer;
+        }
+
+        let (new_block, _) =
+            bucket.allocate(&global_alloc, Object::new(object_value::none()));
+
+        assert!(new_block);
+        assert_eq!(bucket.blocks.len(), 2);
+        assert_eq!(bucket.recyclable_blocks.len(), 1);
+
+        bucket.reclaim_blocks();
+
+        assert_eq!(bucket.new_blocks_only, false);
+    }
+
     #[test]
     fn test_should_evacuate_with_recyclable_blocks() {
         let mut bucket = Bucket::new();
@@ -504,6 +597,24 @@ mod tests {
         assert!(bucket.current_block.is_null());
     }
 
+    #[test]
+    fn test_take_blocks() {
+        let mut bucket = Bucket::new();
+
+        bucket.add_block(Block::new());
+        bucket.recyclable_blocks.push(Block::new());
+
+        bucket.blocks[0].used_lines_bitmap.set(2);
+
+        let blocks = bucket.take_blocks();
+
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].used_lines_bitmap.is_empty());
+        assert_eq!(bucket.blocks.len(), 0);
+        assert_eq!(bucket.recyclable_blocks.len(), 0);
+        assert!(bucket.current_block.is_null());
+    }
+
     #[test]
     fn test_prepare_for_collection_without_evacuation() {
         let mut bucket = Bucket::new();
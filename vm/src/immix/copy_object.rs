@@ -2,26 +2,68 @@
 //!
 //! The CopyObject trait can be implemented by allocators to support copying of
 //! objects into a heap.
+//!
+//! Files are copied by duplicating the underlying file descriptor, while
+//! bindings are copied by copying all their local variables. Duplicating a
+//! file descriptor may fail, in which case the IO error is returned.
+
+use std::collections::HashMap;
+use std::io;
 
 use object::Object;
 use object_value;
 use object_value::ObjectValue;
 use object_pointer::ObjectPointer;
 
+pub type CopyResult = Result<ObjectPointer, io::Error>;
+
+/// The objects copied so far, mapped to their copies.
+pub type CopyMap = HashMap<ObjectPointer, ObjectPointer>;
+
 pub trait CopyObject: Sized {
     /// Allocates a copied object.
     fn allocate_copy(&mut self, Object) -> ObjectPointer;
 
+    /// Keeps the frozen heap of a shared object alive for as long as this
+    /// heap may refer to it.
+    fn retain_frozen_heap(&mut self, ObjectPointer);
+
     /// Performs a deep copy of `object_ptr`
     ///
     /// The copy of the input object is allocated on the current heap.
-    fn copy_object(&mut self, to_copy_ptr: ObjectPointer) -> ObjectPointer {
+    fn copy_object(&mut self, to_copy_ptr: ObjectPointer) -> CopyResult {
+        self.copy_object_with_map(to_copy_ptr, &mut CopyMap::new())
+    }
+
+    /// Performs a deep copy of `object_ptr`, reusing the copies of objects
+    /// that were already copied.
+    ///
+    /// Objects that (indirectly) refer to themselves are copied only once,
+    /// instead of being copied until the stack overflows.
+    fn copy_object_with_map(&mut self,
+                            to_copy_ptr: ObjectPointer,
+                            copies: &mut CopyMap)
+                            -> CopyResult {
         if to_copy_ptr.is_permanent() {
-            return to_copy_ptr;
+            if to_copy_ptr.get().is_frozen() {
+                self.retain_frozen_heap(to_copy_ptr);
+            }
+
+            return Ok(to_copy_ptr);
+        }
+
+        if let Some(copy_ptr) = copies.get(&to_copy_ptr) {
+            return Ok(*copy_ptr);
         }
 
         let to_copy = to_copy_ptr.get();
 
+        // The copy is allocated before copying any referenced objects, so any
+        // references back to the input object can use the copy.
+        let copy_ptr = self.allocate_copy(Object::new(object_value::none()));
+
+        copies.insert(to_copy_ptr, copy_ptr);
+
         // Copy over the object value
         let value_copy = match to_copy.value {
             ObjectValue::None => object_value::none(),
@@ -31,44 +73,59 @@ pub trait CopyObject: Sized {
                 object_value::string(*string.clone())
             }
             ObjectValue::Array(ref raw_vec) => {
-                let new_map = raw_vec.iter()
-                    .map(|val_ptr| self.copy_object(*val_ptr));
+                let mut values = Vec::with_capacity(raw_vec.len());
 
-                object_value::array(new_map.collect::<Vec<_>>())
+                for val_ptr in raw_vec.iter() {
+                    values.push(try!(self.copy_object_with_map(*val_ptr,
+                                                               copies)));
+                }
+
+                object_value::array(values)
             }
-            ObjectValue::File(_) => {
-                panic!("ObjectValue::File can not be cloned");
+            ObjectValue::File(ref file) => {
+                object_value::file(try!(file.try_clone()))
             }
             ObjectValue::Error(num) => object_value::error(num),
             ObjectValue::CompiledCode(ref code) => {
                 object_value::compiled_code(code.clone())
             }
-            ObjectValue::Binding(_) => {
-                panic!("ObjectValue::Binding can not be cloned");
+            ObjectValue::Binding(ref binding) => {
+                object_value::binding(try!(binding.copy_to(self, copies)))
+            }
+            ObjectValue::ProcessHandle(ref handle) => {
+                object_value::process_handle(**handle)
             }
         };
 
-        let mut copy = if let Some(proto_ptr) = to_copy.prototype() {
-            let proto_copy = self.copy_object(proto_ptr);
+        {
+            let copy = copy_ptr.get_mut();
 
-            Object::with_prototype(value_copy, proto_copy)
-        } else {
-            Object::new(value_copy)
-        };
+            copy.value = value_copy;
 
-        if let Some(header) = to_copy.header() {
-            let header_copy = header.copy_to(self);
+            if let Some(proto_ptr) = to_copy.prototype() {
+                copy.set_prototype(try!(self.copy_object_with_map(proto_ptr,
+                                                                  copies)));
+            }
 
-            copy.set_header(header_copy);
+            if let Some(header) = to_copy.header() {
+                copy.set_header(try!(header.copy_to(self, copies)));
+            }
         }
 
-        self.allocate_copy(copy)
+        // The placeholder allocated above did not need to be finalized, but
+        // the copy may.
+        if copy_ptr.is_finalizable() {
+            copy_ptr.mark_for_finalization();
+        }
+
+        Ok(copy_ptr)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs::File;
     use immix::global_allocator::GlobalAllocator;
     use immix::local_allocator::LocalAllocator;
     use binding::Binding;
@@ -93,13 +150,17 @@ mod tests {
         fn allocate_copy(&mut self, object: Object) -> ObjectPointer {
             self.allocator.allocate_copy(object)
         }
+
+        fn retain_frozen_heap(&mut self, pointer: ObjectPointer) {
+            self.allocator.retain_frozen_heap(pointer)
+        }
     }
 
     #[test]
     fn test_copy_none() {
         let mut dummy = DummyAllocator::new();
         let pointer = dummy.allocator.allocate_empty();
-        let copy = dummy.copy_object(pointer);
+        let copy = dummy.copy_object(pointer).unwrap();
 
         assert!(copy.get().value.is_none());
     }
@@ -112,7 +173,7 @@ mod tests {
 
         pointer.get_mut().set_prototype(proto);
 
-        let copy = dummy.copy_object(pointer);
+        let copy = dummy.copy_object(pointer).unwrap();
 
         assert!(copy.get().prototype().is_some());
     }
@@ -125,7 +186,7 @@ mod tests {
 
         ptr1.get_mut().add_attribute("a".to_string(), ptr2);
 
-        let copy = dummy.copy_object(ptr1);
+        let copy = dummy.copy_object(ptr1).unwrap();
 
         assert!(copy.get().header().is_some());
     }
@@ -136,7 +197,7 @@ mod tests {
         let pointer = dummy.allocator
             .allocate_without_prototype(object_value::integer(5));
 
-        let copy = dummy.copy_object(pointer);
+        let copy = dummy.copy_object(pointer).unwrap();
 
         assert!(copy.get().value.is_integer());
         assert_eq!(copy.get().value.as_integer(), 5);
@@ -148,7 +209,7 @@ mod tests {
         let pointer = dummy.allocator
             .allocate_without_prototype(object_value::float(2.5));
 
-        let copy = dummy.copy_object(pointer);
+        let copy = dummy.copy_object(pointer).unwrap();
 
         assert!(copy.get().value.is_float());
         assert_eq!(copy.get().value.as_float(), 2.5);
@@ -160,7 +221,7 @@ mod tests {
         let pointer = dummy.allocator
             .allocate_without_prototype(object_value::string("a".to_string()));
 
-        let copy = dummy.copy_object(pointer);
+        let copy = dummy.copy_object(pointer).unwrap();
 
         assert!(copy.get().value.is_string());
         assert_eq!(copy.get().value.as_string(), &"a".to_string());
@@ -174,7 +235,7 @@ mod tests {
         let array = dummy.allocator
             .allocate_without_prototype(object_value::array(vec![ptr1, ptr2]));
 
-        let copy = dummy.copy_object(array);
+        let copy = dummy.copy_object(array).unwrap();
 
         assert!(copy.get().value.is_array());
         assert_eq!(copy.get().value.as_array().len(), 2);
@@ -186,7 +247,7 @@ mod tests {
         let ptr = dummy.allocator
             .allocate_without_prototype(object_value::error(2));
 
-        let copy = dummy.copy_object(ptr);
+        let copy = dummy.copy_object(ptr).unwrap();
 
         assert!(copy.get().value.is_error());
     }
@@ -202,20 +263,58 @@ mod tests {
         let ptr = dummy.allocator
             .allocate_without_prototype(object_value::compiled_code(cc));
 
-        let copy = dummy.copy_object(ptr);
+        let copy = dummy.copy_object(ptr).unwrap();
 
         assert!(copy.get().value.is_compiled_code());
     }
 
     #[test]
-    #[should_panic]
+    #[cfg(not(platform = "windows"))]
+    fn test_copy_file() {
+        let mut dummy = DummyAllocator::new();
+        let file = File::open("/dev/null").unwrap();
+        let pointer = dummy.allocator
+            .allocate_without_prototype(object_value::file(file));
+
+        let copy = dummy.copy_object(pointer).unwrap();
+
+        assert!(copy.get().value.is_file());
+    }
+
+    #[test]
     fn test_copy_binding() {
         let mut dummy = DummyAllocator::new();
         let self_obj = dummy.allocator.allocate_empty();
+        let local = dummy.allocator
+            .allocate_without_prototype(object_value::integer(5));
+
         let binding = Binding::new(self_obj);
+
+        binding.set_local(0, local);
+
         let pointer = dummy.allocator
             .allocate_without_prototype(object_value::binding(binding));
 
-        dummy.copy_object(pointer);
+        let copy = dummy.copy_object(pointer).unwrap();
+        let copy_binding = copy.get().value.as_binding();
+        let copy_local = copy_binding.get_local(0).unwrap();
+
+        assert!(copy_local != local);
+        assert_eq!(copy_local.get().value.as_integer(), 5);
+        assert!(copy_binding.self_object() != self_obj);
+    }
+
+    #[test]
+    fn test_copy_cyclic_array() {
+        let mut dummy = DummyAllocator::new();
+        let array = dummy.allocator
+            .allocate_without_prototype(object_value::array(Vec::new()));
+
+        array.get_mut().value.as_array_mut().push(array);
+
+        let copy = dummy.copy_object(array).unwrap();
+
+        assert!(copy != array);
+        assert!(copy.get().value.as_array()[0] == copy);
     }
 }
@@ -0,0 +1,257 @@
+//! Allocator for frozen objects shared between processes
+//!
+//! Freezing an object copies it into a heap of its own. Objects in this heap
+//! are tagged as permanent, thus they are never moved or reclaimed by the
+//! garbage collector of a process, and copying them (e.g. when sending them as
+//! a message) simply returns the original pointer.
+//!
+//! Every heap referring to a frozen object keeps the heap of that object
+//! alive. Once no heap refers to a frozen heap any longer its blocks are
+//! returned to the global allocator.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Drop;
+use std::sync::{Arc, Mutex};
+
+use immix::bucket::Bucket;
+use immix::copy_object::{CopyObject, CopyResult};
+use immix::global_allocator::{GlobalAllocator, RcGlobalAllocator};
+
+use object::Object;
+use object_pointer::ObjectPointer;
+
+pub type RcFrozenAllocator = Arc<FrozenAllocator>;
+
+pub struct FrozenAllocator {
+    global_allocator: RcGlobalAllocator,
+    bucket: Mutex<Bucket>,
+
+    /// The frozen heaps referred to by the objects in this heap.
+    frozen_heaps: Mutex<FrozenHeaps>,
+}
+
+/// The frozen heaps kept alive by the heap that owns this structure.
+pub struct FrozenHeaps {
+    heaps: HashMap<usize, RcFrozenAllocator>,
+}
+
+impl FrozenAllocator {
+    pub fn new(global_allocator: RcGlobalAllocator) -> RcFrozenAllocator {
+        let allocator = Arc::new(FrozenAllocator {
+            global_allocator: global_allocator.clone(),
+            bucket: Mutex::new(Bucket::new()),
+            frozen_heaps: Mutex::new(FrozenHeaps::new()),
+        });
+
+        global_allocator.add_frozen_heap(&allocator);
+
+        allocator
+    }
+
+    /// Returns the address used for looking up the heap of a frozen object.
+    pub fn address(&self) -> usize {
+        &*unlock!(self.bucket) as *const Bucket as usize
+    }
+
+    /// Freezes an object graph and copies it into this heap.
+    ///
+    /// Objects that already reside in a frozen heap are not copied again.
+    pub fn freeze(&self, pointer: ObjectPointer) -> CopyResult {
+        pointer.freeze();
+
+        let mut allocator = self;
+
+        allocator.copy_object(pointer)
+    }
+
+    /// Returns the number of blocks used by this heap.
+    pub fn block_count(&self) -> usize {
+        unlock!(self.bucket).block_count()
+    }
+
+    /// Returns the number of frozen heaps kept alive by this heap.
+    pub fn frozen_heap_count(&self) -> usize {
+        unlock!(self.frozen_heaps).len()
+    }
+}
+
+impl<'a> CopyObject for &'a FrozenAllocator {
+    fn allocate_copy(&mut self, object: Object) -> ObjectPointer {
+        let (_, pointer) =
+            unlock!(self.bucket).allocate(&self.global_allocator, object);
+
+        pointer.get_mut().set_permanent();
+
+        pointer
+    }
+
+    fn retain_frozen_heap(&mut self, pointer: ObjectPointer) {
+        unlock!(self.frozen_heaps).retain(&self.global_allocator, pointer);
+    }
+}
+
+impl Drop for FrozenAllocator {
+    fn drop(&mut self) {
+        self.global_allocator.remove_frozen_heap(self.address());
+
+        for block in unlock!(self.bucket).take_blocks() {
+            self.global_allocator.add_block(block);
+        }
+    }
+}
+
+impl FrozenHeaps {
+    pub fn new() -> Self {
+        FrozenHeaps { heaps: HashMap::new() }
+    }
+
+    /// Keeps alive the frozen heap the given object resides in, if any.
+    pub fn retain(&mut self,
+                  global_allocator: &GlobalAllocator,
+                  pointer: ObjectPointer) {
+        if let Some(address) = frozen_heap_address(pointer) {
+            if self.heaps.contains_key(&address) {
+                return;
+            }
+
+            if let Some(heap) = global_allocator.frozen_heap(address) {
+                self.heaps.insert(address, heap);
+            }
+        }
+    }
+
+    /// Keeps the given frozen heap alive.
+    pub fn retain_heap(&mut self, heap: RcFrozenAllocator) {
+        self.heaps.insert(heap.address(), heap);
+    }
+
+    /// Releases all frozen heaps, except for those with the given addresses.
+    pub fn retain_only(&mut self, addresses: &HashSet<usize>) {
+        let released: Vec<usize> = self.heaps
+            .keys()
+            .filter(|address| !addresses.contains(address))
+            .cloned()
+            .collect();
+
+        for address in released {
+            self.heaps.remove(&address);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.heaps.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.heaps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heaps.is_empty()
+    }
+}
+
+/// Returns the address of the frozen heap the given object may reside in.
+///
+/// Only frozen permanent objects can reside in a frozen heap. Whether the
+/// object actually does is determined by looking up the returned address
+/// using `GlobalAllocator::frozen_heap()`.
+pub fn frozen_heap_address(pointer: ObjectPointer) -> Option<usize> {
+    if pointer.is_permanent() && pointer.get().is_frozen() {
+        Some(pointer.block().bucket as usize)
+    } else {
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use immix::copy_object::CopyObject;
+    use immix::global_allocator::GlobalAllocator;
+    use immix::local_allocator::LocalAllocator;
+    use object_value;
+
+    #[test]
+    fn test_freeze() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let mut local_alloc = LocalAllocator::new(global_alloc.clone());
+        let heap = FrozenAllocator::new(global_alloc);
+        let original = local_alloc.allocate_empty();
+        let frozen = heap.freeze(original).unwrap();
+
+        assert!(frozen != original);
+        assert!(frozen.is_permanent());
+        assert!(frozen.get().is_frozen());
+        assert!(original.get().is_frozen());
+        assert_eq!(heap.block_count(), 1);
+    }
+
+    #[test]
+    fn test_freeze_with_frozen_object() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let mut local_alloc = LocalAllocator::new(global_alloc.clone());
+        let heap1 = FrozenAllocator::new(global_alloc.clone());
+        let heap2 = FrozenAllocator::new(global_alloc);
+        let shared = heap1.freeze(local_alloc.allocate_empty()).unwrap();
+        let array = local_alloc
+            .allocate_without_prototype(object_value::array(vec![shared]));
+
+        let frozen = heap2.freeze(array).unwrap();
+
+        assert!(frozen.get().value.as_array()[0] == shared);
+        assert_eq!(heap2.frozen_heap_count(), 1);
+    }
+
+    #[test]
+    fn test_copy_frozen_object() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let mut local_alloc = LocalAllocator::new(global_alloc.clone());
+        let heap = FrozenAllocator::new(global_alloc.clone());
+        let frozen = heap.freeze(local_alloc.allocate_empty()).unwrap();
+        let mut other_alloc = LocalAllocator::new(global_alloc);
+
+        assert!(other_alloc.copy_object(frozen).unwrap() == frozen);
+        assert_eq!(other_alloc.frozen_heaps.len(), 1);
+    }
+
+    #[test]
+    fn test_drop() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let mut local_alloc = LocalAllocator::new(global_alloc.clone());
+        let heap = FrozenAllocator::new(global_alloc.clone());
+        let frozen = heap.freeze(local_alloc.allocate_empty()).unwrap();
+
+        let mut other_alloc = LocalAllocator::new(global_alloc.clone());
+
+        other_alloc.copy_object(frozen).unwrap();
+
+        // The heap is kept alive by the allocator referring to its objects.
+        drop(heap);
+
+        assert_eq!(unlock!(global_alloc.blocks).len(), 0);
+
+        other_alloc.frozen_heaps.clear();
+
+        assert_eq!(unlock!(global_alloc.blocks).len(), 1);
+    }
+
+    #[test]
+    fn test_frozen_heaps_retain_only() {
+        let global_alloc = GlobalAllocator::without_preallocated_blocks();
+        let heap1 = FrozenAllocator::new(global_alloc.clone());
+        let heap2 = FrozenAllocator::new(global_alloc);
+        let mut heaps = FrozenHeaps::new();
+        let mut used = HashSet::new();
+
+        used.insert(heap1.address());
+
+        heaps.retain_heap(heap1);
+        heaps.retain_heap(heap2);
+        heaps.retain_only(&used);
+
+        assert_eq!(heaps.len(), 1);
+    }
+}
@@ -3,9 +3,14 @@
 //! The global allocator is used by process-local allocators to request the
 //! allocation of new blocks or the re-using of existing (and returned) free
 //! blocks.
-use std::sync::{Arc, Mutex};
+//!
+//! The global allocator also keeps track of all frozen heaps, allowing heaps
+//! that refer to frozen objects to keep the heaps of these objects alive.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
 
 use immix::block::{Block, BLOCK_SIZE};
+use immix::frozen_allocator::{FrozenAllocator, RcFrozenAllocator};
 
 /// The number of blocks to pre-allocate.
 pub const PRE_ALLOCATE_BLOCKS: usize = (1 * 1024 * 1024) / BLOCK_SIZE;
@@ -15,6 +20,9 @@ pub type RcGlobalAllocator = Arc<GlobalAllocator>;
 /// Structure used for storing the state of the global allocator.
 pub struct GlobalAllocator {
     pub blocks: Mutex<Vec<Box<Block>>>,
+
+    /// All frozen heaps that are still alive, using their addresses as keys.
+    pub frozen_heaps: Mutex<HashMap<usize, Weak<FrozenAllocator>>>,
 }
 
 impl GlobalAllocator {
@@ -26,12 +34,18 @@ impl GlobalAllocator {
             blocks.push(Block::new());
         }
 
-        Arc::new(GlobalAllocator { blocks: Mutex::new(blocks) })
+        Arc::new(GlobalAllocator {
+            blocks: Mutex::new(blocks),
+            frozen_heaps: Mutex::new(HashMap::new()),
+        })
     }
 
     /// Creates a new global allocator without pre-allocating any blocks.
     pub fn without_preallocated_blocks() -> RcGlobalAllocator {
-        Arc::new(GlobalAllocator { blocks: Mutex::new(Vec::new()) })
+        Arc::new(GlobalAllocator {
+            blocks: Mutex::new(Vec::new()),
+            frozen_heaps: Mutex::new(HashMap::new()),
+        })
     }
 
     /// Requests a new free block from the pool
@@ -49,6 +63,23 @@ impl GlobalAllocator {
     pub fn add_block(&self, block: Box<Block>) {
         unlock!(self.blocks).push(block);
     }
+
+    /// Registers a frozen heap so it can be looked up by its address.
+    pub fn add_frozen_heap(&self, heap: &RcFrozenAllocator) {
+        unlock!(self.frozen_heaps).insert(heap.address(), Arc::downgrade(heap));
+    }
+
+    /// Removes a frozen heap that is about to be released.
+    pub fn remove_frozen_heap(&self, address: usize) {
+        unlock!(self.frozen_heaps).remove(&address);
+    }
+
+    /// Returns the frozen heap with the given address, if it's still alive.
+    pub fn frozen_heap(&self, address: usize) -> Option<RcFrozenAllocator> {
+        unlock!(self.frozen_heaps)
+            .get(&address)
+            .and_then(|heap| heap.upgrade())
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +120,18 @@ mod tests {
 
         assert_eq!(unlock!(alloc.blocks).len(), 1);
     }
+
+    #[test]
+    fn test_frozen_heap() {
+        let alloc = GlobalAllocator::without_preallocated_blocks();
+        let heap = FrozenAllocator::new(alloc.clone());
+        let address = heap.address();
+
+        assert!(alloc.frozen_heap(address).is_some());
+
+        drop(heap);
+
+        assert!(alloc.frozen_heap(address).is_none());
+        assert_eq!(unlock!(alloc.frozen_heaps).len(), 0);
+    }
 }
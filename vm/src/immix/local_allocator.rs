@@ -4,10 +4,13 @@
 //! process heap.
 
 use std::ops::Drop;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use immix::copy_object::CopyObject;
 use immix::bucket::Bucket;
 use immix::block::BLOCK_SIZE;
+use immix::frozen_allocator::FrozenHeaps;
 use immix::global_allocator::RcGlobalAllocator;
 
 use object::Object;
@@ -28,6 +31,35 @@ pub const YOUNG_BLOCK_ALLOCATION_THRESHOLD: usize = (1 * 1024 * 1024) /
 pub const MATURE_BLOCK_ALLOCATION_THRESHOLD: usize = (2 * 1024 * 1024) /
                                                      BLOCK_SIZE;
 
+/// The number of blocks used by a local allocator.
+///
+/// The counters are updated by the allocator whenever blocks are added or
+/// reclaimed, allowing other threads to read them while the allocator is in
+/// use.
+pub struct BlockCounts {
+    pub young: AtomicUsize,
+    pub mature: AtomicUsize,
+}
+
+pub type RcBlockCounts = Arc<BlockCounts>;
+
+impl BlockCounts {
+    pub fn new() -> RcBlockCounts {
+        Arc::new(BlockCounts {
+            young: AtomicUsize::new(0),
+            mature: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn young(&self) -> usize {
+        self.young.load(Ordering::Relaxed)
+    }
+
+    pub fn mature(&self) -> usize {
+        self.mature.load(Ordering::Relaxed)
+    }
+}
+
 /// Structure containing the state of a process-local allocator.
 pub struct LocalAllocator {
     /// The global allocated from which to request blocks of memory and return
@@ -50,6 +82,12 @@ pub struct LocalAllocator {
     /// The number of blocks allocated for the mature generation since the last
     /// garbage collection cycle.
     pub mature_block_allocations: usize,
+
+    /// The number of blocks used by this allocator, readable by other threads.
+    pub block_counts: RcBlockCounts,
+
+    /// The frozen heaps referred to by objects in this heap.
+    pub frozen_heaps: FrozenHeaps,
 }
 
 impl LocalAllocator {
@@ -64,6 +102,8 @@ impl LocalAllocator {
             mature_generation: Bucket::new(),
             young_block_allocations: 0,
             mature_block_allocations: 0,
+            block_counts: BlockCounts::new(),
+            frozen_heaps: FrozenHeaps::new(),
         }
     }
 
@@ -71,6 +111,29 @@ impl LocalAllocator {
         self.global_allocator.clone()
     }
 
+    /// Returns the number of blocks used by the young generation.
+    pub fn young_block_count(&self) -> usize {
+        self.young_generation
+            .iter()
+            .fold(0, |total, bucket| total + bucket.block_count())
+    }
+
+    /// Returns the number of blocks used by the mature generation.
+    pub fn mature_block_count(&self) -> usize {
+        self.mature_generation.block_count()
+    }
+
+    /// Stores the current number of blocks in the shared block counters.
+    pub fn update_block_counts(&self) {
+        self.block_counts
+            .young
+            .store(self.young_block_count(), Ordering::Relaxed);
+
+        self.block_counts
+            .mature
+            .store(self.mature_block_count(), Ordering::Relaxed);
+    }
+
     pub fn eden_space_mut(&mut self) -> &mut Bucket {
         &mut self.young_generation[self.eden_index]
     }
@@ -92,6 +155,28 @@ impl LocalAllocator {
                 self.global_allocator.add_block(block);
             }
         }
+
+        self.update_block_counts();
+    }
+
+    /// Returns all blocks to the global allocator, regardless of whether they
+    /// contain any live objects.
+    pub fn release_blocks(&mut self) {
+        for bucket in self.young_generation.iter_mut() {
+            for block in bucket.take_blocks() {
+                self.global_allocator.add_block(block);
+            }
+        }
+
+        for block in self.mature_generation.take_blocks() {
+            self.global_allocator.add_block(block);
+        }
+
+        self.young_block_allocations = 0;
+        self.mature_block_allocations = 0;
+
+        self.frozen_heaps.clear();
+        self.update_block_counts();
     }
 
     pub fn allocate_with_prototype(&mut self,
@@ -121,6 +206,8 @@ impl LocalAllocator {
 
         if new_block {
             self.young_block_allocations += 1;
+
+            self.update_block_counts();
         }
 
         pointer
@@ -133,6 +220,8 @@ impl LocalAllocator {
 
         if new_block {
             self.mature_block_allocations += 1;
+
+            self.update_block_counts();
         }
 
         pointer
@@ -187,21 +276,15 @@ impl CopyObject for LocalAllocator {
     fn allocate_copy(&mut self, object: Object) -> ObjectPointer {
         self.allocate_eden(object)
     }
+
+    fn retain_frozen_heap(&mut self, pointer: ObjectPointer) {
+        self.frozen_heaps.retain(&self.global_allocator, pointer);
+    }
 }
 
 impl Drop for LocalAllocator {
     fn drop(&mut self) {
-        for bucket in self.young_generation.iter_mut() {
-            for mut block in bucket.blocks.drain(0..) {
-                block.reset();
-                self.global_allocator.add_block(block);
-            }
-        }
-
-        for mut block in self.mature_generation.blocks.drain(0..) {
-            block.reset();
-            self.global_allocator.add_block(block);
-        }
+        self.release_blocks();
     }
 }
 
@@ -414,12 +497,42 @@ mod tests {
     fn test_copy_object() {
         let mut alloc = local_allocator();
         let pointer = alloc.allocate_without_prototype(object_value::integer(5));
-        let copy = alloc.copy_object(pointer);
+        let copy = alloc.copy_object(pointer).unwrap();
 
         assert!(copy.is_young());
         assert!(copy.get().value.is_integer());
     }
 
+    #[test]
+    fn test_block_counts() {
+        let mut alloc = local_allocator();
+
+        alloc.allocate_empty();
+        alloc.allocate_mature(Object::new(object_value::none()));
+
+        assert_eq!(alloc.young_block_count(), 1);
+        assert_eq!(alloc.mature_block_count(), 1);
+        assert_eq!(alloc.block_counts.young(), 1);
+        assert_eq!(alloc.block_counts.mature(), 1);
+    }
+
+    #[test]
+    fn test_release_blocks() {
+        let mut alloc = local_allocator();
+        let global_alloc = alloc.global_allocator();
+
+        alloc.allocate_empty();
+        alloc.allocate_mature(Object::new(object_value::none()));
+        alloc.release_blocks();
+
+        assert_eq!(unlock!(global_alloc.blocks).len(), 2);
+        assert_eq!(alloc.young_block_allocations, 0);
+        assert_eq!(alloc.mature_block_allocations, 0);
+        assert_eq!(alloc.block_counts.young(), 0);
+        assert_eq!(alloc.block_counts.mature(), 0);
+        assert!(alloc.eden_space_mut().current_block.is_null());
+    }
+
     #[test]
     fn test_drop() {
         let mut alloc = local_allocator();
@@ -3,19 +3,35 @@
 //! Each mailbox has its own allocator and its own heap. Incoming messages are
 //! copied into this heap. When a message is received its copied from the
 //! mailbox heap to the process local heap.
+//!
+//! The mailbox heap is garbage collected separately from the process local
+//! heap, without moving any objects.
 
 use std::ops::Drop;
 
 use immix::copy_object::CopyObject;
 use immix::bucket::Bucket;
+use immix::block::BLOCK_SIZE;
+use immix::frozen_allocator::FrozenHeaps;
 use immix::global_allocator::RcGlobalAllocator;
 
 use object::Object;
 use object_pointer::ObjectPointer;
 
+/// The maximum number of blocks that can be allocated before a garbage
+/// collection of the mailbox heap should be performed.
+pub const MAILBOX_BLOCK_ALLOCATION_THRESHOLD: usize = (1 * 1024 * 1024) /
+                                                      BLOCK_SIZE;
+
 pub struct MailboxAllocator {
     global_allocator: RcGlobalAllocator,
     bucket: Bucket,
+
+    /// The number of blocks allocated since the last garbage collection.
+    pub block_allocations: usize,
+
+    /// The frozen heaps referred to by objects in this heap.
+    pub frozen_heaps: FrozenHeaps,
 }
 
 impl MailboxAllocator {
@@ -23,6 +39,8 @@ impl MailboxAllocator {
         MailboxAllocator {
             global_allocator: global_allocator,
             bucket: Bucket::new(),
+            block_allocations: 0,
+            frozen_heaps: FrozenHeaps::new(),
         }
     }
 
@@ -34,8 +52,56 @@ impl MailboxAllocator {
         pointer
     }
 
+    /// Returns the number of blocks used by this allocator.
+    pub fn block_count(&self) -> usize {
+        self.bucket.block_count()
+    }
+
+    /// Returns true if the number of allocated blocks exceeds its threshold.
+    pub fn block_allocation_threshold_exceeded(&self) -> bool {
+        self.block_allocations >= MAILBOX_BLOCK_ALLOCATION_THRESHOLD
+    }
+
+    /// Prepares the mailbox heap for a garbage collection.
+    ///
+    /// Objects in the mailbox heap are never moved, as pointers to delayed
+    /// messages are stored outside of the process. As such we only need to
+    /// reset the bitmaps of every block.
+    pub fn prepare_for_collection(&mut self) {
+        for block in self.bucket
+            .blocks
+            .iter_mut()
+            .chain(self.bucket.recyclable_blocks.iter_mut()) {
+            block.reset_bitmaps();
+        }
+    }
+
+    /// Returns all blocks without any live objects to the global allocator.
+    pub fn reclaim_blocks(&mut self) {
+        for block in self.bucket.reclaim_blocks() {
+            self.global_allocator.add_block(block);
+        }
+
+        self.block_allocations = 0;
+    }
+
+    /// Returns all blocks to the global allocator.
+    pub fn release_blocks(&mut self) {
+        for block in self.bucket.take_blocks() {
+            self.global_allocator.add_block(block);
+        }
+
+        self.block_allocations = 0;
+        self.frozen_heaps.clear();
+    }
+
     fn allocate_raw(&mut self, object: Object) -> ObjectPointer {
-        let (_, pointer) = self.bucket.allocate(&self.global_allocator, object);
+        let (new_block, pointer) =
+            self.bucket.allocate(&self.global_allocator, object);
+
+        if new_block {
+            self.block_allocations += 1;
+        }
 
         pointer
     }
@@ -45,14 +111,15 @@ impl CopyObject for MailboxAllocator {
     fn allocate_copy(&mut self, object: Object) -> ObjectPointer {
         self.allocate(object)
     }
+
+    fn retain_frozen_heap(&mut self, pointer: ObjectPointer) {
+        self.frozen_heaps.retain(&self.global_allocator, pointer);
+    }
 }
 
 impl Drop for MailboxAllocator {
     fn drop(&mut self) {
-        for mut block in self.bucket.blocks.drain(0..) {
-            block.reset();
-            self.global_allocator.add_block(block);
-        }
+        self.release_blocks();
     }
 }
 
@@ -89,12 +156,72 @@ mod tests {
         let original =
             local_alloc.allocate_without_prototype(object_value::integer(5));
 
-        let copy = mbox_alloc.copy_object(original);
+        let copy = mbox_alloc.copy_object(original).unwrap();
 
         assert!(copy.get().generation().is_mailbox());
         assert!(copy.get().value.is_integer());
     }
 
+    #[test]
+    fn test_allocate_new_block() {
+        let mut alloc = mailbox_allocator();
+
+        alloc.allocate(Object::new(object_value::none()));
+
+        assert_eq!(alloc.block_allocations, 1);
+    }
+
+    #[test]
+    fn test_block_allocation_threshold_exceeded() {
+        let mut alloc = mailbox_allocator();
+
+        assert_eq!(alloc.block_allocation_threshold_exceeded(), false);
+
+        alloc.block_allocations = MAILBOX_BLOCK_ALLOCATION_THRESHOLD + 1;
+
+        assert!(alloc.block_allocation_threshold_exceeded());
+    }
+
+    #[test]
+    fn test_reclaim_blocks() {
+        let mut alloc = mailbox_allocator();
+        let global_alloc = alloc.global_allocator.clone();
+
+        alloc.allocate(Object::new(object_value::none()));
+        alloc.prepare_for_collection();
+        alloc.reclaim_blocks();
+
+        assert_eq!(alloc.block_count(), 0);
+        assert_eq!(alloc.block_allocations, 0);
+        assert_eq!(unlock!(global_alloc.blocks).len(), 1);
+    }
+
+    #[test]
+    fn test_reclaim_blocks_with_live_objects() {
+        let mut alloc = mailbox_allocator();
+        let global_alloc = alloc.global_allocator.clone();
+
+        alloc.allocate(Object::new(object_value::none()));
+        alloc.prepare_for_collection();
+        alloc.allocate(Object::new(object_value::integer(10))).mark();
+        alloc.reclaim_blocks();
+
+        assert_eq!(alloc.block_count(), 1);
+        assert_eq!(unlock!(global_alloc.blocks).len(), 0);
+    }
+
+    #[test]
+    fn test_release_blocks() {
+        let mut alloc = mailbox_allocator();
+        let global_alloc = alloc.global_allocator.clone();
+
+        alloc.allocate(Object::new(object_value::none()));
+        alloc.release_blocks();
+
+        assert_eq!(unlock!(global_alloc.blocks).len(), 1);
+        assert!(alloc.bucket.current_block.is_null());
+    }
+
     #[test]
     fn test_drop() {
         let mut alloc = mailbox_allocator();
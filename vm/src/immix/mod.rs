@@ -2,6 +2,7 @@ pub mod copy_object;
 pub mod bitmap;
 pub mod block;
 pub mod bucket;
+pub mod frozen_allocator;
 pub mod global_allocator;
 pub mod histogram;
 pub mod local_allocator;
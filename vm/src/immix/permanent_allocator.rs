@@ -6,6 +6,7 @@ use std::ops::Drop;
 
 use immix::bucket::Bucket;
 use immix::copy_object::CopyObject;
+use immix::frozen_allocator::FrozenHeaps;
 use immix::global_allocator::RcGlobalAllocator;
 
 use object::Object;
@@ -16,6 +17,11 @@ use object_pointer::ObjectPointer;
 pub struct PermanentAllocator {
     global_allocator: RcGlobalAllocator,
     bucket: Bucket,
+
+    /// The frozen heaps referred to by permanent objects.
+    ///
+    /// Permanent objects are never released, thus neither are these heaps.
+    frozen_heaps: FrozenHeaps,
 }
 
 impl PermanentAllocator {
@@ -23,6 +29,7 @@ impl PermanentAllocator {
         PermanentAllocator {
             global_allocator: global_allocator,
             bucket: Bucket::new(),
+            frozen_heaps: FrozenHeaps::new(),
         }
     }
 
@@ -62,6 +69,10 @@ impl CopyObject for PermanentAllocator {
     fn allocate_copy(&mut self, object: Object) -> ObjectPointer {
         self.allocate(object)
     }
+
+    fn retain_frozen_heap(&mut self, pointer: ObjectPointer) {
+        self.frozen_heaps.retain(&self.global_allocator, pointer);
+    }
 }
 
 impl Drop for PermanentAllocator {
@@ -116,6 +116,33 @@ pub enum InstructionType {
     SetParentLocal = 108,
     GetParentLocal = 109,
     GetBindingOfCaller = 110,
+    ReceiveProcessMessageTimeout = 111,
+    ReceiveMatchingProcessMessage = 112,
+    LinkProcess = 113,
+    MonitorProcess = 114,
+    TrapExits = 115,
+    RegisterProcess = 116,
+    UnregisterProcess = 117,
+    WhereisProcess = 118,
+    SendNamedProcessMessage = 119,
+    GetProcessPrototype = 120,
+    ProcessExit = 121,
+    ProcessKill = 122,
+    ProcessStatus = 123,
+    ProcessMailboxSize = 124,
+    ProcessList = 125,
+    ProcessHeapInfo = 126,
+    ProcessPriority = 127,
+    SetProcessPriority = 128,
+    ProcessSleep = 129,
+    SendAfter = 130,
+    TimeMonotonicNanos = 131,
+    TimeWallClock = 132,
+    TimeUtcTimestamp = 133,
+    Freeze = 134,
+    SpawnClosure = 135,
+    SpawnProcessWithArguments = 136,
+    JoinProcess = 137,
 }
 
 /// Struct for storing information about a single instruction.
@@ -11,6 +11,7 @@ pub mod tagged_pointer;
 pub mod binding;
 pub mod bytecode_parser;
 pub mod call_frame;
+pub mod clock;
 pub mod compiled_code;
 pub mod config;
 pub mod errors;
@@ -22,11 +23,14 @@ pub mod object_value;
 pub mod immix;
 pub mod register;
 pub mod process;
+pub mod process_handle;
 pub mod process_list;
+pub mod process_queue;
 pub mod execution_context;
 pub mod gc;
 pub mod thread;
 pub mod thread_list;
+pub mod timer;
 pub mod virtual_machine;
 pub mod virtual_machine_error;
 pub mod virtual_machine_result;
@@ -56,6 +56,20 @@ macro_rules! ensure_strings {
     );
 }
 
+/// Returns an Err if any of the given objects is frozen.
+macro_rules! ensure_not_frozen {
+    ($ins: expr, $($object: expr),+) => (
+        $(
+            if $object.is_frozen() {
+                return_vm_error!(
+                    "frozen objects can not be modified".to_string(),
+                    $ins.line
+                );
+            }
+        )+
+    );
+}
+
 /// Returns an Err if any of the given arguments is not a file.
 macro_rules! ensure_files {
     ($ins: expr, $($ident: ident),+) => (
@@ -84,6 +98,20 @@ macro_rules! ensure_compiled_code {
     );
 }
 
+/// Returns an Err if any of the given arguments is not a process handle.
+macro_rules! ensure_process_handles {
+    ($ins: expr, $($ident: ident),+) => (
+        $(
+            if !$ident.value.is_process_handle() {
+                return_vm_error!(
+                    "all arguments must be Process objects".to_string(),
+                    $ins.line
+                );
+            }
+        )+
+    );
+}
+
 /// Ensures the given index is within the bounds of the array.
 macro_rules! ensure_array_within_bounds {
     ($ins: expr, $array: ident, $index: expr) => (
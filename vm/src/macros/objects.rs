@@ -1,9 +1,12 @@
 #![macro_use]
 
 macro_rules! copy_if_permanent {
-    ($heap: expr, $source: expr, $dest: expr) => ({
+    ($heap: expr, $source: expr, $dest: expr, $ins: expr) => ({
         if $dest.is_permanent() {
-            write_lock!($heap).copy_object($source)
+            try_vm_error!(write_lock!($heap)
+                              .copy_object($source)
+                              .map_err(|err| err.to_string()),
+                          $ins)
         }
         else {
             $source
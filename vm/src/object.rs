@@ -381,6 +381,18 @@ impl Object {
         self.value.should_deallocate_native() || self.has_header()
     }
 
+    /// Marks this object as immutable.
+    pub fn freeze(&mut self) {
+        self.allocate_header();
+
+        self.header_mut().unwrap().frozen = true;
+    }
+
+    /// Returns true if this object can no longer be modified.
+    pub fn is_frozen(&self) -> bool {
+        self.header().map_or(false, |header| header.frozen)
+    }
+
     /// Returns true if an object header has been allocated.
     pub fn has_header(&self) -> bool {
         !self.header.is_null()
@@ -722,6 +734,17 @@ mod tests {
         assert!(obj.generation().is_mature());
     }
 
+    #[test]
+    fn test_object_freeze() {
+        let mut obj = new_object();
+
+        assert_eq!(obj.is_frozen(), false);
+
+        obj.freeze();
+
+        assert!(obj.is_frozen());
+    }
+
     #[test]
     fn test_object_generation_with_default_generation() {
         let obj = new_object();
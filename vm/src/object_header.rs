@@ -3,7 +3,8 @@
 //! The ObjectHeader struct stores metadata associated with an Object, such as
 //! the name, attributes, constants and methods.
 use std::collections::HashMap;
-use immix::copy_object::CopyObject;
+use std::io;
+use immix::copy_object::{CopyObject, CopyMap};
 use object_pointer::{ObjectPointer, ObjectPointerPointer};
 
 pub struct ObjectHeader {
@@ -19,6 +20,9 @@ pub struct ObjectHeader {
     /// The object to use for constant lookups when a constant is not available
     /// in the prototype hierarchy.
     pub outer_scope: Option<ObjectPointer>,
+
+    /// When set to true the object can no longer be modified.
+    pub frozen: bool,
 }
 
 impl ObjectHeader {
@@ -28,6 +32,7 @@ impl ObjectHeader {
             constants: HashMap::new(),
             methods: HashMap::new(),
             outer_scope: None,
+            frozen: false,
         }
     }
 
@@ -51,34 +56,43 @@ impl ObjectHeader {
     }
 
     /// Copies all pointers in this header to the given allocator.
-    pub fn copy_to<T: CopyObject>(&self, allocator: &mut T) -> ObjectHeader {
+    pub fn copy_to<T: CopyObject>(&self,
+                                  allocator: &mut T,
+                                  copies: &mut CopyMap)
+                                  -> Result<ObjectHeader, io::Error> {
         let mut copy = ObjectHeader::new();
 
         for (key, value) in self.attributes.iter() {
-            let value_copy = allocator.copy_object(value.clone());
+            let value_copy =
+                try!(allocator.copy_object_with_map(*value, copies));
 
             copy.add_attribute(key.clone(), value_copy);
         }
 
         for (key, value) in self.constants.iter() {
-            let value_copy = allocator.copy_object(value.clone());
+            let value_copy =
+                try!(allocator.copy_object_with_map(*value, copies));
 
             copy.add_constant(key.clone(), value_copy);
         }
 
         for (key, value) in self.methods.iter() {
-            let value_copy = allocator.copy_object(value.clone());
+            let value_copy =
+                try!(allocator.copy_object_with_map(*value, copies));
 
             copy.add_method(key.clone(), value_copy);
         }
 
         if let Some(scope) = self.outer_scope.as_ref() {
-            let outer_copy = allocator.copy_object(scope.clone());
+            let outer_copy =
+                try!(allocator.copy_object_with_map(*scope, copies));
 
             copy.outer_scope = Some(outer_copy);
         }
 
-        copy
+        copy.frozen = self.frozen;
+
+        Ok(copy)
     }
 
     pub fn add_method(&mut self, key: String, value: ObjectPointer) {
@@ -159,6 +159,37 @@ impl ObjectPointer {
         !self.is_permanent()
     }
 
+    /// Freezes the object and all objects reachable from it.
+    ///
+    /// Objects are frozen in place. Permanent objects are left as-is, as these
+    /// are shared between processes.
+    pub fn freeze(&self) {
+        let mut worklist = vec![*self];
+        let mut children = Vec::new();
+
+        while let Some(pointer) = worklist.pop() {
+            if pointer.is_permanent() || pointer.get().is_frozen() {
+                continue;
+            }
+
+            let track = !pointer.get().has_header();
+
+            pointer.get_mut().freeze();
+
+            // Freezing allocates an object header, which has to be released
+            // when the object is finalized.
+            if track {
+                pointer.mark_for_finalization();
+            }
+
+            pointer.get().push_pointers(&mut children);
+
+            for child in children.drain(..) {
+                worklist.push(*child.get());
+            }
+        }
+    }
+
     /// Returns true if the current object is marked.
     pub fn is_marked(&self) -> bool {
         let bitmap = self.marked_objects_bitmap();
@@ -194,6 +225,23 @@ impl ObjectPointer {
         self.mark_line();
     }
 
+    /// Atomically marks the current object, without marking its line.
+    ///
+    /// Returns `true` if the object was not yet marked. When marking in
+    /// parallel this is used to determine which thread is responsible for
+    /// tracing (and possibly moving) the object.
+    pub fn try_mark(&self) -> bool {
+        let index = self.marked_objects_bitmap_index();
+
+        self.block().marked_objects_bitmap.set_atomic(index)
+    }
+
+    /// Atomically marks the current object and its line.
+    pub fn mark_atomic(&self) {
+        self.try_mark();
+        self.block().used_lines_bitmap.set_atomic(self.line_index());
+    }
+
     /// Returns the mark bitmap to use for this pointer.
     pub fn marked_objects_bitmap(&self) -> &mut ObjectMap {
         &mut self.block_mut().marked_objects_bitmap
@@ -330,7 +378,9 @@ mod tests {
     use immix::bitmap::Bitmap;
     use immix::global_allocator::GlobalAllocator;
     use immix::local_allocator::LocalAllocator;
+    use immix::permanent_allocator::PermanentAllocator;
     use object::{Object, ObjectStatus};
+    use object_value;
     use object_value::ObjectValue;
 
     fn fake_raw_pointer() -> RawObjectPointer {
@@ -593,6 +643,29 @@ mod tests {
         assert!(pointer.block().used_lines_bitmap.is_set(1));
     }
 
+    #[test]
+    fn test_object_pointer_try_mark() {
+        let mut allocator = local_allocator();
+        let pointer = allocator.allocate_empty();
+
+        assert!(pointer.try_mark());
+        assert_eq!(pointer.try_mark(), false);
+
+        assert!(pointer.is_marked());
+        assert_eq!(pointer.block().used_lines_bitmap.is_set(1), false);
+    }
+
+    #[test]
+    fn test_object_pointer_mark_atomic() {
+        let mut allocator = local_allocator();
+        let pointer = allocator.allocate_empty();
+
+        pointer.mark_atomic();
+
+        assert!(pointer.block().marked_objects_bitmap.is_set(4));
+        assert!(pointer.block().used_lines_bitmap.is_set(1));
+    }
+
     #[test]
     fn test_object_pointer_marked_objects_bitmap() {
         let mut allocator = local_allocator();
@@ -689,6 +762,24 @@ mod tests {
         assert!(set.contains(&pointer));
     }
 
+    #[test]
+    fn test_object_pointer_freeze() {
+        let mut allocator = local_allocator();
+        let mut perm_alloc =
+            PermanentAllocator::new(allocator.global_allocator());
+
+        let proto = perm_alloc.allocate_empty();
+        let value = allocator.allocate_empty();
+        let array = allocator
+            .allocate_with_prototype(object_value::array(vec![value]), proto);
+
+        array.freeze();
+
+        assert!(array.get().is_frozen());
+        assert!(value.get().is_frozen());
+        assert_eq!(proto.get().is_frozen(), false);
+    }
+
     #[test]
     fn test_object_pointer_pointer_get_mut() {
         let ptr = ObjectPointer::new(fake_raw_pointer());
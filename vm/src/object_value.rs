@@ -10,6 +10,7 @@ use std::mem;
 use binding::RcBinding;
 use object_pointer::ObjectPointer;
 use compiled_code::RcCompiledCode;
+use process_handle::ProcessHandle;
 
 /// Enum for storing different values in an Object.
 pub enum ObjectValue {
@@ -22,6 +23,7 @@ pub enum ObjectValue {
     Error(u16),
     CompiledCode(RcCompiledCode),
     Binding(RcBinding),
+    ProcessHandle(Box<ProcessHandle>),
 }
 
 impl ObjectValue {
@@ -88,6 +90,13 @@ impl ObjectValue {
         }
     }
 
+    pub fn is_process_handle(&self) -> bool {
+        match *self {
+            ObjectValue::ProcessHandle(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn as_integer(&self) -> i64 {
         match *self {
             ObjectValue::Integer(val) => val,
@@ -161,6 +170,16 @@ impl ObjectValue {
         }
     }
 
+    pub fn as_process_handle(&self) -> ProcessHandle {
+        match *self {
+            ObjectValue::ProcessHandle(ref val) => **val,
+            _ => {
+                panic!("ObjectValue::as_process_handle() called on a non \
+                        process handle")
+            }
+        }
+    }
+
     pub fn take(&mut self) -> ObjectValue {
         mem::replace(self, ObjectValue::None)
     }
@@ -210,6 +229,10 @@ pub fn binding(value: RcBinding) -> ObjectValue {
     ObjectValue::Binding(value)
 }
 
+pub fn process_handle(value: ProcessHandle) -> ObjectValue {
+    ObjectValue::ProcessHandle(Box::new(value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,6 +240,7 @@ mod tests {
     use binding::Binding;
     use compiled_code::CompiledCode;
     use object_pointer::ObjectPointer;
+    use process_handle::ProcessHandle;
 
     #[test]
     fn test_is_none() {
@@ -282,6 +306,14 @@ mod tests {
         assert_eq!(ObjectValue::None.is_binding(), false);
     }
 
+    #[test]
+    fn test_is_process_handle() {
+        let handle = ProcessHandle::new(1, 2);
+
+        assert!(process_handle(handle).is_process_handle());
+        assert_eq!(ObjectValue::None.is_process_handle(), false);
+    }
+
     #[test]
     #[should_panic]
     fn test_as_integer_without_integer() {
@@ -414,6 +446,19 @@ mod tests {
                 pointer);
     }
 
+    #[test]
+    #[should_panic]
+    fn test_as_process_handle_without_process_handle() {
+        ObjectValue::None.as_process_handle();
+    }
+
+    #[test]
+    fn test_as_process_handle_with_process_handle() {
+        let handle = ProcessHandle::new(1, 2);
+
+        assert_eq!(process_handle(handle).as_process_handle(), handle);
+    }
+
     #[test]
     fn test_take() {
         let mut val1 = ObjectValue::Integer(5);
@@ -478,4 +523,9 @@ mod tests {
 
         assert!(binding(b).is_binding());
     }
+
+    #[test]
+    fn test_process_handle() {
+        assert!(process_handle(ProcessHandle::new(1, 2)).is_process_handle());
+    }
 }
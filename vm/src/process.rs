@@ -1,22 +1,29 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::hash::{Hash, Hasher};
 use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::cell::UnsafeCell;
+use std::io;
 
 use immix::bucket::Bucket;
-use immix::copy_object::CopyObject;
-use immix::local_allocator::LocalAllocator;
+use immix::copy_object::{CopyObject, CopyMap, CopyResult};
+use immix::frozen_allocator::{FrozenAllocator, frozen_heap_address};
+use immix::local_allocator::{LocalAllocator, RcBlockCounts};
 use immix::global_allocator::RcGlobalAllocator;
 use immix::mailbox_allocator::MailboxAllocator;
 
-use binding::RcBinding;
+use binding::{Binding, RcBinding};
 use call_frame::CallFrame;
 use compiled_code::RcCompiledCode;
+use errors;
+use gc::incremental::IncrementalMark;
 use object_pointer::{ObjectPointer, ObjectPointerPointer};
 use object_value;
+use process_handle::ProcessHandle;
 use execution_context::ExecutionContext;
 use queue::Queue;
+use thread::RcThread;
 
 pub type RcProcess = Arc<Process>;
 
@@ -34,6 +41,12 @@ pub enum ProcessStatus {
     /// The process has been suspended by the garbage collector.
     SuspendedByGc,
 
+    /// The process is suspended until it receives a message.
+    WaitingForMessage,
+
+    /// The process is suspended until its sleep deadline expires.
+    Sleeping,
+
     /// The process ran into some kind of error during execution.
     Failed,
 
@@ -48,6 +61,98 @@ impl ProcessStatus {
             _ => false,
         }
     }
+
+    /// Returns the name of the status as exposed to the language.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ProcessStatus::Scheduled => "scheduled",
+            ProcessStatus::Running => "running",
+            ProcessStatus::Suspended => "suspended",
+            ProcessStatus::SuspendedByGc => "suspended_by_gc",
+            ProcessStatus::WaitingForMessage => "waiting_for_message",
+            ProcessStatus::Sleeping => "sleeping",
+            ProcessStatus::Failed => "failed",
+            ProcessStatus::Finished => "finished",
+        }
+    }
+}
+
+/// The scheduling priority of a process.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProcessPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl ProcessPriority {
+    /// Returns the priority for an integer as used by the language.
+    pub fn from_integer(value: i64) -> Result<ProcessPriority, String> {
+        match value {
+            0 => Ok(ProcessPriority::Low),
+            1 => Ok(ProcessPriority::Normal),
+            2 => Ok(ProcessPriority::High),
+            _ => Err(format!("{} is not a valid process priority", value)),
+        }
+    }
+
+    /// Returns the integer used by the language for the priority.
+    pub fn to_integer(&self) -> i64 {
+        match *self {
+            ProcessPriority::Low => 0,
+            ProcessPriority::Normal => 1,
+            ProcessPriority::High => 2,
+        }
+    }
+
+    /// Returns the number of reductions a process with this priority can
+    /// perform, based on the number of reductions of a normal process.
+    pub fn reductions(&self, reductions: usize) -> usize {
+        match *self {
+            ProcessPriority::Low => (reductions / 2) + 1,
+            ProcessPriority::Normal => reductions,
+            ProcessPriority::High => reductions * 2,
+        }
+    }
+}
+
+/// Statistics about the memory used by a process.
+#[derive(Debug, PartialEq)]
+pub struct HeapInfo {
+    /// The number of blocks used by the young generation.
+    pub young_blocks: usize,
+
+    /// The number of blocks used by the mature generation.
+    pub mature_blocks: usize,
+
+    /// The number of blocks used by the mailbox heap.
+    pub mailbox_blocks: usize,
+
+    /// The number of garbage collection cycles of the young generation.
+    pub young_collections: usize,
+
+    /// The number of garbage collection cycles of the mature generation.
+    pub mature_collections: usize,
+}
+
+/// The state of a selective receive.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SelectiveReceive {
+    /// The predicate is being evaluated for the message at the given index.
+    Matching {
+        index: usize,
+
+        /// The copy of the message that is passed to the predicate. This copy
+        /// is used as the received message if the predicate matches.
+        message: ObjectPointer,
+
+        /// The call depth of the execution context performing the receive.
+        depth: usize,
+    },
+
+    /// All messages before the given index have been checked, and the process
+    /// is waiting for more messages to arrive.
+    Waiting(usize),
 }
 
 pub enum GcState {
@@ -68,6 +173,10 @@ pub struct LocalData {
     /// The current execution context of this process.
     pub context: Box<ExecutionContext>,
 
+    /// The number of execution contexts that are nested in the top-level
+    /// context.
+    pub call_depth: usize,
+
     /// The state of the garbage collector for this process.
     pub gc_state: GcState,
 
@@ -79,6 +188,29 @@ pub struct LocalData {
     /// lock of sorts. As such the collector must ensure this process is
     /// suspended upon examining the remembered set.
     pub remembered_set: HashSet<ObjectPointer>,
+
+    /// The state of an incremental collection that is marking the heap of
+    /// this process, if any.
+    pub incremental_mark: Option<IncrementalMark>,
+
+    /// When set to "true" this process should be suspended until it receives
+    /// a message.
+    pub wait_for_message: bool,
+
+    /// The time (in nanoseconds) at which waiting for a message times out.
+    pub receive_deadline: Option<u64>,
+
+    /// The time (in nanoseconds) at which the process should stop sleeping.
+    pub sleep_deadline: Option<u64>,
+
+    /// The state of the selective receive currently being performed, if any.
+    pub selective_receive: Option<SelectiveReceive>,
+
+    /// The process this process is waiting on to terminate, if any.
+    pub joining: Option<ProcessHandle>,
+
+    /// The value last returned by the top-level execution context.
+    pub return_value: Option<ObjectPointer>,
 }
 
 pub struct Process {
@@ -88,6 +220,9 @@ pub struct Process {
     /// The status of this process.
     pub status: Mutex<ProcessStatus>,
 
+    /// The scheduling priority of this process.
+    pub priority: Mutex<ProcessPriority>,
+
     /// Condition variable used for waking up other threads waiting for this
     /// process' status to change.
     pub status_signaler: Condvar,
@@ -96,11 +231,62 @@ pub struct Process {
     pub mailbox: Queue<ObjectPointer>,
 
     /// The allocator to use for storing objects in the mailbox heap.
+    ///
+    /// The lock of this allocator is held while adding messages to or removing
+    /// messages from the mailbox, ensuring the mailbox heap isn't reclaimed
+    /// while a message is being sent or received.
     pub mailbox_allocator: Mutex<MailboxAllocator>,
 
+    /// Messages allocated on the mailbox heap that will be sent at a later
+    /// point in time.
+    pub delayed_messages: Mutex<Vec<ObjectPointer>>,
+
+    /// The return values of terminated processes this process joined,
+    /// allocated on the mailbox heap.
+    pub join_results: Mutex<HashMap<ProcessHandle, ObjectPointer>>,
+
     /// Data stored in a process that should only be modified by a single thread
     /// at once.
     pub local_data: UnsafeCell<LocalData>,
+
+    /// The thread to reschedule this process on when it's waiting for a
+    /// message.
+    pub waiting_on: Mutex<Option<RcThread>>,
+
+    /// The handles of the processes linked to this process.
+    pub links: Mutex<HashSet<ProcessHandle>>,
+
+    /// The handles of the processes monitoring this process.
+    pub monitors: Mutex<HashSet<ProcessHandle>>,
+
+    /// The handles of the processes waiting for this process to terminate.
+    ///
+    /// This is set to None once the return value of this process has been
+    /// delivered to the joining processes.
+    pub joiners: Mutex<Option<HashSet<ProcessHandle>>>,
+
+    /// When set to "true" exit signals are received as messages instead of
+    /// terminating the process.
+    pub trap_exits: Mutex<bool>,
+
+    /// The reason of an exit signal that should terminate this process.
+    pub exit_signal: Mutex<Option<String>>,
+
+    /// The error message and call stack of this process, if it failed due to
+    /// an error.
+    pub backtrace: Mutex<Option<String>>,
+
+    /// The number of blocks used by the local allocator of this process.
+    ///
+    /// Unlike the allocator itself these counters can be read while the
+    /// process is running.
+    pub block_counts: RcBlockCounts,
+
+    /// The number of garbage collection cycles of the young generation.
+    pub young_collections: AtomicUsize,
+
+    /// The number of garbage collection cycles of the mature generation.
+    pub mature_collections: AtomicUsize,
 }
 
 unsafe impl Sync for LocalData {}
@@ -113,23 +299,47 @@ impl Process {
                context: ExecutionContext,
                global_allocator: RcGlobalAllocator)
                -> RcProcess {
+        let allocator = LocalAllocator::new(global_allocator.clone());
+        let block_counts = allocator.block_counts.clone();
+
         let local_data = LocalData {
-            allocator: LocalAllocator::new(global_allocator.clone()),
+            allocator: allocator,
             call_frame: call_frame,
             context: Box::new(context),
+            call_depth: 0,
             gc_state: GcState::None,
             suspend_for_gc: false,
             remembered_set: HashSet::new(),
+            incremental_mark: None,
+            wait_for_message: false,
+            receive_deadline: None,
+            sleep_deadline: None,
+            selective_receive: None,
+            joining: None,
+            return_value: None,
         };
 
         let process = Process {
             pid: pid,
             status: Mutex::new(ProcessStatus::Scheduled),
+            priority: Mutex::new(ProcessPriority::Normal),
             status_signaler: Condvar::new(),
             mailbox: Queue::new(),
             mailbox_allocator:
                 Mutex::new(MailboxAllocator::new(global_allocator)),
+            delayed_messages: Mutex::new(Vec::new()),
+            join_results: Mutex::new(HashMap::new()),
             local_data: UnsafeCell::new(local_data),
+            waiting_on: Mutex::new(None),
+            links: Mutex::new(HashSet::new()),
+            monitors: Mutex::new(HashSet::new()),
+            joiners: Mutex::new(Some(HashSet::new())),
+            trap_exits: Mutex::new(false),
+            exit_signal: Mutex::new(None),
+            backtrace: Mutex::new(None),
+            block_counts: block_counts,
+            young_collections: AtomicUsize::new(0),
+            mature_collections: AtomicUsize::new(0),
         };
 
         Arc::new(process)
@@ -178,6 +388,9 @@ impl Process {
     pub fn push_context(&self, context: ExecutionContext) {
         let mut boxed = Box::new(context);
         let mut local_data = self.local_data_mut();
+
+        local_data.call_depth += 1;
+
         let ref mut target = local_data.context;
 
         mem::swap(target, &mut boxed);
@@ -195,6 +408,34 @@ impl Process {
         let parent = local_data.context.parent.take().unwrap();
 
         local_data.context = parent;
+        local_data.call_depth -= 1;
+    }
+
+    pub fn call_depth(&self) -> usize {
+        self.local_data().call_depth
+    }
+
+    /// Replaces the current execution context with the given one.
+    ///
+    /// The new context takes over the parent and return register of the
+    /// current context, and re-uses its allocation. This is used for tail
+    /// calls, where the current context is no longer needed.
+    pub fn replace_context(&self, mut context: ExecutionContext) {
+        let mut local_data = self.local_data_mut();
+        let ref mut target = local_data.context;
+
+        context.parent = target.parent.take();
+        context.return_register = target.return_register;
+
+        **target = context;
+    }
+
+    /// Removes the current call frame and records it as a tail call in its
+    /// parent frame.
+    pub fn elide_call_frame(&self) {
+        self.pop_call_frame();
+
+        self.local_data_mut().call_frame.tail_calls += 1;
     }
 
     pub fn get_register(&self, register: usize) -> Result<ObjectPointer, String> {
@@ -214,6 +455,7 @@ impl Process {
 
     pub fn set_local(&self, index: usize, value: ObjectPointer) {
         self.local_data_mut().context.set_local(index, value);
+        self.marking_barrier(value);
     }
 
     pub fn get_local(&self, index: usize) -> Result<ObjectPointer, String> {
@@ -248,29 +490,192 @@ impl Process {
     }
 
     /// Sends a message to the current process.
-    pub fn send_message(&self, message: ObjectPointer) {
-        let mut to_send = message;
+    ///
+    /// Permanent objects, including frozen objects shared using `share()`,
+    /// are shared between processes and thus are not copied.
+    ///
+    /// An error is returned if the message could not be copied, in which case
+    /// the message is not sent.
+    pub fn send_message(&self,
+                        message: ObjectPointer)
+                        -> Result<(), io::Error> {
+        {
+            let mut allocator = unlock!(self.mailbox_allocator);
+            let copy = try!(allocator.copy_object(message));
+
+            self.mailbox.push(copy);
+        }
+
+        self.wake_up();
+
+        Ok(())
+    }
+
+    /// Sends a message allocated on the mailbox heap using the given closure.
+    pub fn send_allocated_message<F>(&self, allocate: F)
+        where F: FnOnce(&mut MailboxAllocator) -> ObjectPointer
+    {
+        {
+            let mut allocator = unlock!(self.mailbox_allocator);
+
+            self.mailbox.push(allocate(&mut allocator));
+        }
+
+        self.wake_up();
+    }
+
+    /// Copies a message to the mailbox heap so it can be sent at a later point
+    /// in time using `send_delayed_message`.
+    pub fn delay_message(&self, message: ObjectPointer) -> CopyResult {
+        let mut allocator = unlock!(self.mailbox_allocator);
+        let copy = try!(allocator.copy_object(message));
+
+        unlock!(self.delayed_messages).push(copy);
+
+        Ok(copy)
+    }
+
+    /// Sends a message previously copied using `delay_message`.
+    ///
+    /// The message is dropped if the memory of this process has been released
+    /// in the mean time.
+    pub fn send_delayed_message(&self, message: ObjectPointer) {
+        {
+            let _allocator = unlock!(self.mailbox_allocator);
+            let mut delayed = unlock!(self.delayed_messages);
+
+            match delayed.iter().position(|ptr| *ptr == message) {
+                Some(index) => {
+                    delayed.swap_remove(index);
+                    self.mailbox.push(message);
+                }
+                None => return,
+            }
+        }
+
+        self.wake_up();
+    }
 
-        // TODO: Instead of using is_local we can use an enum with two variants:
-        // Remote and Local. A Remote message requires copying the message into
-        // the message allocator, a Local message can be used as-is.
-        //
-        // When we receive() a Remote message we copy it to the eden allocator. If
-        // the message is a Local message we just leave things as-is.
-        //
-        // This can also be used for globals as when sending a global object as
-        // a message we can just use the Local variant.
-        if to_send.is_local() {
-            to_send = unlock!(self.mailbox_allocator).copy_object(to_send);
+    /// Stores the return value of a terminated process this process joined.
+    ///
+    /// The value is allocated on the mailbox heap using the given closure.
+    pub fn deliver_join_result<F>(&self, handle: ProcessHandle, allocate: F)
+        where F: FnOnce(&mut MailboxAllocator) -> ObjectPointer
+    {
+        {
+            let mut allocator = unlock!(self.mailbox_allocator);
+            let result = allocate(&mut allocator);
+
+            unlock!(self.join_results).insert(handle, result);
         }
 
-        self.mailbox.push(to_send);
+        self.wake_up();
+    }
+
+    /// Removes the return value of the given joined process, copying it to
+    /// the heap of the current process.
+    pub fn take_join_result(&self,
+                            handle: &ProcessHandle)
+                            -> Option<ObjectPointer> {
+        let mut allocator = unlock!(self.mailbox_allocator);
+        let result = unlock!(self.join_results).remove(handle);
+
+        result.map(|result| {
+            let copy = self.copy_to_heap(result);
+
+            self.reclaim_mailbox_heap(&mut allocator);
+
+            copy
+        })
     }
 
-    /// Pops a message from the current process' message queue.
+    /// Pops a message from the current process' message queue, copying it to
+    /// the heap of the current process.
     pub fn receive_message(&self) -> Option<ObjectPointer> {
-        // TODO: copy to the heap
-        self.mailbox.pop_nonblock()
+        let mut allocator = unlock!(self.mailbox_allocator);
+
+        self.mailbox.pop_nonblock().map(|message| {
+            let copy = self.copy_to_heap(message);
+
+            self.reclaim_mailbox_heap(&mut allocator);
+
+            copy
+        })
+    }
+
+    /// Returns a copy of the message at the given index without removing it
+    /// from the mailbox.
+    pub fn peek_message(&self, index: usize) -> Option<ObjectPointer> {
+        let _allocator = unlock!(self.mailbox_allocator);
+
+        self.mailbox.get(index).map(|message| self.copy_to_heap(message))
+    }
+
+    /// Removes the message at the given index from the mailbox, leaving the
+    /// order of other messages as-is.
+    ///
+    /// The message is not copied, as a copy was already obtained using
+    /// `peek_message()`. The return value is true if the message existed.
+    pub fn remove_message(&self, index: usize) -> bool {
+        let mut allocator = unlock!(self.mailbox_allocator);
+
+        if self.mailbox.remove(index).is_some() {
+            self.reclaim_mailbox_heap(&mut allocator);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Copies a message from the mailbox heap to the heap of the current
+    /// process.
+    ///
+    /// If the message could not be copied an error object is returned instead.
+    fn copy_to_heap(&self, message: ObjectPointer) -> ObjectPointer {
+        let mut local_data = self.local_data_mut();
+
+        match local_data.allocator.copy_object(message) {
+            Ok(copy) => copy,
+            Err(error) => {
+                let code = errors::from_io_error(error);
+
+                local_data.allocator
+                    .allocate_without_prototype(object_value::error(code))
+            }
+        }
+    }
+
+    /// Returns the blocks of the mailbox heap to the global allocator once all
+    /// messages have been received.
+    fn reclaim_mailbox_heap(&self, allocator: &mut MailboxAllocator) {
+        let delayed = unlock!(self.delayed_messages).len();
+        let joined = unlock!(self.join_results).len();
+
+        if self.mailbox.len() == 0 && delayed == 0 && joined == 0 {
+            allocator.release_blocks();
+        }
+    }
+
+    pub fn selective_receive(&self) -> Option<SelectiveReceive> {
+        self.local_data().selective_receive
+    }
+
+    pub fn set_selective_receive(&self, state: Option<SelectiveReceive>) {
+        self.local_data_mut().selective_receive = state;
+    }
+
+    /// Returns true if the predicate of a selective receive is running.
+    ///
+    /// Messages can't be received while the predicate runs, as this would
+    /// change the index of the message being checked.
+    pub fn is_matching_message(&self) -> bool {
+        match self.selective_receive() {
+            Some(SelectiveReceive::Matching { depth, .. }) => {
+                self.call_depth() > depth
+            }
+            _ => false,
+        }
     }
 
     pub fn should_be_rescheduled(&self) -> bool {
@@ -295,6 +700,98 @@ impl Process {
         self.context().self_object()
     }
 
+    /// Copies a binding into the heap of this process, using the copy as the
+    /// parent binding of the current context.
+    ///
+    /// This allows a newly spawned process to access the variables captured
+    /// by a closure.
+    pub fn capture_binding(&self,
+                           binding: &RcBinding)
+                           -> Result<(), io::Error> {
+        let mut local_data = self.local_data_mut();
+        let copy = try!(binding.copy_to(&mut local_data.allocator,
+                                        &mut CopyMap::new()));
+
+        local_data.context.binding = Binding::with_parent(copy.self_object(),
+                                                          copy);
+
+        Ok(())
+    }
+
+    /// Copies the given arguments into the heap of this process, storing them
+    /// in the local variables of the current context.
+    pub fn set_arguments(&self,
+                         arguments: &Vec<ObjectPointer>)
+                         -> Result<(), io::Error> {
+        for (index, argument) in arguments.iter().enumerate() {
+            let copy =
+                try!(self.local_data_mut().allocator.copy_object(*argument));
+
+            self.set_local(index, copy);
+        }
+
+        Ok(())
+    }
+
+    /// Copies a frozen object graph into a new frozen heap, allowing it to be
+    /// shared with other processes without copying it.
+    ///
+    /// The frozen heap is released once no heap refers to its objects any
+    /// longer.
+    pub fn share(&self, pointer: ObjectPointer) -> CopyResult {
+        let ref mut allocator = self.local_data_mut().allocator;
+        let heap = FrozenAllocator::new(allocator.global_allocator());
+        let copy = try!(heap.freeze(pointer));
+
+        allocator.frozen_heaps.retain_heap(heap);
+
+        Ok(copy)
+    }
+
+    /// Releases the frozen heaps no longer referred to by the heap of this
+    /// process.
+    ///
+    /// This traces all live objects, thus it should only be used after
+    /// collecting the mature generation.
+    pub fn release_unused_frozen_heaps(&self) {
+        if self.local_data().allocator.frozen_heaps.is_empty() {
+            return;
+        }
+
+        let mut objects: Vec<ObjectPointer> =
+            self.roots().iter().map(|pointer| *pointer.get()).collect();
+
+        let mut children = Vec::new();
+        let mut visited = HashSet::new();
+        let mut used = HashSet::new();
+
+        while let Some(pointer) = objects.pop() {
+            if pointer.is_null() {
+                continue;
+            }
+
+            if pointer.is_permanent() {
+                if let Some(address) = frozen_heap_address(pointer) {
+                    used.insert(address);
+                }
+
+                continue;
+            }
+
+            if !visited.insert(pointer) {
+                continue;
+            }
+
+            pointer.get().push_pointers(&mut children);
+
+            for child in children.drain(..) {
+                objects.push(*child.get());
+            }
+        }
+
+        self.local_data_mut().allocator.frozen_heaps.retain_only(&used);
+    }
+
     pub fn context(&self) -> &Box<ExecutionContext> {
         &self.local_data().context
     }
@@ -323,6 +820,25 @@ impl Process {
         self.context_mut().instruction_index = index;
     }
 
+    pub fn status_name(&self) -> &'static str {
+        unlock!(self.status).name()
+    }
+
+    pub fn priority(&self) -> ProcessPriority {
+        *unlock!(self.priority)
+    }
+
+    /// Changes the priority of this process.
+    ///
+    /// The new priority is used the next time the process is scheduled.
+    pub fn set_priority(&self, priority: ProcessPriority) {
+        *unlock!(self.priority) = priority;
+    }
+
+    pub fn mailbox_size(&self) -> usize {
+        self.mailbox.len()
+    }
+
     pub fn is_alive(&self) -> bool {
         match *unlock!(self.status) {
             ProcessStatus::Failed => false,
@@ -340,6 +856,7 @@ impl Process {
     }
 
     pub fn running(&self) {
+        self.local_data_mut().wait_for_message = false;
         self.set_status(ProcessStatus::Running);
     }
 
@@ -376,7 +893,167 @@ impl Process {
     }
 
     pub fn finished(&self) {
-        self.set_status_without_overwriting_gc_status(ProcessStatus::Finished);
+        self.set_terminated_status(ProcessStatus::Finished);
+    }
+
+    pub fn failed(&self) {
+        self.set_terminated_status(ProcessStatus::Failed);
+    }
+
+    /// Sets the final status of a process.
+    ///
+    /// Unlike other status changes this overwrites any pending suspension
+    /// requested by the GC, as a terminated process must never be resumed.
+    fn set_terminated_status(&self, new_status: ProcessStatus) {
+        let mut status = unlock!(self.status);
+
+        self.local_data_mut().suspend_for_gc = false;
+
+        *status = new_status;
+
+        self.status_signaler.notify_all();
+    }
+
+    /// Returns the memory of a terminated process to the global allocator.
+    ///
+    /// This method must only be called by the thread that has exclusive access
+    /// to the process' heap, after the process has terminated.
+    pub fn release_memory(&self) {
+        let mut local_data = self.local_data_mut();
+
+        local_data.remembered_set.clear();
+        local_data.incremental_mark = None;
+        local_data.allocator.release_blocks();
+
+        let mut mailbox_allocator = unlock!(self.mailbox_allocator);
+
+        while self.mailbox.pop_nonblock().is_some() {}
+
+        unlock!(self.delayed_messages).clear();
+        unlock!(self.join_results).clear();
+        mailbox_allocator.release_blocks();
+    }
+
+    /// Links the process with the given handle to this process.
+    ///
+    /// The return value is "false" if this process is no longer alive. The
+    /// status is checked while holding the lock of the links, ensuring that a
+    /// link is either established or reported as failed when the process
+    /// terminates concurrently.
+    pub fn link(&self, handle: ProcessHandle) -> bool {
+        let mut links = unlock!(self.links);
+
+        if !self.is_alive() {
+            return false;
+        }
+
+        links.insert(handle);
+
+        true
+    }
+
+    pub fn unlink(&self, handle: &ProcessHandle) {
+        unlock!(self.links).remove(handle);
+    }
+
+    /// Removes and returns the handles of all linked processes.
+    pub fn take_links(&self) -> Vec<ProcessHandle> {
+        unlock!(self.links).drain().collect()
+    }
+
+    /// Registers the process with the given handle as a monitor of this
+    /// process.
+    ///
+    /// Similar to `link` the return value is "false" if this process is no
+    /// longer alive.
+    pub fn add_monitor(&self, handle: ProcessHandle) -> bool {
+        let mut monitors = unlock!(self.monitors);
+
+        if !self.is_alive() {
+            return false;
+        }
+
+        monitors.insert(handle);
+
+        true
+    }
+
+    /// Removes and returns the handles of all monitoring processes.
+    pub fn take_monitors(&self) -> Vec<ProcessHandle> {
+        unlock!(self.monitors).drain().collect()
+    }
+
+    /// Registers the process with the given handle as a process waiting for
+    /// this process to terminate.
+    ///
+    /// Similar to `link` the return value is "false" if this process is no
+    /// longer alive, or if its return value has already been delivered.
+    pub fn add_joiner(&self, handle: ProcessHandle) -> bool {
+        let mut joiners = unlock!(self.joiners);
+
+        if !self.is_alive() {
+            return false;
+        }
+
+        match *joiners {
+            Some(ref mut handles) => {
+                handles.insert(handle);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes and returns the handles of all joining processes.
+    ///
+    /// Processes can no longer join this process once this method has been
+    /// called.
+    pub fn take_joiners(&self) -> Vec<ProcessHandle> {
+        unlock!(self.joiners)
+            .take()
+            .map(|handles| handles.into_iter().collect())
+            .unwrap_or_else(Vec::new)
+    }
+
+    pub fn set_trap_exits(&self, trap: bool) {
+        *unlock!(self.trap_exits) = trap;
+    }
+
+    pub fn traps_exits(&self) -> bool {
+        *unlock!(self.trap_exits)
+    }
+
+    /// Signals the process to terminate with the given reason.
+    ///
+    /// The process terminates the next time it's run, or at the end of its
+    /// current execution context if it's running.
+    pub fn signal_exit(&self, reason: String) {
+        {
+            let mut signal = unlock!(self.exit_signal);
+
+            if signal.is_none() {
+                *signal = Some(reason);
+            }
+        }
+
+        self.wake_up_from_sleep();
+    }
+
+    pub fn exit_signal(&self) -> Option<String> {
+        unlock!(self.exit_signal).clone()
+    }
+
+    pub fn has_exit_signal(&self) -> bool {
+        unlock!(self.exit_signal).is_some()
+    }
+
+    pub fn set_backtrace(&self, backtrace: String) {
+        *unlock!(self.backtrace) = Some(backtrace);
+    }
+
+    pub fn backtrace(&self) -> Option<String> {
+        unlock!(self.backtrace).clone()
     }
 
     pub fn suspend(&self) {
@@ -411,26 +1088,211 @@ impl Process {
         }
     }
 
-    pub fn should_suspend_for_gc(&self) -> bool {
-        self.suspended_by_gc() || self.local_data().suspend_for_gc
+    /// Marks this process as waiting for a message.
+    ///
+    /// The process is parked by the thread running it once the process
+    /// returns control to the thread.
+    pub fn suspend_for_message(&self) {
+        self.local_data_mut().wait_for_message = true;
     }
 
-    pub fn gc_state(&self) -> &GcState {
-        &self.local_data().gc_state
+    pub fn should_wait_for_message(&self) -> bool {
+        self.local_data().wait_for_message
     }
 
-    pub fn set_gc_state(&self, new_state: GcState) {
-        self.local_data_mut().gc_state = new_state;
+    pub fn joining(&self) -> Option<ProcessHandle> {
+        self.local_data().joining
     }
 
-    pub fn gc_scheduled(&self) {
-        self.set_gc_state(GcState::Scheduled);
+    pub fn set_joining(&self, handle: Option<ProcessHandle>) {
+        self.local_data_mut().joining = handle;
     }
 
-    pub fn should_schedule_gc(&self) -> bool {
-        match *self.gc_state() {
-            GcState::None => self.should_collect_young_generation(),
-            _ => false,
+    pub fn return_value(&self) -> Option<ObjectPointer> {
+        self.local_data().return_value
+    }
+
+    pub fn set_return_value(&self, value: ObjectPointer) {
+        self.local_data_mut().return_value = Some(value);
+    }
+
+    pub fn receive_deadline(&self) -> Option<u64> {
+        self.local_data().receive_deadline
+    }
+
+    pub fn set_receive_deadline(&self, deadline: Option<u64>) {
+        self.local_data_mut().receive_deadline = deadline;
+    }
+
+    pub fn sleep_deadline(&self) -> Option<u64> {
+        self.local_data().sleep_deadline
+    }
+
+    pub fn set_sleep_deadline(&self, deadline: Option<u64>) {
+        self.local_data_mut().sleep_deadline = deadline;
+    }
+
+    pub fn should_sleep(&self) -> bool {
+        self.sleep_deadline().is_some()
+    }
+
+    /// Suspends the process until its sleep deadline expires.
+    ///
+    /// The return value is "false" if the process could not be suspended
+    /// because it's scheduled for garbage collection or received an exit
+    /// signal. In this case the process should be rescheduled instead.
+    pub fn sleep(&self, thread: RcThread) -> bool {
+        let mut status = unlock!(self.status);
+
+        let gc_scheduled = match *self.gc_state() {
+            GcState::Scheduled => true,
+            GcState::None => false,
+        };
+
+        if gc_scheduled || self.has_exit_signal() {
+            return false;
+        }
+
+        *status = ProcessStatus::Sleeping;
+        *unlock!(self.waiting_on) = Some(thread);
+
+        self.status_signaler.notify_all();
+
+        true
+    }
+
+    /// Parks the process until a message is received.
+    ///
+    /// The return value is "false" if the process could not be parked, either
+    /// because a message is already available or because the process is
+    /// scheduled for garbage collection. In this case the process should be
+    /// rescheduled instead.
+    ///
+    /// Messages already checked by a selective receive are not considered to
+    /// be available. A process joining another process is only woken up once
+    /// the return value of said process is available.
+    ///
+    /// The mailbox is checked while holding the status lock, ensuring that a
+    /// message sent while parking the process will wake it up.
+    pub fn park(&self, thread: RcThread) -> bool {
+        let mut status = unlock!(self.status);
+
+        self.local_data_mut().wait_for_message = false;
+
+        let gc_scheduled = match *self.gc_state() {
+            GcState::Scheduled => true,
+            GcState::None => false,
+        };
+
+        let checked = match self.selective_receive() {
+            Some(SelectiveReceive::Waiting(index)) => index,
+            _ => 0,
+        };
+
+        // A process joining another process only waits for its return value,
+        // any messages are left as-is until the join completes.
+        let available = match self.joining() {
+            Some(handle) => unlock!(self.join_results).contains_key(&handle),
+            None => self.mailbox.len() > checked,
+        };
+
+        if gc_scheduled || available {
+            return false;
+        }
+
+        *status = ProcessStatus::WaitingForMessage;
+        *unlock!(self.waiting_on) = Some(thread);
+
+        self.status_signaler.notify_all();
+
+        true
+    }
+
+    /// Reschedules the process if it's waiting for a message.
+    pub fn wake_up(&self) {
+        self.resume(false, None);
+    }
+
+    /// Reschedules the process if it's sleeping or waiting for a message.
+    pub fn wake_up_from_sleep(&self) {
+        self.resume(true, None);
+    }
+
+    /// Reschedules the process if it's still waiting for a message with the
+    /// given receive deadline.
+    ///
+    /// Timer entries of earlier receives may expire after the process already
+    /// received a message, these should not wake up the process.
+    pub fn wake_up_after_timeout(&self, deadline: u64) {
+        self.resume(false, Some(deadline));
+    }
+
+    fn resume(&self, sleeping: bool, deadline: Option<u64>) {
+        let thread_opt = {
+            let mut status = unlock!(self.status);
+
+            // The local data of a waiting process isn't modified until the
+            // process is rescheduled, making it safe to read here.
+            let waiting = match *status {
+                ProcessStatus::WaitingForMessage => {
+                    deadline.is_none() || self.receive_deadline() == deadline
+                }
+                ProcessStatus::Sleeping => sleeping,
+                _ => false,
+            };
+
+            if waiting {
+                *status = ProcessStatus::Scheduled;
+
+                self.status_signaler.notify_all();
+
+                unlock!(self.waiting_on).take()
+            } else {
+                None
+            }
+        };
+
+        if let Some(thread) = thread_opt {
+            thread.resume(self.pid);
+        }
+    }
+
+    pub fn is_waiting_for_message(&self) -> bool {
+        match *unlock!(self.status) {
+            ProcessStatus::WaitingForMessage => true,
+            _ => false,
+        }
+    }
+
+    pub fn should_suspend_for_gc(&self) -> bool {
+        self.suspended_by_gc() || self.local_data().suspend_for_gc
+    }
+
+    pub fn gc_state(&self) -> &GcState {
+        &self.local_data().gc_state
+    }
+
+    pub fn set_gc_state(&self, new_state: GcState) {
+        self.local_data_mut().gc_state = new_state;
+    }
+
+    pub fn gc_scheduled(&self) {
+        self.set_gc_state(GcState::Scheduled);
+    }
+
+    pub fn is_gc_scheduled(&self) -> bool {
+        match *self.gc_state() {
+            GcState::Scheduled => true,
+            GcState::None => false,
+        }
+    }
+
+    pub fn should_schedule_gc(&self) -> bool {
+        match *self.gc_state() {
+            GcState::None => {
+                self.is_marking() || self.should_collect_young_generation()
+            }
+            _ => false,
         }
     }
 
@@ -446,6 +1308,22 @@ impl Process {
             .mature_block_allocation_threshold_exceeded()
     }
 
+    /// Returns true if a collection of the mailbox heap should be scheduled.
+    ///
+    /// The block allocation counter is reset when returning true, preventing
+    /// multiple collections from being scheduled for the same allocations.
+    pub fn should_schedule_mailbox_gc(&self) -> bool {
+        let mut allocator = unlock!(self.mailbox_allocator);
+
+        if allocator.block_allocation_threshold_exceeded() {
+            allocator.block_allocations = 0;
+
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn reset_status(&self) {
         self.set_status(ProcessStatus::Scheduled);
         self.set_gc_state(GcState::None);
@@ -465,6 +1343,30 @@ impl Process {
             context.register.push_pointers(&mut pointers);
         }
 
+        if let Some(ref value) = self.local_data().return_value {
+            pointers.push(value.pointer());
+        }
+
+        if let Some(SelectiveReceive::Matching { ref message, .. }) =
+            self.local_data().selective_receive {
+            pointers.push(message.pointer());
+        }
+
+        pointers
+    }
+
+    /// Returns the messages stored in the mailbox heap, including messages that
+    /// will be sent at a later point in time and the return values of joined
+    /// processes.
+    ///
+    /// The mailbox allocator must be locked when calling this method, ensuring
+    /// no messages are added or removed while scanning the returned pointers.
+    pub fn mailbox_roots(&self) -> Vec<ObjectPointer> {
+        let mut pointers = self.mailbox.to_vec();
+
+        pointers.extend(unlock!(self.delayed_messages).iter().cloned());
+        pointers.extend(unlock!(self.join_results).values().cloned());
+
         pointers
     }
 
@@ -482,12 +1384,59 @@ impl Process {
         if written_to.is_mature() && written.is_young() {
             self.remembered_set_mut().insert(written_to);
         }
+
+        self.marking_barrier(written);
+    }
+
+    /// Write barrier for objects written while the heap is marked
+    /// incrementally.
+    ///
+    /// The written object may be stored in an object that has already been
+    /// marked, thus it's scheduled for tracing during the next slice.
+    pub fn marking_barrier(&self, written: ObjectPointer) {
+        if let Some(ref mut mark) = self.local_data_mut().incremental_mark {
+            mark.push(written);
+        }
+    }
+
+    /// Returns true if an incremental collection is marking the heap.
+    pub fn is_marking(&self) -> bool {
+        self.local_data().incremental_mark.is_some()
+    }
+
+    pub fn incremental_mark_mut(&self) -> &mut Option<IncrementalMark> {
+        &mut self.local_data_mut().incremental_mark
     }
 
     pub fn increment_young_ages(&self) {
         self.local_data_mut().allocator.increment_young_ages()
     }
 
+    /// Records the completion of a garbage collection cycle.
+    pub fn collected(&self, mature: bool) {
+        self.young_collections.fetch_add(1, Ordering::Relaxed);
+
+        if mature {
+            self.mature_collections.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns statistics about the memory used by this process.
+    ///
+    /// This method can be called from any thread, as it only reads counters
+    /// that are safe to access while the process is running. The statistics
+    /// may thus be slightly out of date.
+    pub fn heap_info(&self) -> HeapInfo {
+        HeapInfo {
+            young_blocks: self.block_counts.young(),
+            mature_blocks: self.block_counts.mature(),
+            mailbox_blocks: unlock!(self.mailbox_allocator).block_count(),
+            young_collections: self.young_collections.load(Ordering::Relaxed),
+            mature_collections: self.mature_collections
+                .load(Ordering::Relaxed),
+        }
+    }
+
     pub fn mature_generation_mut(&self) -> &mut Bucket {
         self.local_data_mut().allocator.mature_generation_mut()
     }
@@ -511,9 +1460,14 @@ impl Hash for Process {
 mod tests {
     use super::*;
     use immix::global_allocator::GlobalAllocator;
+    use immix::mailbox_allocator::MAILBOX_BLOCK_ALLOCATION_THRESHOLD;
     use compiled_code::CompiledCode;
+    use execution_context::ExecutionContext;
     use object::Object;
     use object_pointer::ObjectPointer;
+    use object_value;
+    use process_handle::ProcessHandle;
+    use thread::Thread;
 
     fn new_process() -> RcProcess {
         let code = CompiledCode::with_rc("a".to_string(),
@@ -537,6 +1491,575 @@ mod tests {
         assert_eq!(process.roots().len(), 3);
     }
 
+    #[test]
+    fn test_replace_context() {
+        let process = new_process();
+        let code = process.compiled_code();
+        let new_code = CompiledCode::with_rc("b".to_string(),
+                                             "b".to_string(),
+                                             1,
+                                             Vec::new());
+
+        let self_obj = ObjectPointer::null();
+
+        process.push_context(ExecutionContext::with_object(self_obj,
+                                                           code,
+                                                           Some(2)));
+
+        process.replace_context(ExecutionContext::with_object(self_obj,
+                                                              new_code,
+                                                              None));
+
+        assert_eq!(process.compiled_code().name, "b".to_string());
+        assert_eq!(process.context().return_register, Some(2));
+        assert_eq!(process.context().contexts().count(), 2);
+    }
+
+    #[test]
+    fn test_call_depth() {
+        let process = new_process();
+        let code = process.compiled_code();
+        let self_obj = ObjectPointer::null();
+
+        assert_eq!(process.call_depth(), 0);
+
+        process.push_context(ExecutionContext::with_object(self_obj,
+                                                           code,
+                                                           Some(2)));
+
+        assert_eq!(process.call_depth(), 1);
+
+        process.pop_context();
+        process.pop_context();
+
+        assert_eq!(process.call_depth(), 0);
+    }
+
+    #[test]
+    fn test_elide_call_frame() {
+        let process = new_process();
+
+        process.advance_line(2);
+        process.elide_call_frame();
+
+        assert_eq!(process.call_frame().tail_calls, 1);
+        assert!(process.call_frame().parent.is_none());
+    }
+
+    #[test]
+    fn test_park_and_wake_up() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+
+        thread.remember_process(process.clone());
+
+        assert!(process.park(thread.clone()));
+        assert!(process.is_waiting_for_message());
+
+        process.send_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(process.is_waiting_for_message(), false);
+        assert_eq!(thread.process_queue_size(), 1);
+        assert_eq!(thread.has_remembered_processes(), false);
+    }
+
+    #[test]
+    fn test_sleep_and_wake_up() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+
+        thread.remember_process(process.clone());
+
+        assert!(process.sleep(thread.clone()));
+        assert_eq!(process.status_name(), "sleeping");
+
+        // Messages don't wake up a sleeping process.
+        process.send_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(process.status_name(), "sleeping");
+
+        process.wake_up_from_sleep();
+
+        assert_eq!(process.status_name(), "scheduled");
+        assert_eq!(thread.process_queue_size(), 1);
+    }
+
+    #[test]
+    fn test_sleep_with_exit_signal() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+
+        process.signal_exit("kill".to_string());
+
+        assert_eq!(process.sleep(thread), false);
+    }
+
+    #[test]
+    fn test_wake_up_after_timeout() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+
+        thread.remember_process(process.clone());
+        process.set_receive_deadline(Some(20));
+
+        assert!(process.park(thread.clone()));
+
+        // The entry of an earlier receive expired.
+        process.wake_up_after_timeout(10);
+
+        assert!(process.is_waiting_for_message());
+
+        process.wake_up_after_timeout(20);
+
+        assert_eq!(process.is_waiting_for_message(), false);
+        assert_eq!(thread.process_queue_size(), 1);
+    }
+
+    #[test]
+    fn test_park_with_message() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+
+        process.send_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(process.park(thread), false);
+        assert_eq!(process.is_waiting_for_message(), false);
+    }
+
+    #[test]
+    fn test_park_with_checked_messages() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+
+        process.send_message(process.allocate_empty()).unwrap();
+        process.set_selective_receive(Some(SelectiveReceive::Waiting(1)));
+
+        assert!(process.park(thread));
+    }
+
+    #[test]
+    fn test_is_matching_message() {
+        let process = new_process();
+        let state = SelectiveReceive::Matching {
+            index: 0,
+            message: process.allocate_empty(),
+            depth: 0,
+        };
+
+        assert_eq!(process.is_matching_message(), false);
+
+        process.set_selective_receive(Some(state));
+
+        assert_eq!(process.is_matching_message(), false);
+
+        process.local_data_mut().call_depth = 1;
+
+        assert!(process.is_matching_message());
+    }
+
+    #[test]
+    fn test_remove_message() {
+        let process = new_process();
+        let first =
+            process.allocate_without_prototype(object_value::integer(1));
+        let second =
+            process.allocate_without_prototype(object_value::integer(2));
+
+        process.send_message(first).unwrap();
+        process.send_message(second).unwrap();
+
+        assert!(process.remove_message(1));
+        assert_eq!(process.remove_message(1), false);
+        assert!(process.peek_message(1).is_none());
+
+        let received = process.receive_message().unwrap();
+
+        assert_eq!(received.get().value.as_integer(), 1);
+    }
+
+    #[test]
+    fn test_roots_with_selective_receive() {
+        let process = new_process();
+        let message = process.allocate_empty();
+        let state = SelectiveReceive::Matching {
+            index: 0,
+            message: message,
+            depth: 0,
+        };
+
+        process.set_selective_receive(Some(state));
+
+        assert!(process.roots().iter().any(|root| *root.get() == message));
+    }
+
+    #[test]
+    fn test_link() {
+        let process = new_process();
+
+        let handle = ProcessHandle::new(2, 0);
+
+        assert!(process.link(handle));
+        assert_eq!(process.take_links(), vec![handle]);
+        assert_eq!(process.take_links().len(), 0);
+    }
+
+    #[test]
+    fn test_link_finished_process() {
+        let process = new_process();
+
+        process.finished();
+
+        assert_eq!(process.link(ProcessHandle::new(2, 0)), false);
+        assert_eq!(process.add_monitor(ProcessHandle::new(2, 0)), false);
+    }
+
+    #[test]
+    fn test_add_monitor() {
+        let process = new_process();
+
+        let handle = ProcessHandle::new(2, 0);
+
+        assert!(process.add_monitor(handle));
+        assert_eq!(process.take_monitors(), vec![handle]);
+    }
+
+    #[test]
+    fn test_signal_exit() {
+        let process = new_process();
+
+        process.signal_exit("a".to_string());
+        process.signal_exit("b".to_string());
+
+        assert!(process.has_exit_signal());
+        assert_eq!(process.exit_signal(), Some("a".to_string()));
+    }
+
+    #[test]
+    fn test_finished_with_pending_gc_suspension() {
+        let process = new_process();
+
+        process.local_data_mut().suspend_for_gc = true;
+        process.finished();
+
+        assert_eq!(process.is_alive(), false);
+        assert_eq!(process.should_suspend_for_gc(), false);
+    }
+
+    #[test]
+    fn test_release_memory() {
+        let process = new_process();
+        let global_alloc = process.local_data().allocator.global_allocator();
+        let blocks_before = unlock!(global_alloc.blocks).len();
+
+        process.allocate_empty();
+        process.send_message(process.allocate_empty()).unwrap();
+        process.finished();
+        process.release_memory();
+
+        assert_eq!(unlock!(global_alloc.blocks).len(), blocks_before);
+        assert!(process.receive_message().is_none());
+    }
+
+    #[test]
+    fn test_status_name() {
+        let process = new_process();
+
+        assert_eq!(process.status_name(), "scheduled");
+
+        process.failed();
+
+        assert_eq!(process.status_name(), "failed");
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let process = new_process();
+
+        assert_eq!(process.priority(), ProcessPriority::Normal);
+
+        process.set_priority(ProcessPriority::High);
+
+        assert_eq!(process.priority(), ProcessPriority::High);
+    }
+
+    #[test]
+    fn test_priority_from_integer() {
+        assert_eq!(ProcessPriority::from_integer(0), Ok(ProcessPriority::Low));
+        assert_eq!(ProcessPriority::from_integer(2), Ok(ProcessPriority::High));
+        assert!(ProcessPriority::from_integer(3).is_err());
+    }
+
+    #[test]
+    fn test_priority_reductions() {
+        assert_eq!(ProcessPriority::Low.reductions(1000), 501);
+        assert_eq!(ProcessPriority::Normal.reductions(1000), 1000);
+        assert_eq!(ProcessPriority::High.reductions(1000), 2000);
+    }
+
+    #[test]
+    fn test_send_frozen_message() {
+        let process = new_process();
+        let message = process.share(process.allocate_empty()).unwrap();
+
+        process.send_message(message).unwrap();
+
+        assert_eq!(process.heap_info().mailbox_blocks, 0);
+
+        let received = process.receive_message().unwrap();
+
+        assert!(received == message);
+        assert!(received.get().is_frozen());
+    }
+
+    #[test]
+    fn test_share() {
+        let process = new_process();
+        let original = process.allocate_empty();
+        let shared = process.share(original).unwrap();
+
+        assert!(shared != original);
+        assert!(shared.is_permanent());
+        assert!(shared.get().is_frozen());
+        assert!(original.get().is_frozen());
+        assert_eq!(process.local_data().allocator.frozen_heaps.len(), 1);
+    }
+
+    #[test]
+    fn test_release_unused_frozen_heaps() {
+        let process = new_process();
+        let used = process.share(process.allocate_empty()).unwrap();
+
+        process.share(process.allocate_empty()).unwrap();
+        process.set_register(0, used);
+        process.release_unused_frozen_heaps();
+
+        assert_eq!(process.local_data().allocator.frozen_heaps.len(), 1);
+    }
+
+    #[test]
+    fn test_receive_message_copies_to_heap() {
+        let process = new_process();
+        let message =
+            process.allocate_without_prototype(object_value::integer(5));
+
+        process.send_message(message).unwrap();
+
+        assert_eq!(process.heap_info().mailbox_blocks, 1);
+
+        let received = process.receive_message().unwrap();
+
+        assert!(received.is_young());
+        assert_eq!(received.get().value.as_integer(), 5);
+        assert_eq!(process.heap_info().mailbox_blocks, 0);
+    }
+
+    #[test]
+    fn test_peek_message_copies_to_heap() {
+        let process = new_process();
+
+        process.send_message(process.allocate_empty()).unwrap();
+
+        assert!(process.peek_message(0).unwrap().is_young());
+        assert_eq!(process.mailbox_size(), 1);
+    }
+
+    #[test]
+    fn test_delayed_message() {
+        let process = new_process();
+        let message =
+            process.delay_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(process.mailbox_size(), 0);
+
+        process.send_delayed_message(message);
+
+        assert_eq!(process.mailbox_size(), 1);
+        assert!(process.receive_message().is_some());
+        assert_eq!(process.heap_info().mailbox_blocks, 0);
+    }
+
+    #[test]
+    fn test_delayed_message_after_release_memory() {
+        let process = new_process();
+        let message =
+            process.delay_message(process.allocate_empty()).unwrap();
+
+        process.finished();
+        process.release_memory();
+        process.send_delayed_message(message);
+
+        assert_eq!(process.mailbox_size(), 0);
+    }
+
+    #[test]
+    fn test_mailbox_roots() {
+        let process = new_process();
+
+        process.send_message(process.allocate_empty()).unwrap();
+        process.delay_message(process.allocate_empty()).unwrap();
+
+        let roots = process.mailbox_roots();
+
+        assert_eq!(roots.len(), 2);
+        assert!(roots.iter().all(|ptr| ptr.get().generation().is_mailbox()));
+    }
+
+    #[test]
+    fn test_should_schedule_mailbox_gc() {
+        let process = new_process();
+
+        assert_eq!(process.should_schedule_mailbox_gc(), false);
+
+        unlock!(process.mailbox_allocator).block_allocations =
+            MAILBOX_BLOCK_ALLOCATION_THRESHOLD;
+
+        assert!(process.should_schedule_mailbox_gc());
+        assert_eq!(process.should_schedule_mailbox_gc(), false);
+    }
+
+    #[test]
+    fn test_capture_binding() {
+        let process = new_process();
+        let self_obj = process.allocate_empty();
+        let local =
+            process.allocate_without_prototype(object_value::integer(5));
+        let binding = Binding::new(self_obj);
+
+        binding.set_local(0, local);
+
+        process.capture_binding(&binding).unwrap();
+
+        let parent = process.binding().parent().unwrap();
+        let captured = parent.get_local(0).unwrap();
+
+        assert!(captured != local);
+        assert_eq!(captured.get().value.as_integer(), 5);
+        assert!(process.self_object() == parent.self_object());
+    }
+
+    #[test]
+    fn test_set_arguments() {
+        let process = new_process();
+        let argument =
+            process.allocate_without_prototype(object_value::integer(5));
+
+        process.set_arguments(&vec![argument]).unwrap();
+
+        let local = process.get_local(0).unwrap();
+
+        assert!(local != argument);
+        assert_eq!(local.get().value.as_integer(), 5);
+    }
+
+    #[test]
+    fn test_add_joiner() {
+        let process = new_process();
+        let handle = ProcessHandle::new(2, 0);
+
+        assert!(process.add_joiner(handle));
+        assert_eq!(process.take_joiners(), vec![handle]);
+        assert_eq!(process.add_joiner(handle), false);
+    }
+
+    #[test]
+    fn test_add_joiner_finished_process() {
+        let process = new_process();
+
+        process.finished();
+
+        assert_eq!(process.add_joiner(ProcessHandle::new(2, 0)), false);
+    }
+
+    #[test]
+    fn test_join_result() {
+        let process = new_process();
+        let handle = ProcessHandle::new(2, 0);
+
+        process.deliver_join_result(handle, |allocator| {
+            allocator.allocate(Object::new(object_value::integer(5)))
+        });
+
+        assert_eq!(process.heap_info().mailbox_blocks, 1);
+        assert_eq!(process.mailbox_roots().len(), 1);
+
+        let result = process.take_join_result(&handle).unwrap();
+
+        assert!(result.is_young());
+        assert_eq!(result.get().value.as_integer(), 5);
+        assert!(process.take_join_result(&handle).is_none());
+        assert_eq!(process.heap_info().mailbox_blocks, 0);
+    }
+
+    #[test]
+    fn test_park_while_joining() {
+        let process = new_process();
+        let thread = Thread::new(false, None);
+        let handle = ProcessHandle::new(2, 0);
+
+        thread.remember_process(process.clone());
+
+        process.send_message(process.allocate_empty()).unwrap();
+        process.set_joining(Some(handle));
+
+        assert!(process.park(thread.clone()));
+
+        process.deliver_join_result(handle, |allocator| {
+            allocator.allocate(Object::new(object_value::none()))
+        });
+
+        assert!(process.available_for_execution());
+        assert_eq!(process.park(thread), false);
+    }
+
+    #[test]
+    fn test_roots_with_return_value() {
+        let process = new_process();
+        let value = process.allocate_empty();
+
+        process.set_return_value(value);
+
+        assert!(process.roots().iter().any(|pointer| *pointer.get() == value));
+    }
+
+    #[test]
+    fn test_mailbox_size() {
+        let process = new_process();
+
+        process.send_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(process.mailbox_size(), 1);
+    }
+
+    #[test]
+    fn test_heap_info() {
+        let process = new_process();
+
+        process.allocate_empty();
+        process.send_message(process.allocate_empty()).unwrap();
+        process.collected(true);
+        process.collected(false);
+
+        assert_eq!(process.heap_info(),
+                   HeapInfo {
+                       young_blocks: 1,
+                       mature_blocks: 0,
+                       mailbox_blocks: 1,
+                       young_collections: 2,
+                       mature_collections: 1,
+                   });
+    }
+
+    #[test]
+    fn test_set_backtrace() {
+        let process = new_process();
+
+        assert!(process.backtrace().is_none());
+
+        process.set_backtrace("error".to_string());
+
+        assert_eq!(process.backtrace(), Some("error".to_string()));
+    }
+
     #[test]
     fn test_roots_doesnt_copy_pointers() {
         let process = new_process();
@@ -555,4 +2078,46 @@ mod tests {
         assert_eq!(process.get_register(0).unwrap().raw.raw as usize, 0x4);
         assert_eq!(process.self_object().raw.raw as usize, 0x4);
     }
+
+    #[test]
+    fn test_marking_barrier() {
+        let process = new_process();
+        let pointer = process.allocate_empty();
+
+        // Without an incremental collection the barrier does nothing.
+        process.marking_barrier(pointer);
+
+        assert_eq!(process.is_marking(), false);
+
+        let mark = IncrementalMark::new(Vec::new());
+
+        *process.incremental_mark_mut() = Some(mark);
+
+        process.set_local(0, pointer);
+
+        assert!(process.is_marking());
+        assert_eq!(process.incremental_mark_mut()
+                       .as_ref()
+                       .unwrap()
+                       .worklist
+                       .len(),
+                   1);
+    }
+
+    #[test]
+    fn test_should_schedule_gc_while_marking() {
+        let process = new_process();
+
+        assert_eq!(process.should_schedule_gc(), false);
+
+        let mark = IncrementalMark::new(Vec::new());
+
+        *process.incremental_mark_mut() = Some(mark);
+
+        assert!(process.should_schedule_gc());
+
+        process.gc_scheduled();
+
+        assert_eq!(process.should_schedule_gc(), false);
+    }
 }
@@ -0,0 +1,25 @@
+//! Handles for referring to processes
+//!
+//! Process IDs are reused once a process terminates. A ProcessHandle pairs a
+//! PID with the generation of the process slot it refers to, ensuring that a
+//! handle of a terminated process never refers to a newer process that happens
+//! to reuse the same PID.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProcessHandle {
+    /// The PID of the process.
+    pub pid: usize,
+
+    /// The generation of the process slot at the time the process was
+    /// started.
+    pub generation: usize,
+}
+
+impl ProcessHandle {
+    pub fn new(pid: usize, generation: usize) -> ProcessHandle {
+        ProcessHandle {
+            pid: pid,
+            generation: generation,
+        }
+    }
+}
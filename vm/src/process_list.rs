@@ -1,8 +1,21 @@
+use std::collections::HashMap;
+
 use process::RcProcess;
+use process_handle::ProcessHandle;
 
 pub struct ProcessList {
     processes: Vec<Option<RcProcess>>,
     indexes: Vec<usize>,
+
+    /// The generation of every process slot, incremented whenever a process
+    /// is removed from its slot.
+    generations: Vec<usize>,
+
+    /// The PIDs of processes registered under a name.
+    names: HashMap<String, usize>,
+
+    /// The names of registered processes, indexed by their PIDs.
+    registered: HashMap<usize, String>,
 }
 
 impl ProcessList {
@@ -10,6 +23,9 @@ impl ProcessList {
         ProcessList {
             processes: Vec::new(),
             indexes: Vec::new(),
+            generations: Vec::new(),
+            names: HashMap::new(),
+            registered: HashMap::new(),
         }
     }
 
@@ -24,6 +40,7 @@ impl ProcessList {
     pub fn add(&mut self, index: usize, process: RcProcess) {
         if index >= self.processes.len() {
             self.processes.insert(index, Some(process));
+            self.generations.insert(index, 0);
         } else {
             self.processes[index] = Some(process);
         }
@@ -33,7 +50,12 @@ impl ProcessList {
         let index = process.pid;
 
         self.processes[index] = None;
+        self.generations[index] += 1;
         self.indexes.push(index);
+
+        if let Some(name) = self.registered.remove(&index) {
+            self.names.remove(&name);
+        }
     }
 
     pub fn get(&self, index: usize) -> Option<RcProcess> {
@@ -45,4 +67,170 @@ impl ProcessList {
             None
         }
     }
+
+    /// Returns a handle for the process with the given PID.
+    pub fn handle(&self, pid: usize) -> ProcessHandle {
+        ProcessHandle::new(pid, self.generations[pid])
+    }
+
+    /// Returns the process a handle refers to.
+    ///
+    /// None is returned if the process terminated, even if its PID has since
+    /// been reused by another process.
+    pub fn get_handle(&self, handle: &ProcessHandle) -> Option<RcProcess> {
+        match self.generations.get(handle.pid) {
+            Some(generation) if *generation == handle.generation => {
+                self.get(handle.pid)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the handles of all live processes.
+    pub fn handles(&self) -> Vec<ProcessHandle> {
+        self.processes
+            .iter()
+            .enumerate()
+            .filter(|&(_, process)| process.is_some())
+            .map(|(pid, _)| self.handle(pid))
+            .collect()
+    }
+
+    /// Registers a process under the given name.
+    ///
+    /// Registering fails if the name is already in use, if the process is
+    /// already registered under a different name, or if the process no longer
+    /// exists.
+    pub fn register(&mut self, name: String, handle: &ProcessHandle) -> bool {
+        if self.names.contains_key(&name) ||
+           self.registered.contains_key(&handle.pid) ||
+           self.get_handle(handle).is_none() {
+            return false;
+        }
+
+        self.registered.insert(handle.pid, name.clone());
+        self.names.insert(name, handle.pid);
+
+        true
+    }
+
+    /// Removes the given name from the registry.
+    pub fn unregister(&mut self, name: &String) -> bool {
+        if let Some(pid) = self.names.remove(name) {
+            self.registered.remove(&pid);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the process registered under the given name.
+    pub fn whereis(&self, name: &String) -> Option<RcProcess> {
+        self.names.get(name).and_then(|pid| self.get(*pid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compiled_code::CompiledCode;
+    use immix::global_allocator::GlobalAllocator;
+    use object_pointer::ObjectPointer;
+    use process::{Process, RcProcess};
+
+    fn new_process(pid: usize) -> RcProcess {
+        let code = CompiledCode::with_rc("a".to_string(),
+                                         "a".to_string(),
+                                         1,
+                                         Vec::new());
+
+        Process::from_code(pid,
+                           code,
+                           ObjectPointer::null(),
+                           GlobalAllocator::new())
+    }
+
+    fn add_process(list: &mut ProcessList) -> RcProcess {
+        let pid = list.reserve_pid();
+        let process = new_process(pid);
+
+        list.add(pid, process.clone());
+
+        process
+    }
+
+    #[test]
+    fn test_get_handle() {
+        let mut list = ProcessList::new();
+        let process = add_process(&mut list);
+        let handle = list.handle(process.pid);
+
+        assert!(list.get_handle(&handle).is_some());
+    }
+
+    #[test]
+    fn test_get_handle_with_reused_pid() {
+        let mut list = ProcessList::new();
+        let process = add_process(&mut list);
+        let old_handle = list.handle(process.pid);
+
+        list.remove(process);
+
+        let new_process = add_process(&mut list);
+        let new_handle = list.handle(new_process.pid);
+
+        assert_eq!(old_handle.pid, new_handle.pid);
+        assert!(list.get_handle(&old_handle).is_none());
+        assert!(list.get_handle(&new_handle).is_some());
+    }
+
+    #[test]
+    fn test_handles() {
+        let mut list = ProcessList::new();
+        let process1 = add_process(&mut list);
+        let process2 = add_process(&mut list);
+
+        list.remove(process1);
+
+        assert_eq!(list.handles(), vec![list.handle(process2.pid)]);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut list = ProcessList::new();
+        let process = add_process(&mut list);
+        let handle = list.handle(process.pid);
+
+        assert!(list.register("a".to_string(), &handle));
+        assert_eq!(list.register("a".to_string(), &handle), false);
+        assert_eq!(list.register("b".to_string(), &handle), false);
+        assert!(list.whereis(&"a".to_string()).is_some());
+    }
+
+    #[test]
+    fn test_remove_unregisters_process() {
+        let mut list = ProcessList::new();
+        let process = add_process(&mut list);
+        let handle = list.handle(process.pid);
+
+        list.register("a".to_string(), &handle);
+        list.remove(process);
+
+        assert!(list.whereis(&"a".to_string()).is_none());
+        assert_eq!(list.register("a".to_string(), &handle), false);
+    }
+
+    #[test]
+    fn test_unregister() {
+        let mut list = ProcessList::new();
+        let process = add_process(&mut list);
+        let handle = list.handle(process.pid);
+
+        list.register("a".to_string(), &handle);
+
+        assert!(list.unregister(&"a".to_string()));
+        assert_eq!(list.unregister(&"a".to_string()), false);
+        assert!(list.whereis(&"a".to_string()).is_none());
+    }
 }
@@ -0,0 +1,186 @@
+//! Queues of processes scheduled on a thread
+//!
+//! A ProcessQueue stores processes per priority. Processes with a higher
+//! priority are taken from the queue first. To ensure processes with a lower
+//! priority still make progress the queue periodically prefers a lower
+//! priority, even if processes with a higher priority are waiting.
+//!
+//! Processes of the same priority are processed in FIFO order.
+
+use std::collections::VecDeque;
+
+use process::{RcProcess, ProcessPriority};
+
+/// The number of processes to take from the queue before preferring
+/// processes with a normal priority.
+const NORMAL_INTERVAL: usize = 4;
+
+/// The number of processes to take from the queue before preferring
+/// processes with a low priority.
+const LOW_INTERVAL: usize = 16;
+
+pub struct ProcessQueue {
+    high: VecDeque<RcProcess>,
+    normal: VecDeque<RcProcess>,
+    low: VecDeque<RcProcess>,
+
+    /// The number of processes taken from the queue so far.
+    ticks: usize,
+}
+
+impl ProcessQueue {
+    pub fn new() -> ProcessQueue {
+        ProcessQueue {
+            high: VecDeque::new(),
+            normal: VecDeque::new(),
+            low: VecDeque::new(),
+            ticks: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.high.len() + self.normal.len() + self.low.len()
+    }
+
+    /// Adds a process to the end of the queue of its priority.
+    pub fn push(&mut self, process: RcProcess) {
+        match process.priority() {
+            ProcessPriority::High => self.high.push_back(process),
+            ProcessPriority::Normal => self.normal.push_back(process),
+            ProcessPriority::Low => self.low.push_back(process),
+        }
+    }
+
+    /// Removes the next process to run from the queue.
+    pub fn pop(&mut self) -> Option<RcProcess> {
+        self.ticks = self.ticks.wrapping_add(1);
+
+        if self.ticks % LOW_INTERVAL == 0 {
+            self.pop_in_order(&[ProcessPriority::Low,
+                                ProcessPriority::Normal,
+                                ProcessPriority::High])
+        } else if self.ticks % NORMAL_INTERVAL == 0 {
+            self.pop_in_order(&[ProcessPriority::Normal,
+                                ProcessPriority::Low,
+                                ProcessPriority::High])
+        } else {
+            self.pop_in_order(&[ProcessPriority::High,
+                                ProcessPriority::Normal,
+                                ProcessPriority::Low])
+        }
+    }
+
+    /// Removes the oldest process with the highest priority, allowing another
+    /// thread to run it.
+    pub fn steal(&mut self) -> Option<RcProcess> {
+        self.pop_in_order(&[ProcessPriority::High,
+                            ProcessPriority::Normal,
+                            ProcessPriority::Low])
+    }
+
+    fn pop_in_order(&mut self,
+                    priorities: &[ProcessPriority])
+                    -> Option<RcProcess> {
+        for priority in priorities {
+            let queue = match *priority {
+                ProcessPriority::High => &mut self.high,
+                ProcessPriority::Normal => &mut self.normal,
+                ProcessPriority::Low => &mut self.low,
+            };
+
+            if let Some(process) = queue.pop_front() {
+                return Some(process);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compiled_code::CompiledCode;
+    use immix::global_allocator::GlobalAllocator;
+    use object_pointer::ObjectPointer;
+    use process::Process;
+
+    fn new_process(pid: usize, priority: ProcessPriority) -> RcProcess {
+        let code = CompiledCode::with_rc("a".to_string(),
+                                         "a".to_string(),
+                                         1,
+                                         Vec::new());
+
+        let process = Process::from_code(pid,
+                                         code,
+                                         ObjectPointer::null(),
+                                         GlobalAllocator::new());
+
+        process.set_priority(priority);
+
+        process
+    }
+
+    #[test]
+    fn test_len() {
+        let mut queue = ProcessQueue::new();
+
+        queue.push(new_process(1, ProcessPriority::High));
+        queue.push(new_process(2, ProcessPriority::Low));
+
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_pop_by_priority() {
+        let mut queue = ProcessQueue::new();
+
+        queue.push(new_process(1, ProcessPriority::Low));
+        queue.push(new_process(2, ProcessPriority::Normal));
+        queue.push(new_process(3, ProcessPriority::High));
+
+        assert_eq!(queue.pop().unwrap().pid, 3);
+        assert_eq!(queue.pop().unwrap().pid, 2);
+        assert_eq!(queue.pop().unwrap().pid, 1);
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn test_pop_same_priority_in_order() {
+        let mut queue = ProcessQueue::new();
+
+        queue.push(new_process(1, ProcessPriority::Normal));
+        queue.push(new_process(2, ProcessPriority::Normal));
+
+        assert_eq!(queue.pop().unwrap().pid, 1);
+        assert_eq!(queue.pop().unwrap().pid, 2);
+    }
+
+    #[test]
+    fn test_pop_low_priority_makes_progress() {
+        let mut queue = ProcessQueue::new();
+
+        queue.push(new_process(1, ProcessPriority::Low));
+
+        for _ in 0..LOW_INTERVAL {
+            queue.push(new_process(2, ProcessPriority::High));
+        }
+
+        let pids: Vec<usize> = (0..LOW_INTERVAL)
+            .map(|_| queue.pop().unwrap().pid)
+            .collect();
+
+        assert!(pids.contains(&1));
+    }
+
+    #[test]
+    fn test_steal() {
+        let mut queue = ProcessQueue::new();
+
+        queue.push(new_process(1, ProcessPriority::Low));
+        queue.push(new_process(2, ProcessPriority::High));
+
+        assert_eq!(queue.steal().unwrap().pid, 2);
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -80,12 +80,32 @@ impl<T> Queue<T> {
         unlock!(self.values).pop_front()
     }
 
+    /// Removes the value at the given index from the queue and returns it.
+    ///
+    /// The order of the remaining values is preserved.
+    pub fn remove(&self, index: usize) -> Option<T> {
+        unlock!(self.values).remove(index)
+    }
+
     /// Returns the amount of values in the queue.
     pub fn len(&self) -> usize {
         unlock!(self.values).len()
     }
 }
 
+impl<T: Clone> Queue<T> {
+    /// Returns a copy of the value at the given index without removing it
+    /// from the queue.
+    pub fn get(&self, index: usize) -> Option<T> {
+        unlock!(self.values).get(index).cloned()
+    }
+
+    /// Returns a copy of all values in the queue, in FIFO order.
+    pub fn to_vec(&self) -> Vec<T> {
+        unlock!(self.values).iter().cloned().collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,6 +148,43 @@ mod tests {
         assert!(queue.pop_nonblock().is_none());
     }
 
+    #[test]
+    fn test_get() {
+        let queue = Queue::new();
+
+        queue.push(10);
+        queue.push(20);
+
+        assert_eq!(queue.get(1), Some(20));
+        assert!(queue.get(2).is_none());
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let queue = Queue::new();
+
+        queue.push(10);
+        queue.push(20);
+
+        assert_eq!(queue.to_vec(), vec![10, 20]);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_remove() {
+        let queue = Queue::new();
+
+        queue.push(10);
+        queue.push(20);
+        queue.push(30);
+
+        assert_eq!(queue.remove(1), Some(20));
+        assert!(queue.remove(5).is_none());
+        assert_eq!(queue.pop(), 10);
+        assert_eq!(queue.pop(), 30);
+    }
+
     #[test]
     fn test_len() {
         let queue = Queue::new();
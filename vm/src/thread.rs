@@ -6,12 +6,13 @@ use std::thread;
 use std::time::Duration;
 
 use process::RcProcess;
+use process_queue::ProcessQueue;
 
 pub type RcThread = Arc<Thread>;
 pub type JoinHandle = thread::JoinHandle<()>;
 
 pub struct Thread {
-    pub process_queue: Mutex<Vec<RcProcess>>,
+    pub process_queue: Mutex<ProcessQueue>,
     pub remembered_processes: Mutex<HashSet<RcProcess>>,
     pub wakeup_signaler: Condvar,
     pub should_stop: Mutex<bool>,
@@ -22,7 +23,7 @@ pub struct Thread {
 impl Thread {
     pub fn new(main_thread: bool, handle: Option<JoinHandle>) -> RcThread {
         let thread = Thread {
-            process_queue: Mutex::new(Vec::new()),
+            process_queue: Mutex::new(ProcessQueue::new()),
             remembered_processes: Mutex::new(HashSet::new()),
             wakeup_signaler: Condvar::new(),
             should_stop: Mutex::new(false),
@@ -85,19 +86,42 @@ impl Thread {
         unlock!(self.remembered_processes).insert(process);
     }
 
-    pub fn wait_for_work(&self) {
-        let mut queue = unlock!(self.process_queue);
-        let timeout = Duration::from_millis(5);
+    pub fn forget_process(&self, process: &RcProcess) {
+        unlock!(self.remembered_processes).remove(process);
+    }
 
-        while queue.len() == 0 {
-            if self.should_stop() {
-                return;
+    /// Schedules a remembered process using its PID.
+    ///
+    /// This is used for waking up processes that are waiting for a message.
+    pub fn resume(&self, pid: usize) {
+        let process_opt = {
+            let mut remembered = unlock!(self.remembered_processes);
+
+            let found =
+                remembered.iter().find(|process| process.pid == pid).cloned();
+
+            if let Some(ref process) = found {
+                remembered.remove(process);
             }
 
-            let (new_queue, _) =
-                self.wakeup_signaler.wait_timeout(queue, timeout).unwrap();
+            found
+        };
 
-            queue = new_queue;
+        if let Some(process) = process_opt {
+            self.schedule(process);
+        }
+    }
+
+    /// Waits for a process to be scheduled.
+    ///
+    /// This method returns after a short timeout even if no process was
+    /// scheduled, allowing the caller to steal work from other threads.
+    pub fn wait_for_work(&self) {
+        let queue = unlock!(self.process_queue);
+        let timeout = Duration::from_millis(5);
+
+        if queue.len() == 0 && !self.should_stop() {
+            self.wakeup_signaler.wait_timeout(queue, timeout).unwrap();
         }
     }
 
@@ -106,4 +130,9 @@ impl Thread {
 
         queue.pop()
     }
+
+    /// Removes a process from the queue, allowing another thread to run it.
+    pub fn steal_process(&self) -> Option<RcProcess> {
+        unlock!(self.process_queue).steal()
+    }
 }
@@ -63,6 +63,32 @@ impl ThreadList {
         }
     }
 
+    /// Steals a process for the given thread from the thread with the most
+    /// processes queued up.
+    ///
+    /// Processes are never stolen from the main thread, as the VM terminates
+    /// once the main thread runs out of work.
+    pub fn steal(&self, thief: &RcThread) -> Option<RcProcess> {
+        let thief_ptr = &**thief as *const _;
+        let mut victim: Option<&RcThread> = None;
+        let mut victim_size = 0;
+
+        for thread in self.threads.iter() {
+            if thread.main_thread || &**thread as *const _ == thief_ptr {
+                continue;
+            }
+
+            let size = thread.process_queue_size();
+
+            if size > victim_size {
+                victim = Some(thread);
+                victim_size = size;
+            }
+        }
+
+        victim.and_then(|thread| thread.steal_process())
+    }
+
     pub fn schedule(&mut self, process: RcProcess) {
         let mut thread_idx = 0;
         let mut queue_size = None;
@@ -0,0 +1,183 @@
+//! Synchronized timers for values that expire at a certain point in time
+//!
+//! A Timer can be used to schedule values (e.g. processes) that should be
+//! processed once their deadline expires, such as processes waiting for a
+//! message with a timeout.
+//!
+//! Deadlines are expressed in nanoseconds as returned by
+//! `time::precise_time_ns()`.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex, Condvar};
+use std::time::Duration;
+
+use time;
+
+/// A value scheduled to expire at a certain deadline.
+struct Entry<T> {
+    deadline: u64,
+    value: T,
+}
+
+pub struct Timer<T> {
+    entries: Mutex<BinaryHeap<Entry<T>>>,
+    signaler: Condvar,
+}
+
+pub type RcTimer<T> = Arc<Timer<T>>;
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Entry<T>) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Entry<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    // BinaryHeap is a max-heap, so the ordering is reversed in order to pop the
+    // entry with the earliest deadline first.
+    fn cmp(&self, other: &Entry<T>) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl<T> Timer<T> {
+    /// Returns a new Timer.
+    pub fn new() -> Self {
+        Timer {
+            entries: Mutex::new(BinaryHeap::new()),
+            signaler: Condvar::new(),
+        }
+    }
+
+    /// Returns a new Timer that can be shared between threads.
+    pub fn with_rc() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+
+    /// Schedules a value to expire at the given deadline.
+    pub fn schedule(&self, deadline: u64, value: T) {
+        let mut entries = unlock!(self.entries);
+
+        entries.push(Entry {
+            deadline: deadline,
+            value: value,
+        });
+
+        // The new entry may expire before the one a waiting thread is currently
+        // waiting for.
+        self.signaler.notify_all();
+    }
+
+    /// Removes all scheduled values for which the closure returns true.
+    pub fn cancel<F>(&self, cancel: F)
+        where F: Fn(&T) -> bool
+    {
+        let mut entries = unlock!(self.entries);
+
+        let retained = entries.drain()
+            .filter(|entry| !cancel(&entry.value))
+            .collect();
+
+        *entries = retained;
+    }
+
+    /// Returns all values of which the deadline has expired.
+    ///
+    /// If no values have expired yet this method will block until at least a
+    /// single value has expired.
+    pub fn wait_for_expired(&self) -> Vec<T> {
+        let mut entries = unlock!(self.entries);
+
+        loop {
+            let now = time::precise_time_ns();
+            let mut expired = Vec::new();
+
+            while entries.peek().map_or(false, |entry| entry.deadline <= now) {
+                expired.push(entries.pop().unwrap().value);
+            }
+
+            if expired.len() > 0 {
+                return expired;
+            }
+
+            let wait_for = entries.peek().map(|entry| entry.deadline - now);
+
+            entries = if let Some(nanos) = wait_for {
+                let duration = Duration::new(nanos / 1_000_000_000,
+                                             (nanos % 1_000_000_000) as u32);
+
+                self.signaler.wait_timeout(entries, duration).unwrap().0
+            } else {
+                self.signaler.wait(entries).unwrap()
+            };
+        }
+    }
+
+    /// Returns the amount of scheduled values.
+    pub fn len(&self) -> usize {
+        unlock!(self.entries).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use time;
+
+    #[test]
+    fn test_schedule() {
+        let timer = Timer::new();
+
+        timer.schedule(10, 1);
+
+        assert_eq!(timer.len(), 1);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let timer = Timer::new();
+        let now = time::precise_time_ns();
+
+        timer.schedule(now - 10, 1);
+        timer.schedule(now - 20, 2);
+        timer.schedule(now - 30, 3);
+        timer.cancel(|value| *value == 2);
+
+        assert_eq!(timer.len(), 2);
+        assert_eq!(timer.wait_for_expired(), vec![3, 1]);
+    }
+
+    #[test]
+    fn test_wait_for_expired() {
+        let timer = Timer::new();
+        let now = time::precise_time_ns();
+
+        timer.schedule(now + 1_000_000_000_000, 1);
+        timer.schedule(now - 10, 2);
+        timer.schedule(now - 20, 3);
+
+        assert_eq!(timer.wait_for_expired(), vec![3, 2]);
+        assert_eq!(timer.len(), 1);
+    }
+
+    #[test]
+    fn test_wait_for_expired_blocking() {
+        let timer = Timer::with_rc();
+        let timer_clone = timer.clone();
+        let handle = thread::spawn(move || timer_clone.wait_for_expired());
+
+        timer.schedule(time::precise_time_ns() + 1_000_000, 1);
+
+        assert_eq!(handle.join().unwrap(), vec![1]);
+    }
+}
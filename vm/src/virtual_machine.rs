@@ -8,6 +8,8 @@ use std::thread;
 use std::sync::{Arc, RwLock};
 use std::sync::mpsc::channel;
 
+use time;
+
 use immix::copy_object::CopyObject;
 use immix::global_allocator::{GlobalAllocator, RcGlobalAllocator};
 use immix::permanent_allocator::PermanentAllocator;
@@ -18,28 +20,66 @@ use call_frame::CallFrame;
 use compiled_code::RcCompiledCode;
 use config::Config;
 use errors;
+use gc::parallel_mark::RcParallelMark;
 use gc::thread::Thread as GcThread;
 use gc::request::{Request as GcRequest, Generation as GcGeneration};
 use instruction::{InstructionType, Instruction};
+use object::Object;
 use object_pointer::ObjectPointer;
 use object_value;
 use virtual_machine_error::VirtualMachineError;
 use virtual_machine_result::*;
-use process::{RcProcess, Process};
+use process::{RcProcess, Process, ProcessPriority, SelectiveReceive};
+use process_handle::ProcessHandle;
 use process_list::ProcessList;
 use execution_context::ExecutionContext;
 use thread::{RcThread, JoinHandle as ThreadJoinHandle};
 use thread_list::ThreadList;
+use timer::Timer;
 use queue::Queue;
 
+/// The name of the method to invoke when a message is sent that a receiver
+/// doesn't respond to.
+const METHOD_MISSING: &'static str = "method_missing";
+
+/// The exit reason of a process that finished without any errors.
+const EXIT_NORMAL: &'static str = "normal";
+
+/// The exit reason used when linking or monitoring a process that is no longer
+/// alive.
+const EXIT_NO_PROCESS: &'static str = "noproc";
+
+/// The tag of messages sent to linked processes that trap exits.
+const EXIT_MESSAGE: &'static str = "exit";
+
+/// The tag of messages sent to monitoring processes.
+const DOWN_MESSAGE: &'static str = "down";
+
 pub type RcVirtualMachineState = Arc<VirtualMachineState>;
 
+/// An event to process once its timer expires.
+pub enum TimerEvent {
+    /// A process waiting for a message with the given receive deadline should
+    /// be woken up.
+    ReceiveTimeout(RcProcess, u64),
+
+    /// A sleeping process should be woken up.
+    Sleep(RcProcess),
+
+    /// A message (allocated on the mailbox heap of the receiver) should be
+    /// sent to a process.
+    SendMessage(ProcessHandle, ObjectPointer),
+}
+
 pub struct VirtualMachineState {
     pub gc_requests: Queue<GcRequest>,
+    pub mark_requests: Queue<RcParallelMark>,
+    pub timer: Timer<TimerEvent>,
     pub config: Config,
     executed_files: RwLock<HashSet<String>>,
     threads: RwLock<ThreadList>,
     processes: RwLock<ProcessList>,
+    main_process: RwLock<Option<RcProcess>>,
     exit_status: RwLock<Result<(), ()>>,
 
     permanent_allocator: RwLock<PermanentAllocator>,
@@ -55,6 +95,7 @@ pub struct VirtualMachineState {
     method_prototype: ObjectPointer,
     compiled_code_prototype: ObjectPointer,
     binding_prototype: ObjectPointer,
+    process_prototype: ObjectPointer,
     true_object: ObjectPointer,
     false_object: ObjectPointer,
 }
@@ -79,6 +120,7 @@ impl VirtualMachineState {
         let method_proto = perm_alloc.allocate_empty();
         let cc_proto = perm_alloc.allocate_empty();
         let binding_proto = perm_alloc.allocate_empty();
+        let process_proto = perm_alloc.allocate_empty();
 
         let true_obj = perm_alloc.allocate_empty();
         let false_obj = perm_alloc.allocate_empty();
@@ -93,7 +135,10 @@ impl VirtualMachineState {
             executed_files: RwLock::new(HashSet::new()),
             threads: RwLock::new(ThreadList::new()),
             processes: RwLock::new(ProcessList::new()),
+            main_process: RwLock::new(None),
             gc_requests: Queue::new(),
+            mark_requests: Queue::new(),
+            timer: Timer::new(),
             exit_status: RwLock::new(Ok(())),
             permanent_allocator: RwLock::new(perm_alloc),
             global_allocator: global_alloc,
@@ -108,6 +153,7 @@ impl VirtualMachineState {
             method_prototype: method_proto,
             compiled_code_prototype: cc_proto,
             binding_prototype: binding_proto,
+            process_prototype: process_proto,
             true_object: true_obj,
             false_object: false_obj,
         };
@@ -139,10 +185,20 @@ impl VirtualMachine {
             self.start_gc_thread()
         }
 
+        // The GC thread performing a collection takes part in marking, thus we
+        // need one mark thread less than the number of marking threads.
+        for _ in 1..self.config().mark_threads {
+            self.start_mark_thread()
+        }
+
+        self.start_timer_thread();
+
         let thread = self.allocate_main_thread();
         let (_, process) =
             self.allocate_process(code, self.state.top_level.clone());
 
+        *write_lock!(self.state.main_process) = Some(process.clone());
+
         thread.schedule(process);
 
         self.run_thread(thread);
@@ -162,7 +218,7 @@ impl VirtualMachine {
     fn allocate_process(&self,
                         code: RcCompiledCode,
                         self_obj: ObjectPointer)
-                        -> (usize, RcProcess) {
+                        -> (ProcessHandle, RcProcess) {
         let mut processes = write_lock!(self.state.processes);
         let pid = processes.reserve_pid();
         let process = Process::from_code(pid,
@@ -172,7 +228,15 @@ impl VirtualMachine {
 
         processes.add(pid, process.clone());
 
-        (pid, process)
+        (processes.handle(pid), process)
+    }
+
+    fn allocate_process_handle(&self,
+                               process: &RcProcess,
+                               handle: ProcessHandle)
+                               -> ObjectPointer {
+        process.allocate(object_value::process_handle(handle),
+                         self.state.process_prototype.clone())
     }
 
     fn allocate_method(&self,
@@ -193,8 +257,17 @@ impl VirtualMachine {
 
     /// Runs a single Process.
     fn run(&self, thread: RcThread, process: RcProcess) -> EmptyResult {
-        let mut reductions = self.config().reductions;
+        let mut reductions =
+            process.priority().reductions(self.config().reductions);
         let mut suspend_retry = false;
+        let mut wait_retry = false;
+        let mut sleep_retry = false;
+
+        // A process that received an exit signal while waiting to be run
+        // terminates right away.
+        if process.has_exit_signal() {
+            return Ok(());
+        }
 
         process.running();
 
@@ -294,6 +367,13 @@ impl VirtualMachine {
                              code,
                              instruction);
                     }
+                    InstructionType::GetProcessPrototype => {
+                        run!(self,
+                             ins_get_process_prototype,
+                             process,
+                             code,
+                             instruction);
+                    }
                     InstructionType::GetTrue => {
                         run!(self, ins_get_true, process, code, instruction);
                     }
@@ -424,8 +504,8 @@ impl VirtualMachine {
                                           instruction);
                     }
                     InstructionType::Goto => {
-                        index = run!(self, ins_goto, process, code, instruction)
-                            .unwrap();
+                        goto_index =
+                            run!(self, ins_goto, process, code, instruction);
                     }
                     InstructionType::DefMethod => {
                         run!(self, ins_def_method, process, code, instruction);
@@ -722,6 +802,26 @@ impl VirtualMachine {
                              code,
                              instruction);
                     }
+                    InstructionType::Freeze => {
+                        run!(self, ins_freeze, process, code, instruction);
+                    }
+                    InstructionType::SpawnClosure => {
+                        run!(self, ins_spawn_closure, process, code, instruction);
+                    }
+                    InstructionType::SpawnProcessWithArguments => {
+                        run!(self,
+                             ins_spawn_process_with_arguments,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::JoinProcess => {
+                        wait_retry = run!(self,
+                                          ins_join_process,
+                                          process,
+                                          code,
+                                          instruction);
+                    }
                     InstructionType::SpawnProcess => {
                         run!(self, ins_spawn_process, process, code, instruction);
                     }
@@ -739,6 +839,159 @@ impl VirtualMachine {
                                              code,
                                              instruction);
                     }
+                    InstructionType::SendAfter => {
+                        run!(self, ins_send_after, process, code, instruction);
+                    }
+                    InstructionType::TimeMonotonicNanos => {
+                        run!(self,
+                             ins_time_monotonic_nanos,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::TimeWallClock => {
+                        run!(self,
+                             ins_time_wall_clock,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::TimeUtcTimestamp => {
+                        run!(self,
+                             ins_time_utc_timestamp,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::ProcessSleep => {
+                        sleep_retry = run!(self,
+                                           ins_process_sleep,
+                                           process,
+                                           code,
+                                           instruction);
+                    }
+                    InstructionType::ReceiveProcessMessageTimeout => {
+                        wait_retry = run!(self,
+                                          ins_receive_process_message_timeout,
+                                          process,
+                                          code,
+                                          instruction);
+                    }
+                    InstructionType::ReceiveMatchingProcessMessage => {
+                        // The instruction is retried after running the
+                        // predicate, or once a new message is received.
+                        process.set_instruction_index(index - 1);
+
+                        let retry = run!(self,
+                                         ins_receive_matching_process_message,
+                                         process,
+                                         code,
+                                         instruction);
+
+                        if retry {
+                            if process.should_wait_for_message() {
+                                return Ok(());
+                            }
+
+                            continue 'exec_loop;
+                        }
+                    }
+                    InstructionType::LinkProcess => {
+                        run!(self, ins_link_process, process, code, instruction);
+                    }
+                    InstructionType::MonitorProcess => {
+                        run!(self,
+                             ins_monitor_process,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::TrapExits => {
+                        run!(self, ins_trap_exits, process, code, instruction);
+                    }
+                    InstructionType::ProcessExit => {
+                        run!(self, ins_process_exit, process, code, instruction);
+
+                        return Ok(());
+                    }
+                    InstructionType::ProcessKill => {
+                        run!(self, ins_process_kill, process, code, instruction);
+
+                        // A process may kill itself.
+                        if process.has_exit_signal() {
+                            return Ok(());
+                        }
+                    }
+                    InstructionType::ProcessStatus => {
+                        run!(self,
+                             ins_process_status,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::ProcessMailboxSize => {
+                        run!(self,
+                             ins_process_mailbox_size,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::ProcessList => {
+                        run!(self,
+                             ins_process_list,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::ProcessPriority => {
+                        run!(self,
+                             ins_process_priority,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::SetProcessPriority => {
+                        run!(self,
+                             ins_set_process_priority,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::ProcessHeapInfo => {
+                        run!(self,
+                             ins_process_heap_info,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::RegisterProcess => {
+                        run!(self,
+                             ins_register_process,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::UnregisterProcess => {
+                        run!(self,
+                             ins_unregister_process,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::WhereisProcess => {
+                        run!(self,
+                             ins_whereis_process,
+                             process,
+                             code,
+                             instruction);
+                    }
+                    InstructionType::SendNamedProcessMessage => {
+                        run!(self,
+                             ins_send_named_process_message,
+                             process,
+                             code,
+                             instruction);
+                    }
                     InstructionType::GetCurrentPid => {
                         run!(self,
                              ins_get_current_pid,
@@ -771,7 +1024,31 @@ impl VirtualMachine {
                     return Ok(());
                 }
 
+                // Retry the current instruction once a message is received.
+                if wait_retry {
+                    process.set_instruction_index(index - 1);
+                    process.suspend_for_message();
+
+                    return Ok(());
+                }
+
+                // Retry the current instruction once the process wakes up.
+                if sleep_retry {
+                    process.set_instruction_index(index - 1);
+
+                    return Ok(());
+                }
+
                 if let Some(idx) = goto_index {
+                    // Loops jump backwards, thus we check for exit signals
+                    // here to ensure a process stuck in a loop can still be
+                    // terminated.
+                    if idx < index && process.has_exit_signal() {
+                        process.set_instruction_index(idx);
+
+                        return Ok(());
+                    }
+
                     index = idx;
                     goto_index = None;
                 }
@@ -798,7 +1075,7 @@ impl VirtualMachine {
 
             self.gc_safepoint(thread.clone(), process.clone());
 
-            if process.should_suspend_for_gc() {
+            if process.should_suspend_for_gc() || process.has_exit_signal() {
                 return Ok(());
             }
 
@@ -918,8 +1195,12 @@ impl VirtualMachine {
                                           instruction);
 
             if is_permanent && proto.is_local() {
-                proto = write_lock!(self.state.permanent_allocator)
-                    .copy_object(proto);
+                let mut perm_alloc =
+                    write_lock!(self.state.permanent_allocator);
+
+                proto = try_vm_error!(perm_alloc.copy_object(proto)
+                                          .map_err(|err| err.to_string()),
+                                      instruction);
             }
 
             obj.get_mut().set_prototype(proto);
@@ -944,7 +1225,10 @@ impl VirtualMachine {
         let source = instruction_object!(instruction, process, 0);
         let proto = instruction_object!(instruction, process, 1);
 
+        ensure_not_frozen!(instruction, source.get());
+
         source.get_mut().set_prototype(proto);
+        process.write_barrier(source, proto);
 
         Ok(())
     }
@@ -1129,6 +1413,22 @@ impl VirtualMachine {
         Ok(())
     }
 
+    /// Gets the prototype to use for process handles.
+    ///
+    /// This instruction requires one argument: the register to store the
+    /// prototype in.
+    fn ins_get_process_prototype(&self,
+                                 process: RcProcess,
+                                 _: RcCompiledCode,
+                                 instruction: &Instruction)
+                                 -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+
+        process.set_register(register, self.state.process_prototype.clone());
+
+        Ok(())
+    }
+
     /// Gets the prototype to use for compiled code objects.
     ///
     /// This instruction requires one argument: the register to store the
@@ -1313,6 +1613,7 @@ impl VirtualMachine {
 
         if let Some(binding) = process.binding().find_parent(depth) {
             binding.set_local(index, value);
+            process.marking_barrier(value);
         } else {
             return_vm_error!(format!("No binding for depth {}", depth),
                              instruction.line);
@@ -1367,9 +1668,12 @@ impl VirtualMachine {
         let source_ptr = instruction_object!(instruction, process, 2);
         let name = try_vm_error!(code.string(name_index), instruction);
 
+        ensure_not_frozen!(instruction, target_ptr.get());
+
         let source = copy_if_permanent!(self.state.permanent_allocator,
                                         source_ptr,
-                                        target_ptr);
+                                        target_ptr,
+                                        instruction);
 
         target_ptr.add_constant(&process, name.clone(), source);
 
@@ -1393,12 +1697,14 @@ impl VirtualMachine {
         let name_obj = name.get();
 
         ensure_strings!(instruction, name_obj);
+        ensure_not_frozen!(instruction, target_ptr.get());
 
         let name_str = name_obj.value.as_string().clone();
 
         let source = copy_if_permanent!(self.state.permanent_allocator,
                                         source_ptr,
-                                        target_ptr);
+                                        target_ptr,
+                                        instruction);
 
         target_ptr.add_constant(&process, name_str, source);
 
@@ -1516,9 +1822,13 @@ impl VirtualMachine {
         let value_ptr = instruction_object!(instruction, process, 2);
 
         let name = try_vm_error!(code.string(name_index), instruction);
+
+        ensure_not_frozen!(instruction, target_ptr.get());
+
         let value = copy_if_permanent!(self.state.permanent_allocator,
                                        value_ptr,
-                                       target_ptr);
+                                       target_ptr,
+                                       instruction);
 
         target_ptr.add_attribute(&process, name.clone(), value);
 
@@ -1545,9 +1855,12 @@ impl VirtualMachine {
 
         let name = name_obj.value.as_string();
 
+        ensure_not_frozen!(instruction, target_ptr.get());
+
         let value = copy_if_permanent!(self.state.permanent_allocator,
                                        value_ptr,
-                                       target_ptr);
+                                       target_ptr,
+                                       instruction);
 
         target_ptr.add_attribute(&process, name.clone(), value);
 
@@ -1677,16 +1990,33 @@ impl VirtualMachine {
 
     /// Sends a message using a string literal
     ///
-    /// This instruction requires at least 4 arguments:
+    /// This instruction requires at least 5 arguments:
     ///
     /// 1. The register to store the result in.
     /// 2. The register of the receiver.
     /// 3. The index of the string literal to use for the method name.
     /// 4. A boolean (1 or 0) to indicate if the last argument is a rest
     ///    argument. A rest argument will be unpacked into separate arguments.
+    /// 5. The number of keyword arguments.
     ///
     /// Any extra instruction arguments will be passed as arguments to the
-    /// method.
+    /// method. Positional arguments come first, followed by a pair of
+    /// arguments for every keyword argument: the index of the string literal
+    /// containing the argument name, and the register containing the value.
+    ///
+    /// Keyword arguments are resolved against the argument names of the
+    /// method. Optional arguments that are not given are set to their default
+    /// values, or remain undefined if the argument has no default value.
+    ///
+    /// If this instruction is directly followed by a "return" of the result
+    /// register the method is executed as a tail call, re-using the current
+    /// execution context instead of creating a new one.
+    ///
+    /// If the receiver doesn't respond to the message but does define a
+    /// "method_missing" method, that method is called instead. It's passed
+    /// the name of the message and an array of the original arguments.
+    /// Keyword arguments can not be passed to "method_missing", and result in
+    /// an error instead.
     fn ins_send_literal(&self,
                         process: RcProcess,
                         code: RcCompiledCode,
@@ -1695,7 +2025,7 @@ impl VirtualMachine {
         let name_index = try_vm_error!(instruction.arg(2), instruction);
         let name = try_vm_error!(code.string(name_index), instruction);
 
-        self.send_message(name, process, instruction)
+        self.send_message(name, process, code.clone(), instruction)
     }
 
     /// Sends a message using a runtime allocated string
@@ -1705,7 +2035,7 @@ impl VirtualMachine {
     /// literal it should point to a register containing a string.
     fn ins_send(&self,
                 process: RcProcess,
-                _: RcCompiledCode,
+                code: RcCompiledCode,
                 instruction: &Instruction)
                 -> EmptyResult {
         let string = instruction_object!(instruction, process, 2);
@@ -1713,7 +2043,10 @@ impl VirtualMachine {
 
         ensure_strings!(instruction, string_obj);
 
-        self.send_message(string_obj.value.as_string(), process, instruction)
+        self.send_message(string_obj.value.as_string(),
+                          process,
+                          code,
+                          instruction)
     }
 
     /// Checks if an object responds to a message
@@ -1784,6 +2117,10 @@ impl VirtualMachine {
     ///
     /// This instruction takes a single argument: the register containing the
     /// value to return.
+    ///
+    /// Values returned by the top-level context are stored as the return value
+    /// of the process, which can be retrieved using the "join_process"
+    /// instruction.
     fn ins_return(&self,
                   process: RcProcess,
                   _: RcCompiledCode,
@@ -1792,12 +2129,21 @@ impl VirtualMachine {
         let object = instruction_object!(instruction, process, 0);
         let current_context = process.context_mut();
 
+        if let Some(local) = current_context.return_local {
+            current_context.set_local(local, object);
+            process.marking_barrier(object);
+        }
+
         if let Some(register) = current_context.return_register {
             if let Some(parent_context) = current_context.parent_mut() {
                 parent_context.set_register(register, object);
             }
         }
 
+        if current_context.parent.is_none() {
+            process.set_return_value(object);
+        }
+
         Ok(())
     }
 
@@ -1902,6 +2248,8 @@ impl VirtualMachine {
         let name = name_obj.value.as_string();
         let cc = cc_obj.value.as_compiled_code();
 
+        ensure_not_frozen!(instruction, receiver_ptr.get());
+
         let method = self.allocate_method(&process, &receiver_ptr, cc);
 
         receiver_ptr.add_method(&process, name.clone(), method);
@@ -1937,6 +2285,9 @@ impl VirtualMachine {
 
         let name = try_vm_error!(code.string(name_index), instruction);
         let cc = try_vm_error!(code.code_object(cc_index), instruction);
+
+        ensure_not_frozen!(instruction, receiver_ptr.get());
+
         let method = self.allocate_method(&process, &receiver_ptr, cc);
 
         receiver_ptr.add_method(&process, name.clone(), method);
@@ -1955,6 +2306,9 @@ impl VirtualMachine {
     /// 3. The register containing an array of arguments to pass.
     /// 4. The Binding to use, if any. Omitting this argument results in a
     ///    Binding being created automatically.
+    ///
+    /// Default values of any arguments not given are evaluated before running
+    /// the CompiledCode.
     fn ins_run_code(&self,
                     process: RcProcess,
                     _: RcCompiledCode,
@@ -1998,16 +2352,17 @@ impl VirtualMachine {
             None
         };
 
-        self.schedule_code(process.clone(),
-                           code_obj,
-                           cc_ptr,
-                           arguments,
-                           binding,
-                           register);
+        try!(self.schedule_code(process.clone(),
+                                code_obj,
+                                cc_ptr,
+                                arguments,
+                                binding,
+                                register,
+                                instruction.line));
 
         process.pop_call_frame();
 
-        Ok(())
+        self.schedule_default_arguments(&process, instruction.line)
     }
 
     /// Runs a CompiledCode literal.
@@ -2034,12 +2389,13 @@ impl VirtualMachine {
         let receiver = instruction_object!(instruction, process, 2);
         let code_obj = try_vm_error!(code.code_object(code_index), instruction);
 
-        self.schedule_code(process.clone(),
-                           code_obj,
-                           receiver,
-                           &Vec::new(),
-                           None,
-                           register);
+        try!(self.schedule_code(process.clone(),
+                                code_obj,
+                                receiver,
+                                &Vec::new(),
+                                None,
+                                register,
+                                instruction.line));
 
         process.pop_call_frame();
 
@@ -2415,10 +2771,14 @@ impl VirtualMachine {
 
     /// Runs a CompiledCode in a new process.
     ///
-    /// This instruction takes 2 arguments:
+    /// This instruction takes 2 arguments, and an optional third argument:
     ///
     /// 1. The register to store the PID in.
     /// 2. A code objects index pointing to the CompiledCode object to run.
+    /// 3. The register containing the priority (an integer) of the process.
+    ///
+    /// Priorities are 0 for low, 1 for normal, and 2 for high. Processes are
+    /// spawned with a normal priority if no priority is given.
     fn ins_spawn_literal_process(&self,
                                  process: RcProcess,
                                  code: RcCompiledCode,
@@ -2427,10 +2787,9 @@ impl VirtualMachine {
         let register = try_vm_error!(instruction.arg(0), instruction);
         let code_index = try_vm_error!(instruction.arg(1), instruction);
         let code_obj = try_vm_error!(code.code_object(code_index), instruction);
+        let priority = try!(self.spawn_priority(&process, instruction, 2));
 
-        self.spawn_process(process, code_obj, register);
-
-        Ok(())
+        self.spawn_process(process, code_obj, priority, register, |_| Ok(()))
     }
 
     /// Runs a CompiledCode in a new process using a runtime allocated
@@ -2451,1551 +2810,3922 @@ impl VirtualMachine {
         ensure_compiled_code!(instruction, code);
 
         let code_obj = code.value.as_compiled_code();
+        let priority = try!(self.spawn_priority(&process, instruction, 2));
 
-        self.spawn_process(process, code_obj, register);
-
-        Ok(())
+        self.spawn_process(process, code_obj, priority, register, |_| Ok(()))
     }
 
-    /// Sends a message to a process.
+    /// Runs a closure in a new process.
     ///
-    /// This instruction takes 3 arguments:
+    /// This instruction takes 3 arguments, and an optional fourth argument:
     ///
-    /// 1. The register to store the message in.
-    /// 2. The register containing the PID to send the message to.
-    /// 3. The register containing the message (an object) to send to the
-    ///    process.
-    fn ins_send_process_message(&self,
-                                process: RcProcess,
-                                _: RcCompiledCode,
-                                instruction: &Instruction)
-                                -> EmptyResult {
+    /// 1. The register to store the PID in.
+    /// 2. The register containing the CompiledCode object to run.
+    /// 3. The register containing the Binding captured by the closure.
+    /// 4. The register containing the priority (an integer) of the process.
+    ///
+    /// The binding and all objects it refers to are copied into the heap of
+    /// the new process, and used as the parent binding of the closure. An IO
+    /// error is stored in the register if the binding could not be copied.
+    fn ins_spawn_closure(&self,
+                         process: RcProcess,
+                         _: RcCompiledCode,
+                         instruction: &Instruction)
+                         -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let pid_ptr = instruction_object!(instruction, process, 1);
-        let msg_ptr = instruction_object!(instruction, process, 2);
+        let code_ptr = instruction_object!(instruction, process, 1);
+        let binding_ptr = instruction_object!(instruction, process, 2);
 
-        let pid = {
-            let pid_obj = pid_ptr.get();
+        let code_obj = {
+            let code = code_ptr.get();
 
-            ensure_integers!(instruction, pid_obj);
+            ensure_compiled_code!(instruction, code);
 
-            pid_obj.value.as_integer() as usize
+            code.value.as_compiled_code()
         };
 
-        if let Some(receiver) = read_lock!(self.state.processes).get(pid) {
-            receiver.send_message(msg_ptr.clone());
-        }
+        let binding = {
+            let binding_obj = binding_ptr.get();
 
-        process.set_register(register, msg_ptr);
+            if !binding_obj.value.is_binding() {
+                let message = "Argument 2 is not a valid Binding".to_string();
 
-        Ok(())
+                return_vm_error!(message, instruction.line);
+            }
+
+            binding_obj.value.as_binding()
+        };
+
+        let priority = try!(self.spawn_priority(&process, instruction, 3));
+
+        self.spawn_process(process, code_obj, priority, register, |new_proc| {
+            new_proc.capture_binding(&binding)
+        })
     }
 
-    /// Receives a message for the current process.
+    /// Runs a CompiledCode in a new process, passing it a list of arguments.
     ///
-    /// This instruction takes 1 argument: the register to store the resulting
-    /// message in.
+    /// This instruction takes 3 arguments, and an optional fourth argument:
     ///
-    /// If no messages are available this instruction will block until a message
-    /// is available.
-    fn ins_receive_process_message(&self,
-                                   process: RcProcess,
-                                   _: RcCompiledCode,
-                                   instruction: &Instruction)
-                                   -> BooleanResult {
+    /// 1. The register to store the PID in.
+    /// 2. The register containing the CompiledCode object to run.
+    /// 3. The register containing an array of arguments to pass.
+    /// 4. The register containing the priority (an integer) of the process.
+    ///
+    /// The arguments are copied into the heap of the new process and stored
+    /// in its local variables, in the same order as the array. An IO error is
+    /// stored in the register if the arguments could not be copied.
+    ///
+    /// The current process joins the new process, thus the return value of
+    /// the new process can be retrieved using the "join_process" instruction,
+    /// even if the new process terminates before the current process joins it.
+    /// A return value that is never retrieved is released once the current
+    /// process terminates.
+    fn ins_spawn_process_with_arguments(&self,
+                                        process: RcProcess,
+                                        _: RcCompiledCode,
+                                        instruction: &Instruction)
+                                        -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let pid = process.pid;
-        let source = read_lock!(self.state.processes).get(pid).unwrap();
+        let code_ptr = instruction_object!(instruction, process, 1);
+        let args_ptr = instruction_object!(instruction, process, 2);
 
-        if let Some(msg_ptr) = source.receive_message() {
-            process.set_register(register, msg_ptr);
+        let code_obj = {
+            let code = code_ptr.get();
 
-            Ok(false)
-        } else {
-            Ok(true)
-        }
-    }
+            ensure_compiled_code!(instruction, code);
 
-    /// Gets the PID of the currently running process.
-    ///
-    /// This instruction requires one argument: the register to store the PID
-    /// in (as an integer).
-    fn ins_get_current_pid(&self,
-                           process: RcProcess,
-                           _: RcCompiledCode,
-                           instruction: &Instruction)
-                           -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let pid = process.pid;
+            code.value.as_compiled_code()
+        };
 
-        let pid_obj = process.allocate(object_value::integer(pid as i64),
-                                       self.state.integer_prototype.clone());
+        let args_obj = args_ptr.get();
 
-        process.set_register(register, pid_obj);
+        ensure_arrays!(instruction, args_obj);
 
-        Ok(())
-    }
+        let arguments = args_obj.value.as_array();
+        let priority = try!(self.spawn_priority(&process, instruction, 3));
+        let parent = read_lock!(self.state.processes).handle(process.pid);
 
-    /// Adds two floats
-    ///
-    /// This instruction requires 3 arguments:
-    ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the receiver.
-    /// 3. The register of the float to add.
-    fn ins_float_add(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        float_op!(self, process, instruction, +);
+        self.spawn_process(process, code_obj, priority, register, |new_proc| {
+            try!(new_proc.set_arguments(arguments));
 
-        Ok(())
+            new_proc.add_joiner(parent);
+
+            Ok(())
+        })
     }
 
-    /// Multiplies two floats
+    /// Waits for a process to terminate, returning its return value.
     ///
-    /// This instruction requires 3 arguments:
+    /// This instruction takes 2 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the receiver.
-    /// 3. The register of the float to multiply with.
-    fn ins_float_mul(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        float_op!(self, process, instruction, *);
-
-        Ok(())
-    }
-
-    /// Divides two floats
+    /// 1. The register to store the return value in.
+    /// 2. The register containing the process handle of the process to join.
     ///
-    /// This instruction requires 3 arguments:
+    /// The return value is the value last returned by the top-level code of
+    /// the process, copied into the heap of the current process. If the process
+    /// terminated due to an error a "process failed" error is stored in the
+    /// register instead.
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the receiver.
-    /// 3. The register of the float to divide with.
-    fn ins_float_div(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        float_op!(self, process, instruction, /);
+    /// Processes spawned using "spawn_process_with_arguments" are joined when
+    /// spawned. Other processes are only joined once this instruction is
+    /// executed; if such a process terminated before then, an error is stored
+    /// in the register.
+    ///
+    /// The return value is "true" if the instruction should be retried once
+    /// the process terminates.
+    fn ins_join_process(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> BooleanResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let handle_ptr = instruction_object!(instruction, process, 1);
 
-        Ok(())
-    }
+        let handle = {
+            let handle_obj = handle_ptr.get();
 
-    /// Subtracts two floats
-    ///
-    /// This instruction requires 3 arguments:
-    ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the receiver.
-    /// 3. The register of the float to subtract.
-    fn ins_float_sub(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        float_op!(self, process, instruction, -);
+            ensure_process_handles!(instruction, handle_obj);
 
-        Ok(())
-    }
+            handle_obj.value.as_process_handle()
+        };
 
-    /// Gets the modulo of a float
-    ///
-    /// This instruction requires 3 arguments:
-    ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the receiver.
-    /// 3. The register of the float argument.
-    fn ins_float_mod(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        float_op!(self, process, instruction, %);
+        if let Some(result) = process.take_join_result(&handle) {
+            process.set_joining(None);
+            process.set_register(register, result);
 
-        Ok(())
-    }
+            return Ok(false);
+        }
 
-    /// Converts a float to an integer
-    ///
-    /// This instruction requires 2 arguments:
-    ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the float to convert.
-    fn ins_float_to_integer(&self,
-                            process: RcProcess,
-                            _: RcCompiledCode,
-                            instruction: &Instruction)
-                            -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let float_ptr = instruction_object!(instruction, process, 1);
-        let float = float_ptr.get();
+        if process.joining() == Some(handle) {
+            return Ok(true);
+        }
 
-        ensure_floats!(instruction, float);
+        let joined = {
+            let processes = read_lock!(self.state.processes);
+            let own_handle = processes.handle(process.pid);
 
-        let result = float.value.as_float() as i64;
+            processes.get_handle(&handle)
+                .map_or(false, |joinee| joinee.add_joiner(own_handle))
+        };
 
-        let obj = process.allocate(object_value::integer(result),
-                                   self.state.integer_prototype.clone());
+        if joined {
+            process.set_joining(Some(handle));
 
-        process.set_register(register, obj);
+            return Ok(true);
+        }
 
-        Ok(())
+        // The process may have terminated after we checked for its return
+        // value, but before we tried to join it.
+        let result = match process.take_join_result(&handle) {
+            Some(result) => result,
+            None => {
+                let error = object_value::error(errors::PROCESS_NOT_ALIVE);
+
+                process.allocate_without_prototype(error)
+            }
+        };
+
+        process.set_register(register, result);
+
+        Ok(false)
     }
 
-    /// Converts a float to a string
+    /// Sends a message to a process.
     ///
-    /// This instruction requires 2 arguments:
+    /// This instruction takes 3 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the float to convert.
-    fn ins_float_to_string(&self,
-                           process: RcProcess,
-                           _: RcCompiledCode,
-                           instruction: &Instruction)
-                           -> EmptyResult {
+    /// 1. The register to store the message in.
+    /// 2. The register containing the process handle to send the message to.
+    /// 3. The register containing the message (an object) to send to the
+    ///    process.
+    ///
+    /// If the process is no longer alive the message is dropped and an error
+    /// is stored in the register instead. An IO error is stored in the
+    /// register if the message could not be copied, for example when a file
+    /// descriptor could not be duplicated.
+    fn ins_send_process_message(&self,
+                                process: RcProcess,
+                                _: RcCompiledCode,
+                                instruction: &Instruction)
+                                -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let float_ptr = instruction_object!(instruction, process, 1);
-        let float = float_ptr.get();
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let msg_ptr = instruction_object!(instruction, process, 2);
 
-        ensure_floats!(instruction, float);
+        let handle = {
+            let handle_obj = handle_ptr.get();
 
-        let result = float.value.as_float().to_string();
+            ensure_process_handles!(instruction, handle_obj);
 
-        let obj = process.allocate(object_value::string(result),
-                                   self.state.string_prototype.clone());
+            handle_obj.value.as_process_handle()
+        };
 
-        process.set_register(register, obj);
+        let receiver_opt = read_lock!(self.state.processes).get_handle(&handle);
+
+        let result = if let Some(receiver) = receiver_opt {
+            try_io!(receiver.send_message(msg_ptr.clone()), process, register);
+
+            msg_ptr
+        } else {
+            let error = object_value::error(errors::PROCESS_NOT_ALIVE);
+
+            process.allocate_without_prototype(error)
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Checks if one float is smaller than the other.
+    /// Sends a message to a process once the given amount of time has passed.
     ///
-    /// This instruction requires 3 arguments:
+    /// This instruction takes 4 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register containing the float to compare.
-    /// 3. The register containing the float to compare with.
+    /// 1. The register to store the message in.
+    /// 2. The register containing the process handle to send the message to.
+    /// 3. The register containing the message (an object) to send to the
+    ///    process.
+    /// 4. The register containing the delay in milliseconds, as an integer.
     ///
-    /// The result of this instruction is either boolean true or false.
-    fn ins_float_smaller(&self,
-                         process: RcProcess,
-                         _: RcCompiledCode,
-                         instruction: &Instruction)
-                         -> EmptyResult {
-        float_bool_op!(self, process, instruction, <);
+    /// The message is copied right away. If the process is no longer alive the
+    /// message is dropped and an error is stored in the register instead. An
+    /// IO error is stored in the register if the message could not be copied.
+    fn ins_send_after(&self,
+                      process: RcProcess,
+                      _: RcCompiledCode,
+                      instruction: &Instruction)
+                      -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let msg_ptr = instruction_object!(instruction, process, 2);
+        let delay_ptr = instruction_object!(instruction, process, 3);
+
+        let handle_obj = handle_ptr.get();
+        let delay_obj = delay_ptr.get();
+
+        ensure_process_handles!(instruction, handle_obj);
+        ensure_integers!(instruction, delay_obj);
+
+        let handle = handle_obj.value.as_process_handle();
+        let receiver_opt = read_lock!(self.state.processes).get_handle(&handle);
+
+        let result = if let Some(receiver) = receiver_opt {
+            let delay = delay_obj.value.as_integer();
+            let millis = if delay > 0 { delay as u64 } else { 0 };
+            let deadline = time::precise_time_ns()
+                .saturating_add(millis.saturating_mul(1_000_000));
+            let message =
+                try_io!(receiver.delay_message(msg_ptr.clone()),
+                        process,
+                        register);
+
+            self.state
+                .timer
+                .schedule(deadline, TimerEvent::SendMessage(handle, message));
+
+            msg_ptr
+        } else {
+            let error = object_value::error(errors::PROCESS_NOT_ALIVE);
+
+            process.allocate_without_prototype(error)
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Checks if one float is greater than the other.
-    ///
-    /// This instruction requires 3 arguments:
+    /// Suspends the current process for the given amount of time.
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register containing the float to compare.
-    /// 3. The register containing the float to compare with.
+    /// This instruction takes 1 argument: the register containing the time to
+    /// sleep in milliseconds, as an integer.
     ///
-    /// The result of this instruction is either boolean true or false.
-    fn ins_float_greater(&self,
+    /// Messages received while sleeping don't wake up the process.
+    fn ins_process_sleep(&self,
                          process: RcProcess,
                          _: RcCompiledCode,
                          instruction: &Instruction)
-                         -> EmptyResult {
-        float_bool_op!(self, process, instruction, >);
+                         -> BooleanResult {
+        let time_ptr = instruction_object!(instruction, process, 0);
+        let time_obj = time_ptr.get();
 
-        Ok(())
+        ensure_integers!(instruction, time_obj);
+
+        let now = time::precise_time_ns();
+
+        let deadline = match process.sleep_deadline() {
+            Some(deadline) => deadline,
+            None => {
+                let duration = time_obj.value.as_integer();
+                let millis = if duration > 0 { duration as u64 } else { 0 };
+
+                now.saturating_add(millis.saturating_mul(1_000_000))
+            }
+        };
+
+        if now >= deadline {
+            process.set_sleep_deadline(None);
+
+            Ok(false)
+        } else {
+            process.set_sleep_deadline(Some(deadline));
+
+            Ok(true)
+        }
     }
 
-    /// Checks if two floats are equal.
+    /// Gets the value of a monotonic clock in nanoseconds.
     ///
-    /// This instruction requires 3 arguments:
-    ///
-    /// 1. The register to store the result in.
-    /// 2. The register containing the float to compare.
-    /// 3. The register containing the float to compare with.
+    /// This instruction takes 1 argument: the register to store the value (an
+    /// integer) in.
     ///
-    /// The result of this instruction is either boolean true or false.
-    fn ins_float_equals(&self,
-                        process: RcProcess,
-                        _: RcCompiledCode,
-                        instruction: &Instruction)
-                        -> EmptyResult {
-        float_bool_op!(self, process, instruction, ==);
+    /// The value is only useful for measuring the time between two points in
+    /// time.
+    fn ins_time_monotonic_nanos(&self,
+                                process: RcProcess,
+                                _: RcCompiledCode,
+                                instruction: &Instruction)
+                                -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let nanos = self.config().clock.monotonic_nanos() as i64;
+
+        let obj = process.allocate(object_value::integer(nanos),
+                                   self.state.integer_prototype.clone());
+
+        process.set_register(register, obj);
 
         Ok(())
     }
 
-    /// Inserts a value in an array.
-    ///
-    /// This instruction requires 4 arguments:
+    /// Gets the time elapsed since the Unix epoch.
     ///
-    /// 1. The register to store the result (the inserted value) in.
-    /// 2. The register containing the array to insert into.
-    /// 3. The register containing the index (as an integer) to insert at.
-    /// 4. The register containing the value to insert.
+    /// This instruction takes 1 argument: the register to store the time in.
     ///
-    /// An error is returned when the index is greater than the array length. A
-    /// negative index can be used to indicate a position from the end of the
-    /// array.
-    fn ins_array_insert(&self,
-                        process: RcProcess,
-                        _: RcCompiledCode,
-                        instruction: &Instruction)
-                        -> EmptyResult {
+    /// The time is stored as an array of two integers: the number of seconds,
+    /// and the number of nanoseconds within the current second.
+    fn ins_time_wall_clock(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let array_ptr = instruction_object!(instruction, process, 1);
-        let index_ptr = instruction_object!(instruction, process, 2);
-        let value_ptr = instruction_object!(instruction, process, 3);
-
-        let mut array = array_ptr.get_mut();
+        let time = self.config().clock.wall_clock();
 
-        ensure_arrays!(instruction, array);
+        let sec_obj = process.allocate(object_value::integer(time.sec),
+                                       self.state.integer_prototype.clone());
 
-        let index_obj = index_ptr.get();
+        let nsec_obj =
+            process.allocate(object_value::integer(time.nsec as i64),
+                             self.state.integer_prototype.clone());
 
-        ensure_integers!(instruction, index_obj);
+        let obj = process.allocate(object_value::array(vec![sec_obj, nsec_obj]),
+                                   self.state.array_prototype.clone());
 
-        let mut vector = array.value.as_array_mut();
-        let index = int_to_vector_index!(vector, index_obj.value.as_integer());
+        process.set_register(register, obj);
 
-        ensure_array_within_bounds!(instruction, vector, index);
+        Ok(())
+    }
 
-        let value =
-            copy_if_permanent!(self.state.permanent_allocator, value_ptr, array_ptr);
+    /// Gets the current time in UTC as an RFC 3339 formatted string.
+    ///
+    /// This instruction takes 1 argument: the register to store the string in.
+    fn ins_time_utc_timestamp(&self,
+                              process: RcProcess,
+                              _: RcCompiledCode,
+                              instruction: &Instruction)
+                              -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let timestamp = format!("{}", self.config().clock.utc().rfc3339());
 
-        if vector.get(index).is_some() {
-            vector[index] = value;
-        } else {
-            vector.insert(index, value);
-        }
+        let obj = process.allocate(object_value::string(timestamp),
+                                   self.state.string_prototype.clone());
 
-        process.set_register(register, value);
+        process.set_register(register, obj);
 
         Ok(())
     }
 
-    /// Gets the value of an array index.
+    /// Receives a message for the current process.
     ///
-    /// This instruction requires 3 arguments:
+    /// This instruction takes 1 argument: the register to store the resulting
+    /// message in.
     ///
-    /// 1. The register to store the value in.
-    /// 2. The register containing the array.
-    /// 3. The register containing the index.
+    /// If no messages are available this instruction will block until a message
+    /// is available.
+    fn ins_receive_process_message(&self,
+                                   process: RcProcess,
+                                   _: RcCompiledCode,
+                                   instruction: &Instruction)
+                                   -> BooleanResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+
+        try!(self.ensure_not_matching_message(&process, instruction));
+
+        let pid = process.pid;
+        let source = read_lock!(self.state.processes).get(pid).unwrap();
+
+        if let Some(msg_ptr) = source.receive_message() {
+            process.set_register(register, msg_ptr);
+
+            Ok(false)
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// Receives a message for the current process, waiting at most the given
+    /// amount of time.
     ///
-    /// An error is returned when the index is greater than the array length. A
-    /// negative index can be used to indicate a position from the end of the
-    /// array.
-    fn ins_array_at(&self,
-                    process: RcProcess,
-                    _: RcCompiledCode,
-                    instruction: &Instruction)
-                    -> EmptyResult {
+    /// This instruction takes 2 arguments:
+    ///
+    /// 1. The register to store the resulting message in.
+    /// 2. The register containing the timeout in milliseconds, as an integer.
+    ///
+    /// If no messages are available the process is suspended until a message
+    /// is received or the timeout expires. Once the timeout expires an error
+    /// is stored in the register instead of a message.
+    fn ins_receive_process_message_timeout(&self,
+                                           process: RcProcess,
+                                           _: RcCompiledCode,
+                                           instruction: &Instruction)
+                                           -> BooleanResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let array_ptr = instruction_object!(instruction, process, 1);
-        let index_ptr = instruction_object!(instruction, process, 2);
-        let array = array_ptr.get();
+        let timeout_ptr = instruction_object!(instruction, process, 1);
+        let timeout_obj = timeout_ptr.get();
 
-        let index_obj = index_ptr.get();
+        ensure_integers!(instruction, timeout_obj);
 
-        ensure_arrays!(instruction, array);
-        ensure_integers!(instruction, index_obj);
+        try!(self.ensure_not_matching_message(&process, instruction));
 
-        let vector = array.value.as_array();
-        let index = int_to_vector_index!(vector, index_obj.value.as_integer());
+        if let Some(msg_ptr) = process.receive_message() {
+            if process.receive_deadline().is_some() {
+                self.cancel_receive_timeout(&process);
+            }
 
-        ensure_array_within_bounds!(instruction, vector, index);
+            process.set_receive_deadline(None);
+            process.set_register(register, msg_ptr);
 
-        let value = vector[index].clone();
+            return Ok(false);
+        }
 
-        process.set_register(register, value);
+        let now = time::precise_time_ns();
 
-        Ok(())
+        let deadline = match process.receive_deadline() {
+            Some(deadline) => deadline,
+            None => {
+                let timeout = timeout_obj.value.as_integer();
+                let millis = if timeout > 0 { timeout as u64 } else { 0 };
+
+                now.saturating_add(millis.saturating_mul(1_000_000))
+            }
+        };
+
+        if now >= deadline {
+            let error = object_value::error(errors::PROCESS_RECEIVE_TIMEOUT);
+            let error_obj = process.allocate_without_prototype(error);
+
+            process.set_receive_deadline(None);
+            process.set_register(register, error_obj);
+
+            Ok(false)
+        } else {
+            process.set_receive_deadline(Some(deadline));
+
+            Ok(true)
+        }
     }
 
-    /// Removes a value from an array.
-    ///
-    /// This instruction requires 3 arguments:
+    /// Receives the first message for which a predicate returns a truthy
+    /// value.
     ///
-    /// 1. The register to store the removed value in.
-    /// 2. The register containing the array to remove a value from.
-    /// 3. The register containing the index.
+    /// This instruction takes 2 arguments:
     ///
-    /// An error is returned when the index is greater than the array length. A
-    /// negative index can be used to indicate a position from the end of the
-    /// array.
-    fn ins_array_remove(&self,
-                        process: RcProcess,
-                        _: RcCompiledCode,
-                        instruction: &Instruction)
-                        -> EmptyResult {
+    /// 1. The register to store the resulting message in.
+    /// 2. The register containing the CompiledCode object to use as the
+    ///    predicate.
+    ///
+    /// The predicate is called with a message as its only argument and is
+    /// evaluated using the current binding as its parent binding. Messages are
+    /// checked in the order they were received. The first matching message is
+    /// removed from the mailbox, any other messages are left as-is.
+    ///
+    /// If no message matches the process is suspended until a new message is
+    /// received. Receiving messages from within the predicate results in an
+    /// error.
+    ///
+    /// The return value is "true" if this instruction should be retried, either
+    /// after running the predicate or once a new message is received.
+    fn ins_receive_matching_process_message(&self,
+                                            process: RcProcess,
+                                            _: RcCompiledCode,
+                                            instruction: &Instruction)
+                                            -> BooleanResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let array_ptr = instruction_object!(instruction, process, 1);
-        let index_ptr = instruction_object!(instruction, process, 2);
+        let code_ptr = instruction_object!(instruction, process, 1);
 
-        let mut array = array_ptr.get_mut();
-        let index_obj = index_ptr.get();
+        let predicate = {
+            let code_obj = code_ptr.get();
 
-        ensure_arrays!(instruction, array);
-        ensure_integers!(instruction, index_obj);
+            ensure_compiled_code!(instruction, code_obj);
 
-        let mut vector = array.value.as_array_mut();
-        let index = int_to_vector_index!(vector, index_obj.value.as_integer());
+            code_obj.value.as_compiled_code()
+        };
 
-        ensure_array_within_bounds!(instruction, vector, index);
+        try!(self.ensure_not_matching_message(&process, instruction));
 
-        let value = vector.remove(index);
+        let index = match process.selective_receive() {
+            Some(SelectiveReceive::Matching { index, message, .. }) => {
+                let matched = match process.get_register_option(register) {
+                    Some(obj) => obj != self.state.false_object,
+                    None => false,
+                };
 
-        process.set_register(register, value);
+                if matched {
+                    if !process.remove_message(index) {
+                        return_vm_error!(format!("Undefined message index {}",
+                                                 index),
+                                         instruction.line);
+                    }
+
+                    process.set_selective_receive(None);
+                    process.set_register(register, message);
+
+                    return Ok(false);
+                }
+
+                index + 1
+            }
+            Some(SelectiveReceive::Waiting(index)) => index,
+            None => 0,
+        };
+
+        if let Some(message) = process.peek_message(index) {
+            let state = SelectiveReceive::Matching {
+                index: index,
+                message: message,
+                depth: process.call_depth(),
+            };
+
+            process.set_selective_receive(Some(state));
+
+            // The predicate's result is stored in the target register, which
+            // we reset in case the predicate doesn't return a value.
+            process.set_register(register, self.state.false_object.clone());
+            process.advance_line(instruction.line);
+
+            try!(self.schedule_code(process.clone(),
+                                    predicate,
+                                    process.self_object(),
+                                    &vec![message],
+                                    Some(process.binding()),
+                                    register,
+                                    instruction.line));
+
+            process.pop_call_frame();
+        } else {
+            process.set_selective_receive(Some(SelectiveReceive::Waiting(index)));
+            process.suspend_for_message();
+        }
+
+        Ok(true)
+    }
+
+    /// Gets a handle of the currently running process.
+    ///
+    /// This instruction requires one argument: the register to store the
+    /// process handle in.
+    fn ins_get_current_pid(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let handle = read_lock!(self.state.processes).handle(process.pid);
+        let handle_obj = self.allocate_process_handle(&process, handle);
+
+        process.set_register(register, handle_obj);
 
         Ok(())
     }
 
-    /// Gets the amount of elements in an array.
+    /// Links the current process with another process.
     ///
     /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the length in.
-    /// 2. The register containing the array.
-    fn ins_array_length(&self,
+    /// 1. The register to store the result in (true or false).
+    /// 2. The register containing the handle of the process to link with.
+    ///
+    /// Once linked, a process that terminates sends an exit signal to the
+    /// other process. If the process terminated due to an error the other
+    /// process is terminated as well, unless it traps exits. Processes that
+    /// trap exits instead receive an array message in the form
+    /// `["exit", process, reason]`.
+    ///
+    /// The result is false if the process to link with is no longer alive.
+    fn ins_link_process(&self,
                         process: RcProcess,
                         _: RcCompiledCode,
                         instruction: &Instruction)
                         -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let array_ptr = instruction_object!(instruction, process, 1);
-
-        let array = array_ptr.get();
-
-        ensure_arrays!(instruction, array);
-
-        let vector = array.value.as_array();
-        let length = vector.len() as i64;
+        let handle_ptr = instruction_object!(instruction, process, 1);
 
-        let obj = process.allocate(object_value::integer(length),
-                                   self.state.integer_prototype.clone());
+        let handle = {
+            let handle_obj = handle_ptr.get();
 
-        process.set_register(register, obj);
+            ensure_process_handles!(instruction, handle_obj);
 
-        Ok(())
-    }
+            handle_obj.value.as_process_handle()
+        };
 
-    /// Removes all elements from an array.
-    ///
-    /// This instruction requires 1 argument: the register of the array.
-    fn ins_array_clear(&self,
-                       process: RcProcess,
-                       _: RcCompiledCode,
-                       instruction: &Instruction)
-                       -> EmptyResult {
-        let array_ptr = instruction_object!(instruction, process, 0);
+        let (target_opt, own_handle) = {
+            let processes = read_lock!(self.state.processes);
 
-        let mut array = array_ptr.get_mut();
+            (processes.get_handle(&handle), processes.handle(process.pid))
+        };
 
-        ensure_arrays!(instruction, array);
+        let linked = match target_opt {
+            Some(ref target) if handle != own_handle => {
+                target.link(own_handle) && process.link(handle)
+            }
+            _ => false,
+        };
 
-        let mut vector = array.value.as_array_mut();
+        let result = if linked {
+            self.state.true_object.clone()
+        } else {
+            self.state.false_object.clone()
+        };
 
-        vector.clear();
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Returns the lowercase equivalent of a string.
+    /// Monitors another process.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the new string in.
-    /// 2. The register containing the input string.
-    fn ins_string_to_lower(&self,
+    /// 1. The register to store the result in (true or false).
+    /// 2. The register containing the handle of the process to monitor.
+    ///
+    /// Once the monitored process terminates the current process receives an
+    /// array message in the form `["down", process, reason]`. If the process is
+    /// no longer alive this message is sent right away, using "noproc" as the
+    /// reason, and the result is false.
+    fn ins_monitor_process(&self,
                            process: RcProcess,
                            _: RcCompiledCode,
                            instruction: &Instruction)
                            -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let source_ptr = instruction_object!(instruction, process, 1);
-        let source = source_ptr.get();
+        let handle_ptr = instruction_object!(instruction, process, 1);
 
-        ensure_strings!(instruction, source);
+        let handle = {
+            let handle_obj = handle_ptr.get();
 
-        let lower = source.value.as_string().to_lowercase();
+            ensure_process_handles!(instruction, handle_obj);
 
-        let obj = process.allocate(object_value::string(lower),
-                                   self.state.string_prototype.clone());
+            handle_obj.value.as_process_handle()
+        };
 
-        process.set_register(register, obj);
+        let (target_opt, own_handle) = {
+            let processes = read_lock!(self.state.processes);
+
+            (processes.get_handle(&handle), processes.handle(process.pid))
+        };
+
+        let monitoring = if let Some(target) = target_opt {
+            target.add_monitor(own_handle)
+        } else {
+            false
+        };
+
+        let result = if monitoring {
+            self.state.true_object.clone()
+        } else {
+            self.send_exit_message(&process,
+                                   DOWN_MESSAGE,
+                                   handle,
+                                   EXIT_NO_PROCESS);
+
+            self.state.false_object.clone()
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Returns the uppercase equivalent of a string.
+    /// Sets whether the current process traps exit signals.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the new string in.
-    /// 2. The register containing the input string.
-    fn ins_string_to_upper(&self,
-                           process: RcProcess,
-                           _: RcCompiledCode,
-                           instruction: &Instruction)
-                           -> EmptyResult {
+    /// 1. The register to store the result in.
+    /// 2. The register containing a truthy/falsy object. When the register
+    ///    contains a truthy object exit signals of linked processes are
+    ///    received as messages instead of terminating the current process.
+    fn ins_trap_exits(&self,
+                      process: RcProcess,
+                      _: RcCompiledCode,
+                      instruction: &Instruction)
+                      -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let source_ptr = instruction_object!(instruction, process, 1);
-        let source = source_ptr.get();
+        let trap_ptr = instruction_object!(instruction, process, 1);
 
-        ensure_strings!(instruction, source);
+        process.set_trap_exits(trap_ptr != self.state.false_object);
+        process.set_register(register, trap_ptr);
 
-        let upper = source.value.as_string().to_uppercase();
+        Ok(())
+    }
 
-        let obj = process.allocate(object_value::string(upper),
-                                   self.state.string_prototype.clone());
+    /// Terminates the current process.
+    ///
+    /// This instruction requires one argument: the register containing the
+    /// reason (a string) of the termination.
+    ///
+    /// Using the reason "normal" terminates the process as if it finished
+    /// normally. Any other reason marks the process as failed, and is sent to
+    /// linked and monitoring processes.
+    fn ins_process_exit(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        let reason_ptr = instruction_object!(instruction, process, 0);
+        let reason_obj = reason_ptr.get();
 
-        process.set_register(register, obj);
+        ensure_strings!(instruction, reason_obj);
+
+        process.signal_exit(reason_obj.value.as_string().clone());
 
         Ok(())
     }
 
-    /// Checks if two strings are equal.
+    /// Terminates another process.
     ///
     /// This instruction requires 3 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the string to compare.
-    /// 3. The register of the string to compare with.
-    fn ins_string_equals(&self,
-                         process: RcProcess,
-                         _: RcCompiledCode,
-                         instruction: &Instruction)
-                         -> EmptyResult {
+    /// 1. The register to store the result in (true or false).
+    /// 2. The register containing the handle of the process to terminate.
+    /// 3. The register containing the reason (a string) of the termination.
+    ///
+    /// The process terminates the next time it's run, or at the end of its
+    /// current execution context if it's running. The result is false if the
+    /// process is no longer alive.
+    fn ins_process_kill(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let receiver_ptr = instruction_object!(instruction, process, 1);
-        let arg_ptr = instruction_object!(instruction, process, 2);
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let reason_ptr = instruction_object!(instruction, process, 2);
 
-        let receiver = receiver_ptr.get();
-        let arg = arg_ptr.get();
+        let handle_obj = handle_ptr.get();
+        let reason_obj = reason_ptr.get();
 
-        ensure_strings!(instruction, receiver, arg);
+        ensure_process_handles!(instruction, handle_obj);
+        ensure_strings!(instruction, reason_obj);
 
-        let result = receiver.value.as_string() == arg.value.as_string();
+        let handle = handle_obj.value.as_process_handle();
+        let target_opt = read_lock!(self.state.processes).get_handle(&handle);
+
+        let result = if let Some(target) = target_opt {
+            target.signal_exit(reason_obj.value.as_string().clone());
 
-        let boolean = if result {
             self.state.true_object.clone()
         } else {
             self.state.false_object.clone()
         };
 
-        process.set_register(register, boolean);
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Returns an array containing the bytes of a string.
+    /// Gets the status of a process.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register containing the string to get the bytes from.
-    fn ins_string_to_bytes(&self,
-                           process: RcProcess,
-                           _: RcCompiledCode,
-                           instruction: &Instruction)
-                           -> EmptyResult {
+    /// 1. The register to store the status (as a string) in.
+    /// 2. The register containing the handle of the process.
+    ///
+    /// If the process is no longer alive an error is stored instead.
+    fn ins_process_status(&self,
+                          process: RcProcess,
+                          _: RcCompiledCode,
+                          instruction: &Instruction)
+                          -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let arg_ptr = instruction_object!(instruction, process, 1);
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let handle_obj = handle_ptr.get();
 
-        let arg = arg_ptr.get();
+        ensure_process_handles!(instruction, handle_obj);
 
-        ensure_strings!(instruction, arg);
+        let handle = handle_obj.value.as_process_handle();
+        let target_opt = read_lock!(self.state.processes).get_handle(&handle);
 
-        let int_proto = self.state.integer_prototype.clone();
-        let array_proto = self.state.array_prototype.clone();
+        let result = if let Some(target) = target_opt {
+            let name = target.status_name().to_string();
 
-        let array = arg.value
-            .as_string()
-            .as_bytes()
-            .iter()
-            .map(|&b| {
-                process
-                    .allocate(object_value::integer(b as i64), int_proto.clone())
-            })
-            .collect::<Vec<_>>();
+            process.allocate(object_value::string(name),
+                             self.state.string_prototype.clone())
+        } else {
+            let error = object_value::error(errors::PROCESS_NOT_ALIVE);
 
-        let obj = process.allocate(object_value::array(array), array_proto);
+            process.allocate_without_prototype(error)
+        };
 
-        process.set_register(register, obj);
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Creates a string from an array of bytes
+    /// Gets the priority of a process.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register containing the array of bytes.
+    /// 1. The register to store the priority (as an integer) in.
+    /// 2. The register containing the handle of the process.
     ///
-    /// The result of this instruction is either a string based on the given
-    /// bytes, or an error object.
-    fn ins_string_from_bytes(&self,
-                             process: RcProcess,
-                             _: RcCompiledCode,
-                             instruction: &Instruction)
-                             -> EmptyResult {
+    /// If the process is no longer alive an error is stored instead.
+    fn ins_process_priority(&self,
+                            process: RcProcess,
+                            _: RcCompiledCode,
+                            instruction: &Instruction)
+                            -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let arg_ptr = instruction_object!(instruction, process, 1);
-
-        let arg = arg_ptr.get();
-
-        ensure_arrays!(instruction, arg);
-
-        let string_proto = self.state.string_prototype.clone();
-        let array = arg.value.as_array();
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let handle_obj = handle_ptr.get();
 
-        for int_ptr in array.iter() {
-            let int_obj = int_ptr.get();
+        ensure_process_handles!(instruction, handle_obj);
 
-            ensure_integers!(instruction, int_obj);
-        }
+        let handle = handle_obj.value.as_process_handle();
+        let target_opt = read_lock!(self.state.processes).get_handle(&handle);
 
-        let bytes = arg.value
-            .as_array()
-            .iter()
-            .map(|ref int_ptr| int_ptr.get().value.as_integer() as u8)
-            .collect::<Vec<_>>();
+        let result = if let Some(target) = target_opt {
+            let priority = target.priority().to_integer();
 
-        let string = try_error!(try_from_utf8!(bytes), process, register);
+            process.allocate(object_value::integer(priority),
+                             self.state.integer_prototype.clone())
+        } else {
+            let error = object_value::error(errors::PROCESS_NOT_ALIVE);
 
-        let obj = process.allocate(object_value::string(string), string_proto);
+            process.allocate_without_prototype(error)
+        };
 
-        process.set_register(register, obj);
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Returns the amount of characters in a string.
+    /// Changes the priority of a process.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 3 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the string.
-    fn ins_string_length(&self,
-                         process: RcProcess,
-                         _: RcCompiledCode,
-                         instruction: &Instruction)
-                         -> EmptyResult {
+    /// 1. The register to store the result in (true or false).
+    /// 2. The register containing the handle of the process.
+    /// 3. The register containing the new priority (an integer).
+    ///
+    /// The new priority is used the next time the process is scheduled. The
+    /// result is false if the process is no longer alive.
+    fn ins_set_process_priority(&self,
+                                process: RcProcess,
+                                _: RcCompiledCode,
+                                instruction: &Instruction)
+                                -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let arg_ptr = instruction_object!(instruction, process, 1);
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let priority_ptr = instruction_object!(instruction, process, 2);
 
-        let arg = arg_ptr.get();
+        let handle_obj = handle_ptr.get();
+        let priority_obj = priority_ptr.get();
 
-        ensure_strings!(instruction, arg);
+        ensure_process_handles!(instruction, handle_obj);
+        ensure_integers!(instruction, priority_obj);
 
-        let int_proto = self.state.integer_prototype.clone();
-        let length = arg.value.as_string().chars().count() as i64;
+        let priority_int = priority_obj.value.as_integer();
+        let priority = try_vm_error!(ProcessPriority::from_integer(priority_int),
+                                     instruction);
 
-        let obj = process.allocate(object_value::integer(length), int_proto);
+        let handle = handle_obj.value.as_process_handle();
+        let target_opt = read_lock!(self.state.processes).get_handle(&handle);
 
-        process.set_register(register, obj);
+        let result = if let Some(target) = target_opt {
+            target.set_priority(priority);
+
+            self.state.true_object.clone()
+        } else {
+            self.state.false_object.clone()
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Returns the amount of bytes in a string.
+    /// Gets the number of messages in the mailbox of a process.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the result in.
-    /// 2. The register of the string.
-    fn ins_string_size(&self,
-                       process: RcProcess,
-                       _: RcCompiledCode,
-                       instruction: &Instruction)
-                       -> EmptyResult {
+    /// 1. The register to store the number of messages in.
+    /// 2. The register containing the handle of the process.
+    ///
+    /// If the process is no longer alive an error is stored instead.
+    fn ins_process_mailbox_size(&self,
+                                process: RcProcess,
+                                _: RcCompiledCode,
+                                instruction: &Instruction)
+                                -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let arg_ptr = instruction_object!(instruction, process, 1);
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let handle_obj = handle_ptr.get();
 
-        let arg = arg_ptr.get();
+        ensure_process_handles!(instruction, handle_obj);
 
-        ensure_strings!(instruction, arg);
+        let handle = handle_obj.value.as_process_handle();
+        let target_opt = read_lock!(self.state.processes).get_handle(&handle);
 
-        let int_proto = self.state.integer_prototype.clone();
-        let size = arg.value.as_string().len() as i64;
+        let result = if let Some(target) = target_opt {
+            let size = target.mailbox_size() as i64;
 
-        let obj = process.allocate(object_value::integer(size), int_proto);
+            process.allocate(object_value::integer(size),
+                             self.state.integer_prototype.clone())
+        } else {
+            let error = object_value::error(errors::PROCESS_NOT_ALIVE);
 
-        process.set_register(register, obj);
+            process.allocate_without_prototype(error)
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Writes a string to STDOUT and returns the amount of written bytes.
-    ///
-    /// This instruction requires two arguments:
+    /// Gets the handles of all live processes.
     ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the string to write.
-    ///
-    /// The result of this instruction is either an integer indicating the
-    /// amount of bytes written, or an error object.
-    fn ins_stdout_write(&self,
+    /// This instruction requires one argument: the register to store the
+    /// resulting array in.
+    fn ins_process_list(&self,
                         process: RcProcess,
                         _: RcCompiledCode,
                         instruction: &Instruction)
                         -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let arg_ptr = instruction_object!(instruction, process, 1);
+        let handles = read_lock!(self.state.processes).handles();
 
-        let arg = arg_ptr.get();
+        let handle_objs = handles.into_iter()
+            .map(|handle| self.allocate_process_handle(&process, handle))
+            .collect::<Vec<_>>();
 
-        ensure_strings!(instruction, arg);
+        let array = process.allocate(object_value::array(handle_objs),
+                                     self.state.array_prototype.clone());
 
-        let int_proto = self.state.integer_prototype.clone();
-        let mut stdout = io::stdout();
+        process.set_register(register, array);
 
-        let result = try_io!(stdout.write(arg.value.as_string().as_bytes()),
-                             process, register);
+        Ok(())
+    }
 
-        try_io!(stdout.flush(), process, register);
+    /// Gets statistics about the memory used by a process.
+    ///
+    /// This instruction requires 2 arguments:
+    ///
+    /// 1. The register to store the statistics in.
+    /// 2. The register containing the handle of the process.
+    ///
+    /// The statistics are stored as an array of integers in the following
+    /// order:
+    ///
+    /// 1. The number of blocks used by the young generation.
+    /// 2. The number of blocks used by the mature generation.
+    /// 3. The number of blocks used by the mailbox.
+    /// 4. The number of young garbage collection cycles.
+    /// 5. The number of mature garbage collection cycles.
+    ///
+    /// If the process is no longer alive an error is stored instead.
+    fn ins_process_heap_info(&self,
+                             process: RcProcess,
+                             _: RcCompiledCode,
+                             instruction: &Instruction)
+                             -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let handle_ptr = instruction_object!(instruction, process, 1);
+        let handle_obj = handle_ptr.get();
 
-        let obj =
-            process.allocate(object_value::integer(result as i64), int_proto);
+        ensure_process_handles!(instruction, handle_obj);
 
-        process.set_register(register, obj);
+        let handle = handle_obj.value.as_process_handle();
+        let target_opt = read_lock!(self.state.processes).get_handle(&handle);
+
+        let result = if let Some(target) = target_opt {
+            let info = target.heap_info();
+
+            let values = vec![info.young_blocks,
+                              info.mature_blocks,
+                              info.mailbox_blocks,
+                              info.young_collections,
+                              info.mature_collections];
+
+            let value_objs = values.into_iter()
+                .map(|value| {
+                    process.allocate(object_value::integer(value as i64),
+                                     self.state.integer_prototype.clone())
+                })
+                .collect::<Vec<_>>();
+
+            process.allocate(object_value::array(value_objs),
+                             self.state.array_prototype.clone())
+        } else {
+            let error = object_value::error(errors::PROCESS_NOT_ALIVE);
+
+            process.allocate_without_prototype(error)
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Writes a string to STDERR and returns the amount of written bytes.
+    /// Registers a process under a name.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 3 arguments:
     ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the string to write.
+    /// 1. The register to store the result in (true or false).
+    /// 2. The register containing the name (a string) to register.
+    /// 3. The register containing the handle of the process to register.
     ///
-    /// The result of this instruction is either an integer indicating the
-    /// amount of bytes written, or an error object.
-    fn ins_stderr_write(&self,
-                        process: RcProcess,
-                        _: RcCompiledCode,
-                        instruction: &Instruction)
-                        -> EmptyResult {
+    /// The result is false if the name is already taken, if the process is
+    /// already registered, or if the process is no longer alive. Names are
+    /// removed automatically once a process terminates.
+    fn ins_register_process(&self,
+                            process: RcProcess,
+                            _: RcCompiledCode,
+                            instruction: &Instruction)
+                            -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let arg_ptr = instruction_object!(instruction, process, 1);
-
-        let arg = arg_ptr.get();
-
-        ensure_strings!(instruction, arg);
+        let name_ptr = instruction_object!(instruction, process, 1);
+        let handle_ptr = instruction_object!(instruction, process, 2);
 
-        let int_proto = self.state.integer_prototype.clone();
-        let mut stderr = io::stderr();
+        let name_obj = name_ptr.get();
+        let handle_obj = handle_ptr.get();
 
-        let result = try_io!(stderr.write(arg.value.as_string().as_bytes()),
-                             process, register);
+        ensure_strings!(instruction, name_obj);
+        ensure_process_handles!(instruction, handle_obj);
 
-        try_io!(stderr.flush(), process, register);
+        let name = name_obj.value.as_string().clone();
+        let handle = handle_obj.value.as_process_handle();
 
-        let obj =
-            process.allocate(object_value::integer(result as i64), int_proto);
+        let result = if write_lock!(self.state.processes)
+            .register(name, &handle) {
+            self.state.true_object.clone()
+        } else {
+            self.state.false_object.clone()
+        };
 
-        process.set_register(register, obj);
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Reads the given amount of bytes into a string.
+    /// Removes a name from the process registry.
     ///
-    /// This instruction requires two arguments:
+    /// This instruction requires 2 arguments:
     ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the amount of bytes to read.
+    /// 1. The register to store the result in (true or false).
+    /// 2. The register containing the name (a string) to unregister.
     ///
-    /// The result of this instruction is either a string containing the data
-    /// read, or an error object.
-    fn ins_stdin_read(&self,
-                      process: RcProcess,
-                      _: RcCompiledCode,
-                      instruction: &Instruction)
-                      -> EmptyResult {
+    /// The result is false if no process was registered under the name.
+    fn ins_unregister_process(&self,
+                              process: RcProcess,
+                              _: RcCompiledCode,
+                              instruction: &Instruction)
+                              -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let proto = self.state.string_prototype.clone();
+        let name_ptr = instruction_object!(instruction, process, 1);
+        let name_obj = name_ptr.get();
 
-        let mut buffer = file_reading_buffer!(instruction, process, 1);
+        ensure_strings!(instruction, name_obj);
 
-        try_io!(io::stdin().read_to_string(&mut buffer), process, register);
+        let name = name_obj.value.as_string();
 
-        let obj = process.allocate(object_value::string(buffer), proto);
+        let result = if write_lock!(self.state.processes).unregister(name) {
+            self.state.true_object.clone()
+        } else {
+            self.state.false_object.clone()
+        };
 
-        process.set_register(register, obj);
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Reads an entire line from STDIN into a string.
+    /// Looks up the handle of a process registered under a name.
     ///
-    /// This instruction requires 1 argument: the register to store the
-    /// resulting object in.
+    /// This instruction requires 2 arguments:
     ///
-    /// The result of this instruction is either a string containing the read
-    /// data, or an error object.
-    fn ins_stdin_read_line(&self,
+    /// 1. The register to store the process handle in.
+    /// 2. The register containing the name (a string) to look up.
+    ///
+    /// If no process is registered under the name false is stored instead.
+    fn ins_whereis_process(&self,
                            process: RcProcess,
                            _: RcCompiledCode,
                            instruction: &Instruction)
                            -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let proto = self.state.string_prototype.clone();
+        let name_ptr = instruction_object!(instruction, process, 1);
+        let name_obj = name_ptr.get();
 
-        let mut buffer = String::new();
+        ensure_strings!(instruction, name_obj);
 
-        try_io!(io::stdin().read_line(&mut buffer), process, register);
+        let found = {
+            let processes = read_lock!(self.state.processes);
 
-        let obj = process.allocate(object_value::string(buffer), proto);
+            processes.whereis(name_obj.value.as_string())
+                .map(|target| processes.handle(target.pid))
+        };
 
-        process.set_register(register, obj);
+        let result = if let Some(handle) = found {
+            self.allocate_process_handle(&process, handle)
+        } else {
+            self.state.false_object.clone()
+        };
+
+        process.set_register(register, result);
 
         Ok(())
     }
 
-    /// Opens a file handle in a particular mode (read-only, write-only, etc).
+    /// Sends a message to a process registered under a name.
     ///
-    /// This instruction requires X arguments:
+    /// This instruction takes 3 arguments:
     ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The path to the file to open.
-    /// 3. The register containing a string describing the mode to open the
-    ///    file in.
+    /// 1. The register to store the message in.
+    /// 2. The register containing the name (a string) of the process to send
+    ///    the message to.
+    /// 3. The register containing the message (an object) to send to the
+    ///    process.
     ///
-    /// The result of this instruction is either a file object or an error
-    /// object.
+    /// The message is discarded if no process is registered under the name.
+    /// An IO error is stored in the register if the message could not be
+    /// copied.
+    fn ins_send_named_process_message(&self,
+                                      process: RcProcess,
+                                      _: RcCompiledCode,
+                                      instruction: &Instruction)
+                                      -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let name_ptr = instruction_object!(instruction, process, 1);
+        let msg_ptr = instruction_object!(instruction, process, 2);
+        let name_obj = name_ptr.get();
+
+        ensure_strings!(instruction, name_obj);
+
+        let found = read_lock!(self.state.processes)
+            .whereis(name_obj.value.as_string());
+
+        if let Some(receiver) = found {
+            try_io!(receiver.send_message(msg_ptr.clone()), process, register);
+        }
+
+        process.set_register(register, msg_ptr);
+
+        Ok(())
+    }
+
+    /// Adds two floats
     ///
-    /// The available file modes supported are the same as those supported by
-    /// the `fopen()` system call, thus:
+    /// This instruction requires 3 arguments:
     ///
-    /// * r: opens a file for reading only
-    /// * r+: opens a file for reading and writing
-    /// * w: opens a file for writing only, truncating it if it exists, creating
-    ///   it otherwise
-    /// * w+: opens a file for reading and writing, truncating it if it exists,
-    ///   creating it if it doesn't exist
-    /// * a: opens a file for appending, creating it if it doesn't exist
-    /// * a+: opens a file for reading and appending, creating it if it doesn't
-    ///   exist
-    fn ins_file_open(&self,
+    /// 1. The register to store the result in.
+    /// 2. The register of the receiver.
+    /// 3. The register of the float to add.
+    fn ins_float_add(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        float_op!(self, process, instruction, +);
+
+        Ok(())
+    }
+
+    /// Multiplies two floats
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the receiver.
+    /// 3. The register of the float to multiply with.
+    fn ins_float_mul(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        float_op!(self, process, instruction, *);
+
+        Ok(())
+    }
+
+    /// Divides two floats
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the receiver.
+    /// 3. The register of the float to divide with.
+    fn ins_float_div(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        float_op!(self, process, instruction, /);
+
+        Ok(())
+    }
+
+    /// Subtracts two floats
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the receiver.
+    /// 3. The register of the float to subtract.
+    fn ins_float_sub(&self,
                      process: RcProcess,
                      _: RcCompiledCode,
                      instruction: &Instruction)
                      -> EmptyResult {
+        float_op!(self, process, instruction, -);
+
+        Ok(())
+    }
+
+    /// Gets the modulo of a float
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the receiver.
+    /// 3. The register of the float argument.
+    fn ins_float_mod(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        float_op!(self, process, instruction, %);
+
+        Ok(())
+    }
+
+    /// Converts a float to an integer
+    ///
+    /// This instruction requires 2 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the float to convert.
+    fn ins_float_to_integer(&self,
+                            process: RcProcess,
+                            _: RcCompiledCode,
+                            instruction: &Instruction)
+                            -> EmptyResult {
         let register = try_vm_error!(instruction.arg(0), instruction);
-        let path_ptr = instruction_object!(instruction, process, 1);
-        let mode_ptr = instruction_object!(instruction, process, 2);
+        let float_ptr = instruction_object!(instruction, process, 1);
+        let float = float_ptr.get();
 
-        let file_proto = self.state.file_prototype.clone();
+        ensure_floats!(instruction, float);
 
-        let path = path_ptr.get();
-        let mode = mode_ptr.get();
+        let result = float.value.as_float() as i64;
 
-        let path_string = path.value.as_string();
-        let mode_string = mode.value.as_string().as_ref();
-        let mut open_opts = OpenOptions::new();
+        let obj = process.allocate(object_value::integer(result),
+                                   self.state.integer_prototype.clone());
 
-        match mode_string {
-            "r" => open_opts.read(true),
-            "r+" => open_opts.read(true).write(true).truncate(true).create(true),
-            "w" => open_opts.write(true).truncate(true).create(true),
-            "w+" => open_opts.read(true).write(true).truncate(true).create(true),
-            "a" => open_opts.append(true).create(true),
-            "a+" => open_opts.read(true).append(true).create(true),
-            _ => set_error!(errors::IO_INVALID_OPEN_MODE, process, register),
-        };
+        process.set_register(register, obj);
 
-        let file = try_io!(open_opts.open(path_string), process, register);
+        Ok(())
+    }
 
-        let obj = process.allocate(object_value::file(file), file_proto);
+    /// Converts a float to a string
+    ///
+    /// This instruction requires 2 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the float to convert.
+    fn ins_float_to_string(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let float_ptr = instruction_object!(instruction, process, 1);
+        let float = float_ptr.get();
+
+        ensure_floats!(instruction, float);
+
+        let result = float.value.as_float().to_string();
+
+        let obj = process.allocate(object_value::string(result),
+                                   self.state.string_prototype.clone());
 
         process.set_register(register, obj);
 
         Ok(())
     }
 
-    /// Writes a string to a file.
+    /// Checks if one float is smaller than the other.
     ///
     /// This instruction requires 3 arguments:
     ///
-    /// 1. The register to store the amount of written bytes in.
-    /// 2. The register containing the file object to write to.
-    /// 3. The register containing the string to write.
+    /// 1. The register to store the result in.
+    /// 2. The register containing the float to compare.
+    /// 3. The register containing the float to compare with.
     ///
-    /// The result of this instruction is either the amount of written bytes or
-    /// an error object.
-    fn ins_file_write(&self,
-                      process: RcProcess,
-                      _: RcCompiledCode,
-                      instruction: &Instruction)
-                      -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let file_ptr = instruction_object!(instruction, process, 1);
-        let string_ptr = instruction_object!(instruction, process, 2);
+    /// The result of this instruction is either boolean true or false.
+    fn ins_float_smaller(&self,
+                         process: RcProcess,
+                         _: RcCompiledCode,
+                         instruction: &Instruction)
+                         -> EmptyResult {
+        float_bool_op!(self, process, instruction, <);
 
-        let mut file = file_ptr.get_mut();
-        let string = string_ptr.get();
+        Ok(())
+    }
 
-        ensure_files!(instruction, file);
-        ensure_strings!(instruction, string);
+    /// Checks if one float is greater than the other.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register containing the float to compare.
+    /// 3. The register containing the float to compare with.
+    ///
+    /// The result of this instruction is either boolean true or false.
+    fn ins_float_greater(&self,
+                         process: RcProcess,
+                         _: RcCompiledCode,
+                         instruction: &Instruction)
+                         -> EmptyResult {
+        float_bool_op!(self, process, instruction, >);
 
-        let int_proto = self.state.integer_prototype.clone();
-        let mut file = file.value.as_file_mut();
-        let bytes = string.value.as_string().as_bytes();
+        Ok(())
+    }
 
-        let result = try_io!(file.write(bytes), process, register);
+    /// Checks if two floats are equal.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register containing the float to compare.
+    /// 3. The register containing the float to compare with.
+    ///
+    /// The result of this instruction is either boolean true or false.
+    fn ins_float_equals(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        float_bool_op!(self, process, instruction, ==);
+
+        Ok(())
+    }
+
+    /// Inserts a value in an array.
+    ///
+    /// This instruction requires 4 arguments:
+    ///
+    /// 1. The register to store the result (the inserted value) in.
+    /// 2. The register containing the array to insert into.
+    /// 3. The register containing the index (as an integer) to insert at.
+    /// 4. The register containing the value to insert.
+    ///
+    /// An error is returned when the index is greater than the array length. A
+    /// negative index can be used to indicate a position from the end of the
+    /// array.
+    fn ins_array_insert(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let array_ptr = instruction_object!(instruction, process, 1);
+        let index_ptr = instruction_object!(instruction, process, 2);
+        let value_ptr = instruction_object!(instruction, process, 3);
+
+        let mut array = array_ptr.get_mut();
+
+        ensure_arrays!(instruction, array);
+        ensure_not_frozen!(instruction, array);
+
+        let index_obj = index_ptr.get();
+
+        ensure_integers!(instruction, index_obj);
+
+        let mut vector = array.value.as_array_mut();
+        let index = int_to_vector_index!(vector, index_obj.value.as_integer());
+
+        ensure_array_within_bounds!(instruction, vector, index);
+
+        let value = copy_if_permanent!(self.state.permanent_allocator,
+                                       value_ptr,
+                                       array_ptr,
+                                       instruction);
+
+        if vector.get(index).is_some() {
+            vector[index] = value;
+        } else {
+            vector.insert(index, value);
+        }
+
+        process.write_barrier(array_ptr, value);
+
+        process.set_register(register, value);
+
+        Ok(())
+    }
+
+    /// Gets the value of an array index.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the value in.
+    /// 2. The register containing the array.
+    /// 3. The register containing the index.
+    ///
+    /// An error is returned when the index is greater than the array length. A
+    /// negative index can be used to indicate a position from the end of the
+    /// array.
+    fn ins_array_at(&self,
+                    process: RcProcess,
+                    _: RcCompiledCode,
+                    instruction: &Instruction)
+                    -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let array_ptr = instruction_object!(instruction, process, 1);
+        let index_ptr = instruction_object!(instruction, process, 2);
+        let array = array_ptr.get();
+
+        let index_obj = index_ptr.get();
+
+        ensure_arrays!(instruction, array);
+        ensure_integers!(instruction, index_obj);
+
+        let vector = array.value.as_array();
+        let index = int_to_vector_index!(vector, index_obj.value.as_integer());
+
+        ensure_array_within_bounds!(instruction, vector, index);
+
+        let value = vector[index].clone();
+
+        process.set_register(register, value);
+
+        Ok(())
+    }
+
+    /// Removes a value from an array.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the removed value in.
+    /// 2. The register containing the array to remove a value from.
+    /// 3. The register containing the index.
+    ///
+    /// An error is returned when the index is greater than the array length. A
+    /// negative index can be used to indicate a position from the end of the
+    /// array.
+    fn ins_array_remove(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let array_ptr = instruction_object!(instruction, process, 1);
+        let index_ptr = instruction_object!(instruction, process, 2);
+
+        let mut array = array_ptr.get_mut();
+        let index_obj = index_ptr.get();
+
+        ensure_arrays!(instruction, array);
+        ensure_not_frozen!(instruction, array);
+        ensure_integers!(instruction, index_obj);
+
+        let mut vector = array.value.as_array_mut();
+        let index = int_to_vector_index!(vector, index_obj.value.as_integer());
+
+        ensure_array_within_bounds!(instruction, vector, index);
+
+        let value = vector.remove(index);
+
+        process.set_register(register, value);
+
+        Ok(())
+    }
+
+    /// Gets the amount of elements in an array.
+    ///
+    /// This instruction requires 2 arguments:
+    ///
+    /// 1. The register to store the length in.
+    /// 2. The register containing the array.
+    fn ins_array_length(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let array_ptr = instruction_object!(instruction, process, 1);
+
+        let array = array_ptr.get();
+
+        ensure_arrays!(instruction, array);
+
+        let vector = array.value.as_array();
+        let length = vector.len() as i64;
+
+        let obj = process.allocate(object_value::integer(length),
+                                   self.state.integer_prototype.clone());
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Removes all elements from an array.
+    ///
+    /// This instruction requires 1 argument: the register of the array.
+    fn ins_array_clear(&self,
+                       process: RcProcess,
+                       _: RcCompiledCode,
+                       instruction: &Instruction)
+                       -> EmptyResult {
+        let array_ptr = instruction_object!(instruction, process, 0);
+
+        let mut array = array_ptr.get_mut();
+
+        ensure_arrays!(instruction, array);
+        ensure_not_frozen!(instruction, array);
+
+        let mut vector = array.value.as_array_mut();
+
+        vector.clear();
+
+        Ok(())
+    }
+
+    /// Returns the lowercase equivalent of a string.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the new string in.
+    /// 2. The register containing the input string.
+    fn ins_string_to_lower(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let source_ptr = instruction_object!(instruction, process, 1);
+        let source = source_ptr.get();
+
+        ensure_strings!(instruction, source);
+
+        let lower = source.value.as_string().to_lowercase();
+
+        let obj = process.allocate(object_value::string(lower),
+                                   self.state.string_prototype.clone());
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Returns the uppercase equivalent of a string.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the new string in.
+    /// 2. The register containing the input string.
+    fn ins_string_to_upper(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let source_ptr = instruction_object!(instruction, process, 1);
+        let source = source_ptr.get();
+
+        ensure_strings!(instruction, source);
+
+        let upper = source.value.as_string().to_uppercase();
+
+        let obj = process.allocate(object_value::string(upper),
+                                   self.state.string_prototype.clone());
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Checks if two strings are equal.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the string to compare.
+    /// 3. The register of the string to compare with.
+    fn ins_string_equals(&self,
+                         process: RcProcess,
+                         _: RcCompiledCode,
+                         instruction: &Instruction)
+                         -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let receiver_ptr = instruction_object!(instruction, process, 1);
+        let arg_ptr = instruction_object!(instruction, process, 2);
+
+        let receiver = receiver_ptr.get();
+        let arg = arg_ptr.get();
+
+        ensure_strings!(instruction, receiver, arg);
+
+        let result = receiver.value.as_string() == arg.value.as_string();
+
+        let boolean = if result {
+            self.state.true_object.clone()
+        } else {
+            self.state.false_object.clone()
+        };
+
+        process.set_register(register, boolean);
+
+        Ok(())
+    }
+
+    /// Returns an array containing the bytes of a string.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register containing the string to get the bytes from.
+    fn ins_string_to_bytes(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let arg_ptr = instruction_object!(instruction, process, 1);
+
+        let arg = arg_ptr.get();
+
+        ensure_strings!(instruction, arg);
+
+        let int_proto = self.state.integer_prototype.clone();
+        let array_proto = self.state.array_prototype.clone();
+
+        let array = arg.value
+            .as_string()
+            .as_bytes()
+            .iter()
+            .map(|&b| {
+                process
+                    .allocate(object_value::integer(b as i64), int_proto.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let obj = process.allocate(object_value::array(array), array_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Creates a string from an array of bytes
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register containing the array of bytes.
+    ///
+    /// The result of this instruction is either a string based on the given
+    /// bytes, or an error object.
+    fn ins_string_from_bytes(&self,
+                             process: RcProcess,
+                             _: RcCompiledCode,
+                             instruction: &Instruction)
+                             -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let arg_ptr = instruction_object!(instruction, process, 1);
+
+        let arg = arg_ptr.get();
+
+        ensure_arrays!(instruction, arg);
+
+        let string_proto = self.state.string_prototype.clone();
+        let array = arg.value.as_array();
+
+        for int_ptr in array.iter() {
+            let int_obj = int_ptr.get();
+
+            ensure_integers!(instruction, int_obj);
+        }
+
+        let bytes = arg.value
+            .as_array()
+            .iter()
+            .map(|ref int_ptr| int_ptr.get().value.as_integer() as u8)
+            .collect::<Vec<_>>();
+
+        let string = try_error!(try_from_utf8!(bytes), process, register);
+
+        let obj = process.allocate(object_value::string(string), string_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Returns the amount of characters in a string.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the string.
+    fn ins_string_length(&self,
+                         process: RcProcess,
+                         _: RcCompiledCode,
+                         instruction: &Instruction)
+                         -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let arg_ptr = instruction_object!(instruction, process, 1);
+
+        let arg = arg_ptr.get();
+
+        ensure_strings!(instruction, arg);
+
+        let int_proto = self.state.integer_prototype.clone();
+        let length = arg.value.as_string().chars().count() as i64;
+
+        let obj = process.allocate(object_value::integer(length), int_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Returns the amount of bytes in a string.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. The register of the string.
+    fn ins_string_size(&self,
+                       process: RcProcess,
+                       _: RcCompiledCode,
+                       instruction: &Instruction)
+                       -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let arg_ptr = instruction_object!(instruction, process, 1);
+
+        let arg = arg_ptr.get();
+
+        ensure_strings!(instruction, arg);
+
+        let int_proto = self.state.integer_prototype.clone();
+        let size = arg.value.as_string().len() as i64;
+
+        let obj = process.allocate(object_value::integer(size), int_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Writes a string to STDOUT and returns the amount of written bytes.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the string to write.
+    ///
+    /// The result of this instruction is either an integer indicating the
+    /// amount of bytes written, or an error object.
+    fn ins_stdout_write(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let arg_ptr = instruction_object!(instruction, process, 1);
+
+        let arg = arg_ptr.get();
+
+        ensure_strings!(instruction, arg);
+
+        let int_proto = self.state.integer_prototype.clone();
+        let mut stdout = io::stdout();
+
+        let result = try_io!(stdout.write(arg.value.as_string().as_bytes()),
+                             process, register);
+
+        try_io!(stdout.flush(), process, register);
+
+        let obj =
+            process.allocate(object_value::integer(result as i64), int_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Writes a string to STDERR and returns the amount of written bytes.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the string to write.
+    ///
+    /// The result of this instruction is either an integer indicating the
+    /// amount of bytes written, or an error object.
+    fn ins_stderr_write(&self,
+                        process: RcProcess,
+                        _: RcCompiledCode,
+                        instruction: &Instruction)
+                        -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let arg_ptr = instruction_object!(instruction, process, 1);
+
+        let arg = arg_ptr.get();
+
+        ensure_strings!(instruction, arg);
+
+        let int_proto = self.state.integer_prototype.clone();
+        let mut stderr = io::stderr();
+
+        let result = try_io!(stderr.write(arg.value.as_string().as_bytes()),
+                             process, register);
+
+        try_io!(stderr.flush(), process, register);
+
+        let obj =
+            process.allocate(object_value::integer(result as i64), int_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Reads the given amount of bytes into a string.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the amount of bytes to read.
+    ///
+    /// The result of this instruction is either a string containing the data
+    /// read, or an error object.
+    fn ins_stdin_read(&self,
+                      process: RcProcess,
+                      _: RcCompiledCode,
+                      instruction: &Instruction)
+                      -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let proto = self.state.string_prototype.clone();
+
+        let mut buffer = file_reading_buffer!(instruction, process, 1);
+
+        try_io!(io::stdin().read_to_string(&mut buffer), process, register);
+
+        let obj = process.allocate(object_value::string(buffer), proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Reads an entire line from STDIN into a string.
+    ///
+    /// This instruction requires 1 argument: the register to store the
+    /// resulting object in.
+    ///
+    /// The result of this instruction is either a string containing the read
+    /// data, or an error object.
+    fn ins_stdin_read_line(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let proto = self.state.string_prototype.clone();
+
+        let mut buffer = String::new();
+
+        try_io!(io::stdin().read_line(&mut buffer), process, register);
+
+        let obj = process.allocate(object_value::string(buffer), proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Opens a file handle in a particular mode (read-only, write-only, etc).
+    ///
+    /// This instruction requires X arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The path to the file to open.
+    /// 3. The register containing a string describing the mode to open the
+    ///    file in.
+    ///
+    /// The result of this instruction is either a file object or an error
+    /// object.
+    ///
+    /// The available file modes supported are the same as those supported by
+    /// the `fopen()` system call, thus:
+    ///
+    /// * r: opens a file for reading only
+    /// * r+: opens a file for reading and writing
+    /// * w: opens a file for writing only, truncating it if it exists, creating
+    ///   it otherwise
+    /// * w+: opens a file for reading and writing, truncating it if it exists,
+    ///   creating it if it doesn't exist
+    /// * a: opens a file for appending, creating it if it doesn't exist
+    /// * a+: opens a file for reading and appending, creating it if it doesn't
+    ///   exist
+    fn ins_file_open(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let path_ptr = instruction_object!(instruction, process, 1);
+        let mode_ptr = instruction_object!(instruction, process, 2);
+
+        let file_proto = self.state.file_prototype.clone();
+
+        let path = path_ptr.get();
+        let mode = mode_ptr.get();
+
+        let path_string = path.value.as_string();
+        let mode_string = mode.value.as_string().as_ref();
+        let mut open_opts = OpenOptions::new();
+
+        match mode_string {
+            "r" => open_opts.read(true),
+            "r+" => open_opts.read(true).write(true).truncate(true).create(true),
+            "w" => open_opts.write(true).truncate(true).create(true),
+            "w+" => open_opts.read(true).write(true).truncate(true).create(true),
+            "a" => open_opts.append(true).create(true),
+            "a+" => open_opts.read(true).append(true).create(true),
+            _ => set_error!(errors::IO_INVALID_OPEN_MODE, process, register),
+        };
+
+        let file = try_io!(open_opts.open(path_string), process, register);
+
+        let obj = process.allocate(object_value::file(file), file_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Writes a string to a file.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the amount of written bytes in.
+    /// 2. The register containing the file object to write to.
+    /// 3. The register containing the string to write.
+    ///
+    /// The result of this instruction is either the amount of written bytes or
+    /// an error object.
+    fn ins_file_write(&self,
+                      process: RcProcess,
+                      _: RcCompiledCode,
+                      instruction: &Instruction)
+                      -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let file_ptr = instruction_object!(instruction, process, 1);
+        let string_ptr = instruction_object!(instruction, process, 2);
+
+        let mut file = file_ptr.get_mut();
+        let string = string_ptr.get();
+
+        ensure_files!(instruction, file);
+        ensure_strings!(instruction, string);
+
+        let int_proto = self.state.integer_prototype.clone();
+        let mut file = file.value.as_file_mut();
+        let bytes = string.value.as_string().as_bytes();
+
+        let result = try_io!(file.write(bytes), process, register);
+
+        let obj =
+            process.allocate(object_value::integer(result as i64), int_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Reads a number of bytes from a file.
+    ///
+    /// This instruction takes 3 arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the file to read from.
+    /// 3. The register containing the amount of bytes to read, if left out
+    ///    all data is read instead.
+    ///
+    /// The result of this instruction is either a string containing the data
+    /// read, or an error object.
+    fn ins_file_read(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let file_ptr = instruction_object!(instruction, process, 1);
+
+        let mut file_obj = file_ptr.get_mut();
+
+        ensure_files!(instruction, file_obj);
+
+        let mut buffer = file_reading_buffer!(instruction, process, 2);
+        let int_proto = self.state.integer_prototype.clone();
+        let mut file = file_obj.value.as_file_mut();
+
+        try_io!(file.read_to_string(&mut buffer), process, register);
+
+        let obj = process.allocate(object_value::string(buffer), int_proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Reads an entire line from a file.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the file to read from.
+    ///
+    /// The result of this instruction is either a string containing the read
+    /// line, or an error object.
+    fn ins_file_read_line(&self,
+                          process: RcProcess,
+                          _: RcCompiledCode,
+                          instruction: &Instruction)
+                          -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let file_ptr = instruction_object!(instruction, process, 1);
+
+        let mut file_obj = file_ptr.get_mut();
+
+        ensure_files!(instruction, file_obj);
+
+        let proto = self.state.string_prototype.clone();
+        let mut file = file_obj.value.as_file_mut();
+        let mut bytes = Vec::new();
+
+        for result in file.bytes() {
+            let byte = try_io!(result, process, register);
+
+            bytes.push(byte);
+
+            if byte == 0xA {
+                break;
+            }
+        }
+
+        let string = try_error!(try_from_utf8!(bytes), process, register);
+
+        let obj = process.allocate(object_value::string(string), proto);
+
+        process.set_register(register, obj);
+
+        Ok(())
+    }
+
+    /// Flushes a file.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the result in.
+    /// 2. the register containing the file to flush.
+    ///
+    /// The resulting object is either boolean true (upon success), or an error
+    /// object.
+    fn ins_file_flush(&self,
+                      process: RcProcess,
+                      _: RcCompiledCode,
+                      instruction: &Instruction)
+                      -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let file_ptr = instruction_object!(instruction, process, 1);
+
+        let mut file_obj = file_ptr.get_mut();
+
+        ensure_files!(instruction, file_obj);
+
+        let mut file = file_obj.value.as_file_mut();
+
+        try_io!(file.flush(), process, register);
+
+        process.set_register(register, self.state.true_object.clone());
+
+        Ok(())
+    }
+
+    /// Returns the size of a file in bytes.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the file.
+    ///
+    /// The resulting object is either an integer representing the amount of
+    /// bytes, or an error object.
+    fn ins_file_size(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let file_ptr = instruction_object!(instruction, process, 1);
+
+        let file_obj = file_ptr.get();
+
+        ensure_files!(instruction, file_obj);
+
+        let file = file_obj.value.as_file();
+        let meta = try_io!(file.metadata(), process, register);
+
+        let size = meta.len() as i64;
+        let proto = self.state.integer_prototype.clone();
+
+        let result = process.allocate(object_value::integer(size), proto);
+
+        process.set_register(register, result);
+
+        Ok(())
+    }
+
+    /// Sets a file cursor to the given offset in bytes.
+    ///
+    /// This instruction requires 3 arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The register containing the input file.
+    /// 3. The offset to seek to as an integer.
+    ///
+    /// The resulting object is either an integer representing the new cursor
+    /// position, or an error object.
+    fn ins_file_seek(&self,
+                     process: RcProcess,
+                     _: RcCompiledCode,
+                     instruction: &Instruction)
+                     -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let file_ptr = instruction_object!(instruction, process, 1);
+        let offset_ptr = instruction_object!(instruction, process, 2);
+
+        let mut file_obj = file_ptr.get_mut();
+        let offset_obj = offset_ptr.get();
+
+        ensure_files!(instruction, file_obj);
+        ensure_integers!(instruction, offset_obj);
+
+        let mut file = file_obj.value.as_file_mut();
+        let offset = offset_obj.value.as_integer();
+
+        ensure_positive_read_size!(instruction, offset);
+
+        let seek_from = SeekFrom::Start(offset as u64);
+        let new_offset = try_io!(file.seek(seek_from), process, register);
+
+        let proto = self.state.integer_prototype.clone();
+
+        let result =
+            process.allocate(object_value::integer(new_offset as i64), proto);
+
+        process.set_register(register, result);
+
+        Ok(())
+    }
+
+    /// Parses and runs a given bytecode file using a string literal
+    ///
+    /// Files are executed only once. After a file has been executed any
+    /// following calls are basically no-ops.
+    ///
+    /// This instruction requires 2 arguments:
+    ///
+    /// 1. The register to store the resulting object in.
+    /// 2. The string literal index containing the file path of the bytecode
+    ///    file.
+    ///
+    /// The result of this instruction is whatever the bytecode file returned.
+    fn ins_run_literal_file(&self,
+                            process: RcProcess,
+                            code: RcCompiledCode,
+                            instruction: &Instruction)
+                            -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let index = try_vm_error!(instruction.arg(1), instruction);
+        let path = try_vm_error!(code.string(index), instruction);
+
+        self.run_file(path, process, instruction, register)
+    }
+
+    /// Parses and runs a given bytecode file using a runtime allocated string
+    ///
+    /// This instruction takes the same arguments as the "run_literal_file"
+    /// instruction except instead of using a string literal it uses a register
+    /// containing a runtime allocated string.
+    fn ins_run_file(&self,
+                    process: RcProcess,
+                    _: RcCompiledCode,
+                    instruction: &Instruction)
+                    -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let path_ptr = instruction_object!(instruction, process, 1);
+
+        let path = path_ptr.get();
+
+        ensure_strings!(instruction, path);
+
+        self.run_file(path.value.as_string(), process, instruction, register)
+    }
+
+    /// Sets the caller of a method.
+    ///
+    /// This instruction requires one argument: the register to store the caller
+    /// in. If no caller is present "self" is set in the register instead.
+    fn ins_get_caller(&self,
+                      process: RcProcess,
+                      _: RcCompiledCode,
+                      instruction: &Instruction)
+                      -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+
+        let caller = {
+            let context = process.context();
+
+            if let Some(parent) = context.parent() {
+                parent.self_object()
+            } else {
+                context.self_object()
+            }
+        };
+
+        process.set_register(register, caller);
+
+        Ok(())
+    }
+
+    /// Freezes an object, preventing it from being modified.
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register to store the frozen object in.
+    /// 2. The register containing the object to freeze.
+    ///
+    /// The object and all objects it refers to are frozen in place, after
+    /// which they are copied into a heap shared by all processes. The shared
+    /// copy is stored in the register, and can be sent to other processes
+    /// without copying it.
+    ///
+    /// Permanent objects are already shared and are returned as-is. If the
+    /// object could not be copied an error is stored in the register instead.
+    fn ins_freeze(&self,
+                  process: RcProcess,
+                  _: RcCompiledCode,
+                  instruction: &Instruction)
+                  -> EmptyResult {
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let object_ptr = instruction_object!(instruction, process, 1);
+
+        let frozen = if object_ptr.is_permanent() {
+            object_ptr
+        } else {
+            try_io!(process.share(object_ptr), process, register)
+        };
+
+        process.set_register(register, frozen);
+
+        Ok(())
+    }
+
+    /// Sets the outer scope of an object
+    ///
+    /// This instruction requires two arguments:
+    ///
+    /// 1. The register containing the object for which to set the outer scope.
+    /// 2. The register containing the object to use as the outer scope.
+    fn ins_set_outer_scope(&self,
+                           process: RcProcess,
+                           _: RcCompiledCode,
+                           instruction: &Instruction)
+                           -> EmptyResult {
+        let target_ptr = instruction_object!(instruction, process, 0);
+        let scope_ptr = instruction_object!(instruction, process, 1);
+
+        ensure_not_frozen!(instruction, target_ptr.get());
+
+        let scope = copy_if_permanent!(self.state.permanent_allocator,
+                                       scope_ptr,
+                                       target_ptr,
+                                       instruction);
+
+        target_ptr.set_outer_scope(&process, scope);
+
+        Ok(())
+    }
+
+    /// Prints a VM backtrace of a given thread with a message.
+    fn error(&self, process: RcProcess, error: VirtualMachineError) {
+        let mut stderr = io::stderr();
+        let mut message =
+            format!("A fatal VM error occurred in process {}:", process.pid);
+
+        message.push_str(&format!("\n\n{}\n\nCall stack:\n\n", error.message));
+
+        let mut frames = process.call_frame().call_stack().peekable();
+
+        while let Some(frame) = frames.next() {
+            message.push_str(&format!(
+                "{} line {} in {}\n",
+                frame.file(),
+                frame.line,
+                frame.name()
+            ));
+
+            if frame.tail_calls > 0 {
+                message.push_str(&format!(
+                    "    ... {} frame(s) elided by tail calls\n",
+                    frame.tail_calls
+                ));
+            }
+
+            // Recursive calls produce many identical frames, which we collapse
+            // into a single entry to keep the output readable.
+            let mut repeated = 0;
+
+            while frames.peek()
+                .map_or(false, |next| frame.same_location(next)) {
+                frames.next();
+
+                repeated += 1;
+            }
+
+            if repeated > 0 {
+                message.push_str(&format!(
+                    "    ... {} identical frame(s) omitted\n",
+                    repeated
+                ));
+            }
+        }
+
+        stderr.write(message.as_bytes()).unwrap();
+        stderr.flush().unwrap();
+
+        process.set_backtrace(message);
+    }
+
+    /// Returns true if the given process is the main process.
+    fn is_main_process(&self, process: &RcProcess) -> bool {
+        if let Some(ref main) = *read_lock!(self.state.main_process) {
+            &**main as *const Process == &**process as *const Process
+        } else {
+            false
+        }
+    }
+
+    /// Schedules the execution of a new CompiledCode.
+    ///
+    /// An error is returned if scheduling the code would exceed the maximum
+    /// call depth of the process.
+    fn schedule_code(&self,
+                     process: RcProcess,
+                     code: RcCompiledCode,
+                     self_obj: ObjectPointer,
+                     args: &Vec<ObjectPointer>,
+                     binding: Option<RcBinding>,
+                     register: usize,
+                     line: u32)
+                     -> EmptyResult {
+        let max_depth = self.config().max_call_depth;
+
+        if process.call_depth() >= max_depth {
+            return_vm_error!(format!("Stack overflow: the maximum call depth \
+                                      of {} has been exceeded",
+                                     max_depth),
+                             line);
+        }
+
+        let context = if let Some(rc_bind) = binding {
+            ExecutionContext::with_binding(rc_bind, code.clone(), Some(register))
+        } else {
+            ExecutionContext::with_object(self_obj, code.clone(), Some(register))
+        };
+
+        let frame = CallFrame::from_code(code);
+
+        process.push_context(context);
+        process.push_call_frame(frame);
+
+        for (index, arg) in args.iter().enumerate() {
+            process.set_local(index, arg.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Schedules the execution of a new CompiledCode in tail position.
+    ///
+    /// Instead of nesting a new context the current context is replaced,
+    /// re-using its return register. The call frame of the call site is
+    /// removed and recorded as an elided frame in the frame of the caller.
+    fn schedule_tail_code(&self,
+                          process: RcProcess,
+                          code: RcCompiledCode,
+                          self_obj: ObjectPointer,
+                          args: &Vec<ObjectPointer>) {
+        let context = ExecutionContext::with_object(self_obj, code, None);
+
+        process.replace_context(context);
+        process.elide_call_frame();
+
+        for (index, arg) in args.iter().enumerate() {
+            process.set_local(index, arg.clone());
+        }
+    }
+
+    /// Runs a bytecode file.
+    fn run_file(&self,
+                path_str: &String,
+                process: RcProcess,
+                instruction: &Instruction,
+                register: usize)
+                -> EmptyResult {
+        process.advance_line(instruction.line);
+
+        {
+            let mut executed = write_lock!(self.state.executed_files);
+
+            if executed.contains(path_str) {
+                return Ok(());
+            } else {
+                executed.insert(path_str.clone());
+            }
+        }
+
+        let mut input_path = PathBuf::from(path_str);
+
+        if input_path.is_relative() {
+            let mut found = false;
+
+            for directory in self.config().directories.iter() {
+                let full_path = directory.join(path_str);
+
+                if full_path.exists() {
+                    input_path = full_path;
+                    found = true;
+
+                    break;
+                }
+            }
+
+            if !found {
+                return_vm_error!(format!("No file found for {}", path_str),
+                                 instruction.line);
+            }
+        }
+
+        let input_path_str = input_path.to_str().unwrap();
+
+        match bytecode_parser::parse_file(input_path_str) {
+            Ok(body) => {
+                let self_obj = self.state.top_level.clone();
+
+                try!(self.schedule_code(process.clone(),
+                                        body,
+                                        self_obj,
+                                        &Vec::new(),
+                                        None,
+                                        register,
+                                        instruction.line));
+
+                process.pop_call_frame();
+
+                Ok(())
+            }
+            Err(err) => {
+                return_vm_error!(
+                    format!("Failed to parse {}: {:?}", input_path_str, err),
+                    instruction.line
+                );
+            }
+        }
+    }
+
+    /// Sends a message to an object.
+    fn send_message(&self,
+                    name: &String,
+                    process: RcProcess,
+                    code: RcCompiledCode,
+                    instruction: &Instruction)
+                    -> EmptyResult {
+        // Advance the line number so error messages contain the correct frame
+        // pointing to the call site.
+        process.advance_line(instruction.line);
+
+        let register = try_vm_error!(instruction.arg(0), instruction);
+        let receiver_ptr = instruction_object!(instruction, process, 1);
+        let rest_arg = try_vm_error!(instruction.arg(3), instruction) == 1;
+
+        // When the receiver doesn't respond to the message we'll fall back to
+        // the "method_missing" method, if defined.
+        let (method_ptr, missing) = {
+            let receiver = receiver_ptr.get();
+
+            if let Some(method) = receiver.lookup_method(name) {
+                (method, false)
+            } else {
+                let fallback = try_vm_error!(
+                    receiver.lookup_method(&METHOD_MISSING.to_string())
+                        .ok_or_else(|| format!("undefined method \"{}\"", name)),
+                    instruction
+                );
+
+                (fallback, true)
+            }
+        };
+
+        let method_obj = method_ptr.get();
+
+        ensure_compiled_code!(instruction, method_obj);
+
+        let method_code = method_obj.value.as_compiled_code();
+
+        // Errors about the given arguments should refer to the method that is
+        // actually called.
+        let method_name = if missing {
+            METHOD_MISSING.to_string()
+        } else {
+            name.clone()
+        };
+
+        // Argument handling
+        let kw_count = try_vm_error!(instruction.arg(4), instruction);
+        let tot_args = method_code.arguments as usize;
+        let req_args = method_code.required_arguments as usize;
+
+        let arg_count = try_vm_error!(
+            instruction.arguments
+                .len()
+                .checked_sub(5 + (kw_count * 2))
+                .ok_or_else(|| {
+                    format!("Invalid number of keyword arguments: {}", kw_count)
+                }),
+            instruction
+        );
+
+        let mut arguments = try!(
+            self.collect_arguments(process.clone(), instruction, 5, arg_count)
+        );
+
+        // Unpack the last argument if it's a rest argument
+        if rest_arg {
+            if let Some(last_arg) = arguments.pop() {
+                let array = last_arg.get();
+
+                ensure_arrays!(instruction, array);
+
+                for value in array.value.as_array() {
+                    arguments.push(value.clone());
+                }
+            }
+        }
+
+        // The fallback method is called with the name of the original message
+        // and an array containing the original arguments.
+        if missing {
+            if kw_count > 0 {
+                return_vm_error!(format!("The message \"{}\" is not defined, \
+                                          and keyword arguments can not be \
+                                          passed to {}",
+                                         name,
+                                         METHOD_MISSING),
+                                 instruction.line);
+            }
+
+            let name_obj = process.allocate(object_value::string(name.clone()),
+                                            self.state.string_prototype.clone());
+
+            let args_obj = process.allocate(object_value::array(arguments),
+                                            self.state.array_prototype.clone());
+
+            arguments = vec![name_obj, args_obj];
+        }
+
+        // If the method defines a rest argument we'll pack any excessive
+        // arguments into a single array.
+        let rest_array = if method_code.rest_argument {
+            let rest = if arguments.len() > tot_args {
+                arguments.split_off(tot_args)
+            } else {
+                Vec::new()
+            };
+
+            Some(process.allocate(object_value::array(rest),
+                                  self.state.array_prototype.clone()))
+        } else {
+            None
+        };
+
+        if arguments.len() > tot_args {
+            return_vm_error!(
+                format!(
+                    "{} accepts up to {} arguments, but {} arguments were given",
+                    method_name,
+                    method_code.arguments,
+                    arguments.len()
+                ),
+                instruction.line
+            );
+        }
+
+        let keywords = try!(self.keyword_arguments(process.clone(),
+                                                   code,
+                                                   &method_code,
+                                                   &method_name,
+                                                   arguments.len(),
+                                                   instruction,
+                                                   5 + arg_count,
+                                                   kw_count));
+
+        if keywords.is_empty() && arguments.len() < req_args {
+            return_vm_error!(
+                format!(
+                    "{} requires {} arguments, but {} arguments were given",
+                    method_name,
+                    method_code.required_arguments,
+                    arguments.len()
+                ),
+                instruction.line
+            );
+        }
+
+        // Required arguments can be given either by position or by name.
+        let missing_required = (arguments.len()..req_args)
+            .find(|index| !keywords.iter().any(|&(kw, _)| kw == *index));
+
+        if let Some(index) = missing_required {
+            return_vm_error!(
+                format!("The required argument \"{}\" of {} is not given",
+                        method_code.locals[index],
+                        method_name),
+                instruction.line
+            );
+        }
+
+        if self.is_tail_call(&process, register) {
+            self.schedule_tail_code(process.clone(),
+                                    method_code,
+                                    receiver_ptr.clone(),
+                                    &arguments);
+        } else {
+            try!(self.schedule_code(process.clone(),
+                                    method_code,
+                                    receiver_ptr.clone(),
+                                    &arguments,
+                                    None,
+                                    register,
+                                    instruction.line));
+
+            process.pop_call_frame();
+        }
+
+        // Any optional arguments skipped by keyword arguments remain undefined
+        // in the new binding.
+        for &(index, value) in keywords.iter() {
+            process.set_local(index, value);
+        }
+
+        if let Some(rest) = rest_array {
+            process.set_local(tot_args, rest);
+        }
+
+        self.schedule_default_arguments(&process, instruction.line)
+    }
+
+    /// Returns an error if the predicate of a selective receive tries to
+    /// receive a message.
+    fn ensure_not_matching_message(&self,
+                                   process: &RcProcess,
+                                   instruction: &Instruction)
+                                   -> EmptyResult {
+        if process.is_matching_message() {
+            return_vm_error!("Messages can not be received while running the \
+                              predicate of a selective receive"
+                                 .to_string(),
+                             instruction.line);
+        }
+
+        Ok(())
+    }
+
+    /// Schedules the evaluation of default argument values for the current
+    /// execution context.
+    ///
+    /// A default value is only evaluated if its argument was not given. The
+    /// values are evaluated in the binding of the current context, in the
+    /// order the arguments are defined in.
+    fn schedule_default_arguments(&self,
+                                  process: &RcProcess,
+                                  line: u32)
+                                  -> EmptyResult {
+        let code = process.compiled_code();
+        let binding = process.binding();
+        let max_depth = self.config().max_call_depth;
+
+        // Contexts are executed in the reverse order of scheduling, thus we
+        // schedule the last argument first.
+        for &(index, ref default_code) in code.default_arguments.iter().rev() {
+            if binding.local_exists(index) {
+                continue;
+            }
+
+            if process.call_depth() >= max_depth {
+                return_vm_error!(format!("Stack overflow: the maximum call \
+                                          depth of {} has been exceeded",
+                                         max_depth),
+                                 line);
+            }
+
+            let context = ExecutionContext::for_default_argument(
+                binding.clone(),
+                default_code.clone(),
+                index
+            );
+
+            process.push_context(context);
+            process.push_call_frame(CallFrame::from_code(default_code.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the keyword arguments of a message send.
+    ///
+    /// Keyword arguments are given as pairs of a string literal index (the
+    /// name of the argument) and a register containing the value. The names
+    /// are resolved against the arguments of the method to call.
+    ///
+    /// The return value is a Vec containing the local variable indexes and
+    /// values of the keyword arguments.
+    fn keyword_arguments(&self,
+                         process: RcProcess,
+                         code: RcCompiledCode,
+                         method_code: &RcCompiledCode,
+                         name: &String,
+                         positional: usize,
+                         instruction: &Instruction,
+                         offset: usize,
+                         amount: usize)
+                         -> KeywordArgumentsResult {
+        let mut keywords: Vec<(usize, ObjectPointer)> = Vec::new();
+
+        for kw_index in 0..amount {
+            let arg_offset = offset + (kw_index * 2);
+            let kw_name_idx = try_vm_error!(instruction.arg(arg_offset),
+                                            instruction);
+
+            let kw_name = try_vm_error!(code.string(kw_name_idx), instruction);
+            let value_reg = try_vm_error!(instruction.arg(arg_offset + 1),
+                                          instruction);
+
+            let value = try_vm_error!(process.get_register(value_reg),
+                                      instruction);
+
+            let index = try_vm_error!(
+                method_code.argument_index(kw_name).ok_or_else(|| {
+                    format!("{} does not define an argument named \"{}\"",
+                            name,
+                            kw_name)
+                }),
+                instruction
+            );
+
+            let duplicate = index < positional ||
+                            keywords.iter().any(|&(other, _)| other == index);
+
+            if duplicate {
+                return_vm_error!(format!("The argument \"{}\" of {} is given \
+                                          more than once",
+                                         kw_name,
+                                         name),
+                                 instruction.line);
+            }
+
+            keywords.push((index, value));
+        }
+
+        Ok(keywords)
+    }
+
+    /// Returns true if a message send storing its result in the given register
+    /// is in tail position.
+    ///
+    /// A send is in tail position when the next instruction returns the
+    /// register the result is stored in, and the current context has a parent
+    /// to return the value to.
+    fn is_tail_call(&self, process: &RcProcess, register: usize) -> bool {
+        let context = process.context();
+
+        if context.parent.is_none() || context.return_register.is_none() {
+            return false;
+        }
+
+        match context.code.instructions.get(context.instruction_index) {
+            Some(next) => {
+                match next.instruction_type {
+                    InstructionType::Return => {
+                        next.arg(0).ok() == Some(register)
+                    }
+                    _ => false,
+                }
+            }
+            None => false,
+        }
+    }
+
+    /// Collects a set of arguments from an instruction.
+    fn collect_arguments(&self,
+                         process: RcProcess,
+                         instruction: &Instruction,
+                         offset: usize,
+                         amount: usize)
+                         -> ObjectVecResult {
+        let mut args: Vec<ObjectPointer> = Vec::new();
+
+        for index in offset..(offset + amount) {
+            let arg_index = try_vm_error!(instruction.arg(index), instruction);
+
+            let arg = try_vm_error!(
+                process.get_register(arg_index),
+                instruction
+            );
+
+            args.push(arg)
+        }
+
+        Ok(args)
+    }
+
+    /// Starts a new thread.
+    fn start_thread(&self) -> RcThread {
+        let state_clone = self.state.clone();
+
+        let (sender, receiver) = channel();
+
+        let handle = thread::spawn(move || {
+            let thread = receiver.recv().unwrap();
+            let vm = VirtualMachine::new(state_clone);
+
+            vm.run_thread(thread);
+        });
+
+        let thread = self.allocate_thread(Some(handle));
+
+        sender.send(thread.clone()).unwrap();
+
+        thread
+    }
+
+    /// Starts a new GC thread
+    fn start_gc_thread(&self) {
+        let state_clone = self.state.clone();
+
+        thread::spawn(move || {
+            let mut gc_thread = GcThread::new(state_clone);
+
+            gc_thread.run();
+        });
+    }
+
+    /// Starts a thread that helps GC threads mark the heap of a process.
+    fn start_mark_thread(&self) {
+        let state_clone = self.state.clone();
+
+        thread::spawn(move || {
+            loop {
+                state_clone.mark_requests.pop().help();
+            }
+        });
+    }
+
+    /// Starts the thread used for processing events of which the timer
+    /// expired.
+    fn start_timer_thread(&self) {
+        let state_clone = self.state.clone();
+
+        thread::spawn(move || {
+            loop {
+                for event in state_clone.timer.wait_for_expired() {
+                    match event {
+                        TimerEvent::ReceiveTimeout(process, deadline) => {
+                            process.wake_up_after_timeout(deadline)
+                        }
+                        TimerEvent::Sleep(process) => {
+                            process.wake_up_from_sleep()
+                        }
+                        TimerEvent::SendMessage(handle, message) => {
+                            let receiver_opt = read_lock!(state_clone.processes)
+                                .get_handle(&handle);
+
+                            // The message is dropped if the receiver is no
+                            // longer alive, in which case its mailbox heap has
+                            // already been released.
+                            if let Some(receiver) = receiver_opt {
+                                receiver.send_delayed_message(message);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Parks a process until it receives a message or its receive deadline
+    /// expires.
+    fn park_process(&self, thread: RcThread, process: RcProcess) {
+        let deadline = process.receive_deadline();
+
+        // The process has to be remembered before parking it, as a message
+        // may arrive the moment it's parked.
+        thread.remember_process(process.clone());
+
+        if process.park(thread.clone()) {
+            if let Some(deadline) = deadline {
+                let event = TimerEvent::ReceiveTimeout(process, deadline);
+
+                self.state.timer.schedule(deadline, event);
+            }
+        } else {
+            thread.forget_process(&process);
+            process.suspend();
+            thread.schedule(process);
+        }
+    }
+
+    /// Removes the timer entry of a process that received a message before
+    /// its receive deadline expired.
+    fn cancel_receive_timeout(&self, process: &RcProcess) {
+        self.state.timer.cancel(|event| match *event {
+            TimerEvent::ReceiveTimeout(ref waiting, _) => {
+                waiting.pid == process.pid
+            }
+            _ => false,
+        });
+    }
+
+    /// Suspends a process until its sleep deadline expires.
+    fn sleep_process(&self, thread: RcThread, process: RcProcess) {
+        let deadline = process.sleep_deadline().unwrap();
+
+        // The process has to be remembered before suspending it, as it may be
+        // woken up by an exit signal the moment it's suspended.
+        thread.remember_process(process.clone());
+
+        if process.sleep(thread.clone()) {
+            self.state.timer.schedule(deadline, TimerEvent::Sleep(process));
+        } else {
+            thread.forget_process(&process);
+            process.suspend();
+            thread.schedule(process);
+        }
+    }
+
+    /// Steals a process from another thread.
+    fn steal_process(&self, thread: &RcThread) -> Option<RcProcess> {
+        // The thread list is locked for writing while stopping all threads,
+        // which waits for this thread to finish. Blocking on the lock here
+        // would result in a deadlock.
+        if let Ok(threads) = self.state.threads.try_read() {
+            threads.steal(thread)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the priority to spawn a process with.
+    ///
+    /// The priority is read from the register of the optional instruction
+    /// argument at the given index, defaulting to a normal priority.
+    fn spawn_priority(&self,
+                      process: &RcProcess,
+                      instruction: &Instruction,
+                      index: usize)
+                      -> Result<ProcessPriority, VirtualMachineError> {
+        if instruction.arguments.len() <= index {
+            return Ok(ProcessPriority::Normal);
+        }
+
+        let priority_ptr = instruction_object!(instruction, process, index);
+        let priority_obj = priority_ptr.get();
+
+        ensure_integers!(instruction, priority_obj);
+
+        let priority_int = priority_obj.value.as_integer();
+        let priority = try_vm_error!(ProcessPriority::from_integer(priority_int),
+                                     instruction);
+
+        Ok(priority)
+    }
+
+    /// Spawns a new process.
+    ///
+    /// The `prepare` closure is called with the new process before it's
+    /// scheduled, allowing callers to copy data into the heap of the new
+    /// process. If the closure returns an error the process is discarded and
+    /// the error is stored in the register.
+    fn spawn_process<F>(&self,
+                        process: RcProcess,
+                        code: RcCompiledCode,
+                        priority: ProcessPriority,
+                        register: usize,
+                        prepare: F)
+                        -> EmptyResult
+        where F: FnOnce(&RcProcess) -> Result<(), io::Error>
+    {
+        let (handle, new_proc) =
+            self.allocate_process(code, self.state.top_level.clone());
+
+        if let Err(error) = prepare(&new_proc) {
+            write_lock!(self.state.processes).remove(new_proc);
+
+            set_error!(errors::from_io_error(error), process, register);
+        }
+
+        new_proc.set_priority(priority);
+
+        write_lock!(self.state.threads).schedule(new_proc);
+
+        let handle_obj = self.allocate_process_handle(&process, handle);
+
+        process.set_register(register, handle_obj);
+
+        Ok(())
+    }
+
+    /// Start a thread's execution loop.
+    fn run_thread(&self, thread: RcThread) {
+        while !thread.should_stop() {
+            // Terminate gracefully once the main thread has processed its
+            // process queue.
+            if thread.main_can_terminate() {
+                write_lock!(self.state.threads).stop();
+                break;
+            }
+
+            thread.wait_for_work();
+
+            let mut proc_opt = thread.pop_process();
+
+            if proc_opt.is_none() && self.config().work_stealing {
+                proc_opt = self.steal_process(&thread);
+            }
+
+            // A thread may be woken up (e.g. due to a VM error or a timeout)
+            // without there being work available.
+            if proc_opt.is_none() {
+                continue;
+            }
+
+            let process = proc_opt.unwrap();
+
+            match self.run(thread.clone(), process.clone()) {
+                Ok(_) => {
+                    if let Some(reason) = process.exit_signal() {
+                        let error = if reason == EXIT_NORMAL {
+                            None
+                        } else {
+                            Some(reason)
+                        };
+
+                        self.terminate_process(process, error);
+                    } else if process.should_suspend_for_gc() {
+                        process.suspend_for_gc();
+                        thread.remember_process(process.clone());
+                    } else if process.should_wait_for_message() {
+                        self.park_process(thread.clone(), process);
+                    } else if process.should_sleep() {
+                        self.sleep_process(thread.clone(), process);
+                    } else if process.should_be_rescheduled() {
+                        thread.schedule(process);
+                    } else {
+                        self.terminate_process(process, None);
+                    }
+                }
+                Err(err) => {
+                    let reason = err.message.clone();
+
+                    self.error(process.clone(), err);
+                    self.terminate_process(process, Some(reason));
+                }
+            }
+        }
+    }
+
+    /// Terminates a process and notifies any linked or monitoring processes.
+    ///
+    /// The error argument contains the reason of the termination if the
+    /// process terminated due to an error or an exit signal.
+    fn terminate_process(&self, process: RcProcess, error: Option<String>) {
+        // The return value has to be delivered before marking the process as
+        // terminated, as the GC may release the memory of a terminated process
+        // at any given time.
+        self.deliver_return_value(&process, error.is_none());
+
+        if error.is_some() {
+            process.failed();
+
+            // Failures of processes other than the main process only affect
+            // the exit status when explicitly configured to do so.
+            if self.is_main_process(&process) ||
+               self.config().fail_on_process_errors {
+                *write_lock!(self.state.exit_status) = Err(());
+            }
+        } else {
+            process.finished();
+        }
 
-        let obj =
-            process.allocate(object_value::integer(result as i64), int_proto);
+        let handle = {
+            let mut processes = write_lock!(self.state.processes);
+            let handle = processes.handle(process.pid);
 
-        process.set_register(register, obj);
+            processes.remove(process.clone());
 
-        Ok(())
-    }
+            handle
+        };
 
-    /// Reads a number of bytes from a file.
-    ///
-    /// This instruction takes 3 arguments:
-    ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the file to read from.
-    /// 3. The register containing the amount of bytes to read, if left out
-    ///    all data is read instead.
-    ///
-    /// The result of this instruction is either a string containing the data
-    /// read, or an error object.
-    fn ins_file_read(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let file_ptr = instruction_object!(instruction, process, 1);
+        if process.receive_deadline().is_some() {
+            self.cancel_receive_timeout(&process);
+        }
 
-        let mut file_obj = file_ptr.get_mut();
+        let reason = error.unwrap_or_else(|| EXIT_NORMAL.to_string());
 
-        ensure_files!(instruction, file_obj);
+        for linked_handle in process.take_links() {
+            let linked_opt =
+                read_lock!(self.state.processes).get_handle(&linked_handle);
 
-        let mut buffer = file_reading_buffer!(instruction, process, 2);
-        let int_proto = self.state.integer_prototype.clone();
-        let mut file = file_obj.value.as_file_mut();
+            if let Some(linked) = linked_opt {
+                linked.unlink(&handle);
 
-        try_io!(file.read_to_string(&mut buffer), process, register);
+                if linked.traps_exits() {
+                    self.send_exit_message(&linked, EXIT_MESSAGE, handle, &reason);
+                } else if reason != EXIT_NORMAL {
+                    linked.signal_exit(reason.clone());
+                }
+            }
+        }
 
-        let obj = process.allocate(object_value::string(buffer), int_proto);
+        for monitor_handle in process.take_monitors() {
+            let monitor_opt =
+                read_lock!(self.state.processes).get_handle(&monitor_handle);
 
-        process.set_register(register, obj);
+            if let Some(monitor) = monitor_opt {
+                self.send_exit_message(&monitor, DOWN_MESSAGE, handle, &reason);
+            }
+        }
 
-        Ok(())
+        // A pending GC request may still use the heap of the process, in which
+        // case the GC thread releases the memory once it handles the request.
+        if !process.is_gc_scheduled() {
+            process.release_memory();
+        }
     }
 
-    /// Reads an entire line from a file.
-    ///
-    /// This instruction requires two arguments:
+    /// Delivers the return value of a terminating process to all processes
+    /// joining it.
     ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the file to read from.
-    ///
-    /// The result of this instruction is either a string containing the read
-    /// line, or an error object.
-    fn ins_file_read_line(&self,
-                          process: RcProcess,
-                          _: RcCompiledCode,
-                          instruction: &Instruction)
-                          -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let file_ptr = instruction_object!(instruction, process, 1);
+    /// If the process failed an error is delivered instead.
+    fn deliver_return_value(&self, process: &RcProcess, finished: bool) {
+        let joiners = process.take_joiners();
 
-        let mut file_obj = file_ptr.get_mut();
-
-        ensure_files!(instruction, file_obj);
-
-        let proto = self.state.string_prototype.clone();
-        let mut file = file_obj.value.as_file_mut();
-        let mut bytes = Vec::new();
+        if joiners.is_empty() {
+            return;
+        }
 
-        for result in file.bytes() {
-            let byte = try_io!(result, process, register);
+        let handle = read_lock!(self.state.processes).handle(process.pid);
 
-            bytes.push(byte);
+        let value = if finished {
+            Some(process.return_value()
+                .unwrap_or_else(|| self.state.false_object.clone()))
+        } else {
+            None
+        };
 
-            if byte == 0xA {
-                break;
+        for joiner_handle in joiners {
+            let joiner_opt =
+                read_lock!(self.state.processes).get_handle(&joiner_handle);
+
+            if let Some(joiner) = joiner_opt {
+                joiner.deliver_join_result(handle, |allocator| {
+                    let result = match value {
+                        Some(value) => {
+                            allocator.copy_object(value)
+                                .map_err(|err| errors::from_io_error(err))
+                        }
+                        None => Err(errors::PROCESS_FAILED),
+                    };
+
+                    result.unwrap_or_else(|code| {
+                        let error = object_value::error(code);
+
+                        allocator.allocate(Object::new(error))
+                    })
+                });
             }
         }
+    }
 
-        let string = try_error!(try_from_utf8!(bytes), process, register);
+    /// Sends a message notifying a process about the termination of another
+    /// process.
+    ///
+    /// The message is allocated directly on the mailbox heap of the receiver,
+    /// and is an array in the form `[tag, process, reason]`.
+    fn send_exit_message(&self,
+                         receiver: &RcProcess,
+                         tag: &str,
+                         handle: ProcessHandle,
+                         reason: &str) {
+        receiver.send_allocated_message(|allocator| {
+            let tag_obj = allocator.allocate(Object::with_prototype(
+                object_value::string(tag.to_string()),
+                self.state.string_prototype.clone()));
+
+            let handle_obj = allocator.allocate(Object::with_prototype(
+                object_value::process_handle(handle),
+                self.state.process_prototype.clone()));
+
+            let reason_obj = allocator.allocate(Object::with_prototype(
+                object_value::string(reason.to_string()),
+                self.state.string_prototype.clone()));
+
+            allocator.allocate(Object::with_prototype(
+                object_value::array(vec![tag_obj, handle_obj, reason_obj]),
+                self.state.array_prototype.clone()))
+        });
+    }
 
-        let obj = process.allocate(object_value::string(string), proto);
+    /// Checks if a garbage collection run should be scheduled for the given
+    /// process.
+    fn gc_safepoint(&self, thread: RcThread, process: RcProcess) {
+        if process.should_schedule_gc() {
+            process.gc_scheduled();
 
-        process.set_register(register, obj);
+            let request =
+                GcRequest::new(GcGeneration::Young,
+                               thread.clone(),
+                               process.clone());
 
-        Ok(())
-    }
+            self.state.gc_requests.push(request);
+        }
 
-    /// Flushes a file.
-    ///
-    /// This instruction requires two arguments:
-    ///
-    /// 1. The register to store the result in.
-    /// 2. the register containing the file to flush.
-    ///
-    /// The resulting object is either boolean true (upon success), or an error
-    /// object.
-    fn ins_file_flush(&self,
-                      process: RcProcess,
-                      _: RcCompiledCode,
-                      instruction: &Instruction)
-                      -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let file_ptr = instruction_object!(instruction, process, 1);
+        if process.should_schedule_mailbox_gc() {
+            let request =
+                GcRequest::new(GcGeneration::Mailbox, thread, process.clone());
 
-        let mut file_obj = file_ptr.get_mut();
+            self.state.gc_requests.push(request);
+        }
+    }
+}
 
-        ensure_files!(instruction, file_obj);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{i64, u64};
+    use compiled_code::CompiledCode;
+    use thread::Thread;
 
-        let mut file = file_obj.value.as_file_mut();
+    fn vm() -> VirtualMachine {
+        VirtualMachine::new(VirtualMachineState::new(Config::new()))
+    }
 
-        try_io!(file.flush(), process, register);
+    fn process(vm: &VirtualMachine) -> RcProcess {
+        let main = CompiledCode::with_rc("main".to_string(),
+                                         "test.aeon".to_string(),
+                                         1,
+                                         Vec::new());
 
-        process.set_register(register, self.state.true_object.clone());
+        vm.allocate_process(main, vm.state.top_level).1
+    }
 
-        Ok(())
+    fn integer(vm: &VirtualMachine,
+               process: &RcProcess,
+               value: i64)
+               -> ObjectPointer {
+        process.allocate(object_value::integer(value),
+                         vm.state.integer_prototype)
     }
 
-    /// Returns the size of a file in bytes.
-    ///
-    /// This instruction requires two arguments:
-    ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the file.
-    ///
-    /// The resulting object is either an integer representing the amount of
-    /// bytes, or an error object.
-    fn ins_file_size(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let file_ptr = instruction_object!(instruction, process, 1);
+    /// Returns a process with an object that only defines "method_missing" in
+    /// register 0, and an integer in register 1.
+    fn process_with_method_missing(vm: &VirtualMachine,
+                                   arguments: u32)
+                                   -> RcProcess {
+        let process = process(vm);
 
-        let file_obj = file_ptr.get();
+        let mut fallback = CompiledCode::new(METHOD_MISSING.to_string(),
+                                             "test.aeon".to_string(),
+                                             1,
+                                             Vec::new());
 
-        ensure_files!(instruction, file_obj);
+        fallback.arguments = arguments;
+        fallback.required_arguments = arguments;
 
-        let file = file_obj.value.as_file();
-        let meta = try_io!(file.metadata(), process, register);
+        let method =
+            process.allocate(object_value::compiled_code(Arc::new(fallback)),
+                             vm.state.method_prototype);
 
-        let size = meta.len() as i64;
-        let proto = self.state.integer_prototype.clone();
+        let receiver = process.allocate_empty();
 
-        let result = process.allocate(object_value::integer(size), proto);
+        receiver.get_mut().add_method(METHOD_MISSING.to_string(), method);
 
-        process.set_register(register, result);
+        let argument = integer(vm, &process, 10);
 
-        Ok(())
+        process.set_register(0, receiver);
+        process.set_register(1, argument);
+
+        process
     }
 
-    /// Sets a file cursor to the given offset in bytes.
-    ///
-    /// This instruction requires 3 arguments:
-    ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The register containing the input file.
-    /// 3. The offset to seek to as an integer.
-    ///
-    /// The resulting object is either an integer representing the new cursor
-    /// position, or an error object.
-    fn ins_file_seek(&self,
-                     process: RcProcess,
-                     _: RcCompiledCode,
-                     instruction: &Instruction)
-                     -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let file_ptr = instruction_object!(instruction, process, 1);
-        let offset_ptr = instruction_object!(instruction, process, 2);
+    /// Returns an instruction that sends a message to the object in register
+    /// 0, passing the value of register 1 as its only argument.
+    fn send_instruction() -> Instruction {
+        Instruction::new(InstructionType::SendLiteral,
+                         vec![2, 0, 0, 0, 0, 1],
+                         1,
+                         1)
+    }
 
-        let mut file_obj = file_ptr.get_mut();
-        let offset_obj = offset_ptr.get();
+    /// Stores a handle to the target process in register 1 of the given
+    /// process.
+    fn set_handle(vm: &VirtualMachine,
+                  process: &RcProcess,
+                  target: &RcProcess) {
+        let handle = read_lock!(vm.state.processes).handle(target.pid);
+        let handle_obj = vm.allocate_process_handle(process, handle);
 
-        ensure_files!(instruction, file_obj);
-        ensure_integers!(instruction, offset_obj);
+        process.set_register(1, handle_obj);
+    }
 
-        let mut file = file_obj.value.as_file_mut();
-        let offset = offset_obj.value.as_integer();
+    /// Returns the tag, process handle, and reason of an exit message.
+    fn receive_exit_message(process: &RcProcess)
+                            -> (String, ProcessHandle, String) {
+        let message = process.receive_message().unwrap();
+        let values = message.get().value.as_array();
 
-        ensure_positive_read_size!(instruction, offset);
+        (values[0].get().value.as_string().clone(),
+         values[1].get().value.as_process_handle(),
+         values[2].get().value.as_string().clone())
+    }
+
+    /// Stores a CompiledCode object in register 1 of the given process, for
+    /// use as the predicate of a selective receive.
+    fn set_predicate(vm: &VirtualMachine, process: &RcProcess) {
+        let code = process.compiled_code();
+        let code_obj = process.allocate(object_value::compiled_code(code),
+                                        vm.state.compiled_code_prototype);
 
-        let seek_from = SeekFrom::Start(offset as u64);
-        let new_offset = try_io!(file.seek(seek_from), process, register);
+        process.set_register(1, code_obj);
+    }
 
-        let proto = self.state.integer_prototype.clone();
+    fn receive_matching(vm: &VirtualMachine, process: &RcProcess) -> bool {
+        let instruction =
+            Instruction::new(InstructionType::ReceiveMatchingProcessMessage,
+                             vec![0, 1],
+                             1,
+                             1);
+
+        vm.ins_receive_matching_process_message(process.clone(),
+                                                process.compiled_code(),
+                                                &instruction)
+            .unwrap()
+    }
 
-        let result =
-            process.allocate(object_value::integer(new_offset as i64), proto);
+    /// Returns from a predicate, storing its result in register 0.
+    fn return_from_predicate(process: &RcProcess, result: ObjectPointer) {
+        process.pop_context();
+        process.set_register(0, result);
+    }
 
-        process.set_register(register, result);
+    #[test]
+    fn test_send_message_with_method_missing() {
+        let vm = vm();
+        let process = process_with_method_missing(&vm, 2);
+        let code = process.compiled_code();
 
-        Ok(())
-    }
+        let result = vm.send_message(&"foo".to_string(),
+                                     process.clone(),
+                                     code,
+                                     &send_instruction());
+
+        assert!(result.is_ok());
 
-    /// Parses and runs a given bytecode file using a string literal
-    ///
-    /// Files are executed only once. After a file has been executed any
-    /// following calls are basically no-ops.
-    ///
-    /// This instruction requires 2 arguments:
-    ///
-    /// 1. The register to store the resulting object in.
-    /// 2. The string literal index containing the file path of the bytecode
-    ///    file.
-    ///
-    /// The result of this instruction is whatever the bytecode file returned.
-    fn ins_run_literal_file(&self,
-                            process: RcProcess,
-                            code: RcCompiledCode,
-                            instruction: &Instruction)
-                            -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let index = try_vm_error!(instruction.arg(1), instruction);
-        let path = try_vm_error!(code.string(index), instruction);
+        let name = process.get_local(0).unwrap();
+        let args = process.get_local(1).unwrap();
 
-        self.run_file(path, process, instruction, register)
+        assert_eq!(name.get().value.as_string(), &"foo".to_string());
+        assert_eq!(args.get().value.as_array().len(), 1);
+        assert_eq!(args.get().value.as_array()[0].get().value.as_integer(),
+                   10);
     }
 
-    /// Parses and runs a given bytecode file using a runtime allocated string
-    ///
-    /// This instruction takes the same arguments as the "run_literal_file"
-    /// instruction except instead of using a string literal it uses a register
-    /// containing a runtime allocated string.
-    fn ins_run_file(&self,
-                    process: RcProcess,
-                    _: RcCompiledCode,
-                    instruction: &Instruction)
-                    -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let path_ptr = instruction_object!(instruction, process, 1);
+    #[test]
+    fn test_send_message_with_method_missing_and_keyword_arguments() {
+        let vm = vm();
+        let process = process_with_method_missing(&vm, 2);
+        let code = process.compiled_code();
 
-        let path = path_ptr.get();
+        // Sends the value of register 1 as keyword argument "foo".
+        let instruction = Instruction::new(InstructionType::SendLiteral,
+                                           vec![2, 0, 0, 0, 1, 0, 1],
+                                           1,
+                                           1);
 
-        ensure_strings!(instruction, path);
+        let error = vm.send_message(&"foo".to_string(),
+                                    process.clone(),
+                                    code,
+                                    &instruction)
+            .err()
+            .unwrap();
 
-        self.run_file(path.value.as_string(), process, instruction, register)
+        assert_eq!(error.message,
+                   "The message \"foo\" is not defined, and keyword \
+                    arguments can not be passed to method_missing"
+                       .to_string());
     }
 
-    /// Sets the caller of a method.
-    ///
-    /// This instruction requires one argument: the register to store the caller
-    /// in. If no caller is present "self" is set in the register instead.
-    fn ins_get_caller(&self,
-                      process: RcProcess,
-                      _: RcCompiledCode,
-                      instruction: &Instruction)
-                      -> EmptyResult {
-        let register = try_vm_error!(instruction.arg(0), instruction);
+    #[test]
+    fn test_send_message_with_method_missing_and_invalid_arguments() {
+        let vm = vm();
+        let process = process_with_method_missing(&vm, 1);
+        let code = process.compiled_code();
 
-        let caller = {
-            let context = process.context();
+        let error = vm.send_message(&"foo".to_string(),
+                                    process.clone(),
+                                    code,
+                                    &send_instruction())
+            .err()
+            .unwrap();
 
-            if let Some(parent) = context.parent() {
-                parent.self_object()
-            } else {
-                context.self_object()
-            }
-        };
+        assert_eq!(error.message,
+                   "method_missing accepts up to 1 arguments, but 2 \
+                    arguments were given"
+                       .to_string());
+    }
 
-        process.set_register(register, caller);
+    #[test]
+    fn test_receive_process_message_timeout_with_huge_timeout() {
+        let vm = vm();
+        let process = process(&vm);
+        let code = process.compiled_code();
+        let timeout = integer(&vm, &process, i64::MAX);
 
-        Ok(())
-    }
+        process.set_register(1, timeout);
 
-    /// Sets the outer scope of an object
-    ///
-    /// This instruction requires two arguments:
-    ///
-    /// 1. The register containing the object for which to set the outer scope.
-    /// 2. The register containing the object to use as the outer scope.
-    fn ins_set_outer_scope(&self,
-                           process: RcProcess,
-                           _: RcCompiledCode,
-                           instruction: &Instruction)
-                           -> EmptyResult {
-        let target_ptr = instruction_object!(instruction, process, 0);
-        let scope_ptr = instruction_object!(instruction, process, 1);
+        let instruction =
+            Instruction::new(InstructionType::ReceiveProcessMessageTimeout,
+                             vec![0, 1],
+                             1,
+                             1);
+
+        let suspend =
+            vm.ins_receive_process_message_timeout(process.clone(),
+                                                    code,
+                                                    &instruction)
+                .unwrap();
+
+        assert!(suspend);
+        assert_eq!(process.receive_deadline(), Some(u64::MAX));
+    }
 
-        let scope =
-            copy_if_permanent!(self.state.permanent_allocator, scope_ptr, target_ptr);
+    #[test]
+    fn test_receive_process_message_timeout_cancels_timer() {
+        let vm = vm();
+        let process = process(&vm);
+        let thread = Thread::new(false, None);
+        let timeout = integer(&vm, &process, 60_000);
 
-        target_ptr.set_outer_scope(&process, scope);
+        process.set_register(1, timeout);
 
-        Ok(())
+        let instruction =
+            Instruction::new(InstructionType::ReceiveProcessMessageTimeout,
+                             vec![0, 1],
+                             1,
+                             1);
+
+        assert!(vm.ins_receive_process_message_timeout(process.clone(),
+                                                    process.compiled_code(),
+                                                    &instruction)
+            .unwrap());
+
+        vm.park_process(thread.clone(), process.clone());
+
+        assert_eq!(vm.state.timer.len(), 1);
+
+        process.send_message(process.allocate_empty()).unwrap();
+
+        assert_eq!(vm.ins_receive_process_message_timeout(process.clone(),
+                                                       process.compiled_code(),
+                                                       &instruction)
+                       .unwrap(),
+                   false);
+
+        assert_eq!(vm.state.timer.len(), 0);
+        assert!(process.receive_deadline().is_none());
     }
 
-    /// Prints a VM backtrace of a given thread with a message.
-    fn error(&self, process: RcProcess, error: VirtualMachineError) {
-        let mut stderr = io::stderr();
-        let mut message =
-            format!("A fatal VM error occurred in process {}:", process.pid);
+    #[test]
+    fn test_process_sleep_with_huge_duration() {
+        let vm = vm();
+        let process = process(&vm);
+        let duration = integer(&vm, &process, i64::MAX);
 
-        message.push_str(&format!("\n\n{}\n\nCall stack:\n\n", error.message));
+        process.set_register(0, duration);
 
-        for frame in process.call_frame().call_stack() {
-            message.push_str(&format!(
-                "{} line {} in {}\n",
-                frame.file(),
-                frame.line,
-                frame.name()
-            ));
-        }
+        let instruction =
+            Instruction::new(InstructionType::ProcessSleep, vec![0], 1, 1);
 
-        stderr.write(message.as_bytes()).unwrap();
-        stderr.flush().unwrap();
+        assert!(vm.ins_process_sleep(process.clone(),
+                                     process.compiled_code(),
+                                     &instruction)
+            .unwrap());
 
-        *write_lock!(self.state.exit_status) = Err(());
+        assert_eq!(process.sleep_deadline(), Some(u64::MAX));
     }
 
-    /// Schedules the execution of a new CompiledCode.
-    fn schedule_code(&self,
-                     process: RcProcess,
-                     code: RcCompiledCode,
-                     self_obj: ObjectPointer,
-                     args: &Vec<ObjectPointer>,
-                     binding: Option<RcBinding>,
-                     register: usize) {
-        let context = if let Some(rc_bind) = binding {
-            ExecutionContext::with_binding(rc_bind, code.clone(), Some(register))
-        } else {
-            ExecutionContext::with_object(self_obj, code.clone(), Some(register))
-        };
+    #[test]
+    fn test_terminate_main_process_with_exit_signal() {
+        let vm = vm();
+        let process = process(&vm);
 
-        let frame = CallFrame::from_code(code);
+        *write_lock!(vm.state.main_process) = Some(process.clone());
 
-        process.push_context(context);
-        process.push_call_frame(frame);
+        vm.terminate_process(process, Some("killed".to_string()));
 
-        for (index, arg) in args.iter().enumerate() {
-            process.set_local(index, arg.clone());
-        }
+        assert!(read_lock!(vm.state.exit_status).is_err());
     }
 
-    /// Runs a bytecode file.
-    fn run_file(&self,
-                path_str: &String,
-                process: RcProcess,
-                instruction: &Instruction,
-                register: usize)
-                -> EmptyResult {
-        process.advance_line(instruction.line);
+    #[test]
+    fn test_terminate_process_with_exit_signal() {
+        let vm = vm();
+        let process = process(&vm);
 
-        {
-            let mut executed = write_lock!(self.state.executed_files);
+        vm.terminate_process(process, Some("killed".to_string()));
 
-            if executed.contains(path_str) {
-                return Ok(());
-            } else {
-                executed.insert(path_str.clone());
-            }
-        }
+        assert!(read_lock!(vm.state.exit_status).is_ok());
+    }
 
-        let mut input_path = PathBuf::from(path_str);
+    #[test]
+    fn test_join_process_after_termination() {
+        let vm = vm();
+        let process = process(&vm);
+        let code = process.compiled_code();
 
-        if input_path.is_relative() {
-            let mut found = false;
+        vm.allocate_thread(None);
 
-            for directory in self.config().directories.iter() {
-                let full_path = directory.join(path_str);
+        let code_obj = process.allocate(object_value::compiled_code(code),
+                                        vm.state.compiled_code_prototype);
 
-                if full_path.exists() {
-                    input_path = full_path;
-                    found = true;
+        let args =
+            process.allocate_without_prototype(object_value::array(Vec::new()));
 
-                    break;
-                }
-            }
+        process.set_register(1, code_obj);
+        process.set_register(2, args);
 
-            if !found {
-                return_vm_error!(format!("No file found for {}", path_str),
-                                 instruction.line);
-            }
-        }
+        let spawn =
+            Instruction::new(InstructionType::SpawnProcessWithArguments,
+                             vec![0, 1, 2],
+                             1,
+                             1);
 
-        let input_path_str = input_path.to_str().unwrap();
+        vm.ins_spawn_process_with_arguments(process.clone(),
+                                             process.compiled_code(),
+                                             &spawn)
+            .unwrap();
 
-        match bytecode_parser::parse_file(input_path_str) {
-            Ok(body) => {
-                let self_obj = self.state.top_level.clone();
+        let handle =
+            process.get_register(0).unwrap().get().value.as_process_handle();
 
-                self.schedule_code(process.clone(),
-                                   body,
-                                   self_obj,
-                                   &Vec::new(),
-                                   None,
-                                   register);
+        let child = read_lock!(vm.state.processes)
+            .get_handle(&handle)
+            .unwrap();
 
-                process.pop_call_frame();
+        child.set_return_value(integer(&vm, &child, 42));
 
-                Ok(())
-            }
-            Err(err) => {
-                return_vm_error!(
-                    format!("Failed to parse {}: {:?}", input_path_str, err),
-                    instruction.line
-                );
-            }
-        }
-    }
+        // The child finishes before the parent tries to join it.
+        vm.terminate_process(child, None);
 
-    /// Sends a message to an object.
-    fn send_message(&self,
-                    name: &String,
-                    process: RcProcess,
-                    instruction: &Instruction)
-                    -> EmptyResult {
-        // Advance the line number so error messages contain the correct frame
-        // pointing to the call site.
-        process.advance_line(instruction.line);
+        let join = Instruction::new(InstructionType::JoinProcess,
+                                    vec![3, 0],
+                                    1,
+                                    1);
 
-        let register = try_vm_error!(instruction.arg(0), instruction);
-        let receiver_ptr = instruction_object!(instruction, process, 1);
-        let rest_arg = try_vm_error!(instruction.arg(3), instruction) == 1;
+        let retry =
+            vm.ins_join_process(process.clone(), process.compiled_code(), &join)
+                .unwrap();
 
-        let method_ptr = {
-            let receiver = receiver_ptr.get();
+        assert_eq!(retry, false);
+        assert_eq!(process.get_register(3).unwrap().get().value.as_integer(),
+                   42);
+    }
 
-            try_vm_error!(
-                receiver.lookup_method(name)
-                    .ok_or_else(|| format!("undefined method \"{}\"", name)),
-                instruction
-            )
-        };
+    #[test]
+    fn test_link_process_with_exit_signal() {
+        let vm = vm();
+        let linked = process(&vm);
+        let process = process(&vm);
+        let instruction =
+            Instruction::new(InstructionType::LinkProcess, vec![0, 1], 1, 1);
 
-        let method_obj = method_ptr.get();
+        set_handle(&vm, &process, &linked);
 
-        ensure_compiled_code!(instruction, method_obj);
+        vm.ins_link_process(process.clone(),
+                            process.compiled_code(),
+                            &instruction)
+            .unwrap();
 
-        let method_code = method_obj.value.as_compiled_code();
+        assert!(process.get_register(0).unwrap() == vm.state.true_object);
 
-        // Argument handling
-        let arg_count = instruction.arguments.len() - 4;
-        let tot_args = method_code.arguments as usize;
-        let req_args = method_code.required_arguments as usize;
+        vm.terminate_process(linked, Some("oops".to_string()));
 
-        let mut arguments = try!(
-            self.collect_arguments(process.clone(), instruction, 4, arg_count)
-        );
+        assert_eq!(process.exit_signal(), Some("oops".to_string()));
+        assert_eq!(process.mailbox.len(), 0);
+    }
 
-        // Unpack the last argument if it's a rest argument
-        if rest_arg {
-            if let Some(last_arg) = arguments.pop() {
-                let array = last_arg.get();
+    #[test]
+    fn test_link_process_with_normal_exit() {
+        let vm = vm();
+        let linked = process(&vm);
+        let process = process(&vm);
+        let instruction =
+            Instruction::new(InstructionType::LinkProcess, vec![0, 1], 1, 1);
 
-                ensure_arrays!(instruction, array);
+        set_handle(&vm, &process, &linked);
 
-                for value in array.value.as_array() {
-                    arguments.push(value.clone());
-                }
-            }
-        }
+        vm.ins_link_process(process.clone(),
+                            process.compiled_code(),
+                            &instruction)
+            .unwrap();
 
-        // If the method defines a rest argument we'll pack any excessive
-        // arguments into a single array.
-        if method_code.rest_argument && arguments.len() > tot_args {
-            let rest_count = arguments.len() - tot_args;
-            let mut rest = Vec::new();
+        vm.terminate_process(linked, None);
 
-            for obj in arguments[arguments.len() - rest_count..].iter() {
-                rest.push(obj.clone());
-            }
+        assert!(process.exit_signal().is_none());
+    }
 
-            arguments.truncate(tot_args);
+    #[test]
+    fn test_link_process_with_trapped_exits() {
+        let vm = vm();
+        let linked = process(&vm);
+        let process = process(&vm);
+        let handle = read_lock!(vm.state.processes).handle(linked.pid);
+        let link =
+            Instruction::new(InstructionType::LinkProcess, vec![0, 1], 1, 1);
 
-            let rest_array = process.allocate(object_value::array(rest),
-                                              self.state.array_prototype.clone());
+        let trap =
+            Instruction::new(InstructionType::TrapExits, vec![0, 1], 1, 1);
 
-            arguments.push(rest_array);
-        } else if method_code.rest_argument && arguments.len() == 0 {
-            let rest_array = process.allocate(object_value::array(Vec::new()),
-                                              self.state.array_prototype.clone());
+        process.set_register(1, vm.state.true_object);
 
-            arguments.push(rest_array);
-        }
+        vm.ins_trap_exits(process.clone(), process.compiled_code(), &trap)
+            .unwrap();
 
-        if arguments.len() > tot_args && !method_code.rest_argument {
-            return_vm_error!(
-                format!(
-                    "{} accepts up to {} arguments, but {} arguments were given",
-                    name,
-                    method_code.arguments,
-                    arguments.len()
-                ),
-                instruction.line
-            );
-        }
+        set_handle(&vm, &process, &linked);
 
-        if arguments.len() < req_args {
-            return_vm_error!(
-                format!(
-                    "{} requires {} arguments, but {} arguments were given",
-                    name,
-                    method_code.required_arguments,
-                    arguments.len()
-                ),
-                instruction.line
-            );
-        }
+        vm.ins_link_process(process.clone(), process.compiled_code(), &link)
+            .unwrap();
 
-        self.schedule_code(process.clone(),
-                           method_code,
-                           receiver_ptr.clone(),
-                           &arguments,
-                           None,
-                           register);
+        vm.terminate_process(linked, Some("oops".to_string()));
 
-        process.pop_call_frame();
+        assert!(process.exit_signal().is_none());
 
-        Ok(())
+        assert_eq!(receive_exit_message(&process),
+                   (EXIT_MESSAGE.to_string(), handle, "oops".to_string()));
     }
 
-    /// Collects a set of arguments from an instruction.
-    fn collect_arguments(&self,
-                         process: RcProcess,
-                         instruction: &Instruction,
-                         offset: usize,
-                         amount: usize)
-                         -> ObjectVecResult {
-        let mut args: Vec<ObjectPointer> = Vec::new();
+    #[test]
+    fn test_link_process_not_alive() {
+        let vm = vm();
+        let linked = process(&vm);
+        let process = process(&vm);
+        let instruction =
+            Instruction::new(InstructionType::LinkProcess, vec![0, 1], 1, 1);
 
-        for index in offset..(offset + amount) {
-            let arg_index = try_vm_error!(instruction.arg(index), instruction);
+        set_handle(&vm, &process, &linked);
 
-            let arg = try_vm_error!(
-                process.get_register(arg_index),
-                instruction
-            );
+        vm.terminate_process(linked, None);
 
-            args.push(arg)
-        }
+        vm.ins_link_process(process.clone(),
+                            process.compiled_code(),
+                            &instruction)
+            .unwrap();
 
-        Ok(args)
+        assert!(process.get_register(0).unwrap() == vm.state.false_object);
     }
 
-    /// Starts a new thread.
-    fn start_thread(&self) -> RcThread {
-        let state_clone = self.state.clone();
+    #[test]
+    fn test_monitor_process() {
+        let vm = vm();
+        let monitored = process(&vm);
+        let process = process(&vm);
+        let handle = read_lock!(vm.state.processes).handle(monitored.pid);
+        let instruction =
+            Instruction::new(InstructionType::MonitorProcess, vec![0, 1], 1, 1);
 
-        let (sender, receiver) = channel();
+        set_handle(&vm, &process, &monitored);
 
-        let handle = thread::spawn(move || {
-            let thread = receiver.recv().unwrap();
-            let vm = VirtualMachine::new(state_clone);
+        vm.ins_monitor_process(process.clone(),
+                               process.compiled_code(),
+                               &instruction)
+            .unwrap();
 
-            vm.run_thread(thread);
-        });
+        assert!(process.get_register(0).unwrap() == vm.state.true_object);
 
-        let thread = self.allocate_thread(Some(handle));
+        vm.terminate_process(monitored, Some("oops".to_string()));
 
-        sender.send(thread.clone()).unwrap();
+        // Monitoring processes are never terminated by an exit signal.
+        assert!(process.exit_signal().is_none());
 
-        thread
+        assert_eq!(receive_exit_message(&process),
+                   (DOWN_MESSAGE.to_string(), handle, "oops".to_string()));
     }
 
-    /// Starts a new GC thread
-    fn start_gc_thread(&self) {
-        let state_clone = self.state.clone();
+    #[test]
+    fn test_monitor_process_not_alive() {
+        let vm = vm();
+        let monitored = process(&vm);
+        let process = process(&vm);
+        let handle = read_lock!(vm.state.processes).handle(monitored.pid);
+        let instruction =
+            Instruction::new(InstructionType::MonitorProcess, vec![0, 1], 1, 1);
 
-        thread::spawn(move || {
-            let mut gc_thread = GcThread::new(state_clone);
+        set_handle(&vm, &process, &monitored);
 
-            gc_thread.run();
-        });
+        vm.terminate_process(monitored, None);
+
+        vm.ins_monitor_process(process.clone(),
+                               process.compiled_code(),
+                               &instruction)
+            .unwrap();
+
+        assert!(process.get_register(0).unwrap() == vm.state.false_object);
+
+        assert_eq!(receive_exit_message(&process),
+                   (DOWN_MESSAGE.to_string(),
+                    handle,
+                    EXIT_NO_PROCESS.to_string()));
     }
 
-    /// Spawns a new process.
-    fn spawn_process(&self,
-                     process: RcProcess,
-                     code: RcCompiledCode,
-                     register: usize) {
-        let (pid, new_proc) =
-            self.allocate_process(code, self.state.top_level.clone());
+    #[test]
+    fn test_receive_matching_process_message() {
+        let vm = vm();
+        let process = process(&vm);
+        let depth = process.call_depth();
 
-        write_lock!(self.state.threads).schedule(new_proc);
+        set_predicate(&vm, &process);
 
-        let pid_obj = process.allocate(object_value::integer(pid as i64),
-                                       self.state.integer_prototype.clone());
+        process.send_message(integer(&vm, &process, 1)).unwrap();
+        process.send_message(integer(&vm, &process, 2)).unwrap();
+
+        // The predicate is scheduled for the first message, which it rejects.
+        assert!(receive_matching(&vm, &process));
+        assert_eq!(process.call_depth(), depth + 1);
+        assert_eq!(process.get_local(0).unwrap().get().value.as_integer(), 1);
+
+        return_from_predicate(&process, vm.state.false_object);
+
+        // The second message is checked next, and matches.
+        assert!(receive_matching(&vm, &process));
+        assert_eq!(process.get_local(0).unwrap().get().value.as_integer(), 2);
+
+        let checked = process.get_local(0).unwrap();
+
+        return_from_predicate(&process, vm.state.true_object);
 
-        process.set_register(register, pid_obj);
+        assert_eq!(receive_matching(&vm, &process), false);
+
+        let message = process.get_register(0).unwrap();
+
+        assert!(message == checked);
+        assert_eq!(message.get().value.as_integer(), 2);
+        assert_eq!(process.mailbox.len(), 1);
+        assert!(process.selective_receive().is_none());
     }
 
-    /// Start a thread's execution loop.
-    fn run_thread(&self, thread: RcThread) {
-        while !thread.should_stop() {
-            // Bail out if any of the other threads errored.
-            if read_lock!(self.state.exit_status).is_err() {
-                break;
-            }
+    #[test]
+    fn test_receive_matching_process_message_waiting() {
+        let vm = vm();
+        let process = process(&vm);
+        let thread = Thread::new(false, None);
 
-            // Terminate gracefully once the main thread has processed its
-            // process queue.
-            if thread.main_can_terminate() {
-                write_lock!(self.state.threads).stop();
-                break;
-            }
+        set_predicate(&vm, &process);
 
-            thread.wait_for_work();
+        process.send_message(integer(&vm, &process, 1)).unwrap();
 
-            let proc_opt = thread.pop_process();
+        assert!(receive_matching(&vm, &process));
 
-            // A thread may be woken up (e.g. due to a VM error) without there
-            // being work available.
-            if proc_opt.is_none() {
-                continue;
-            }
+        return_from_predicate(&process, vm.state.false_object);
 
-            let process = proc_opt.unwrap();
+        // All messages have been checked, so the process waits for a new one.
+        assert!(receive_matching(&vm, &process));
+        assert!(process.selective_receive() ==
+                Some(SelectiveReceive::Waiting(1)));
+        assert!(process.should_wait_for_message());
 
-            match self.run(thread.clone(), process.clone()) {
-                Ok(_) => {
-                    if process.should_suspend_for_gc() {
-                        process.suspend_for_gc();
-                        thread.remember_process(process.clone());
-                    } else if process.should_be_rescheduled() {
-                        thread.schedule(process);
-                    } else {
-                        process.finished();
+        vm.park_process(thread.clone(), process.clone());
 
-                        write_lock!(self.state.processes).remove(process);
-                    }
-                }
-                // TODO: process supervision
-                Err(err) => {
-                    self.error(process, err);
+        assert!(process.is_waiting_for_message());
 
-                    write_lock!(self.state.threads).stop();
-                }
-            }
-        }
+        process.send_message(integer(&vm, &process, 2)).unwrap();
+
+        assert_eq!(process.is_waiting_for_message(), false);
+        assert_eq!(thread.process_queue_size(), 1);
+
+        // Only the new message is passed to the predicate.
+        assert!(receive_matching(&vm, &process));
+        assert_eq!(process.get_local(0).unwrap().get().value.as_integer(), 2);
+
+        return_from_predicate(&process, vm.state.true_object);
+
+        assert_eq!(receive_matching(&vm, &process), false);
+        assert_eq!(process.get_register(0).unwrap().get().value.as_integer(),
+                   2);
+        assert_eq!(process.mailbox.len(), 1);
     }
 
-    /// Checks if a garbage collection run should be scheduled for the given
-    /// process.
-    fn gc_safepoint(&self, thread: RcThread, process: RcProcess) {
-        if process.should_schedule_gc() {
-            process.gc_scheduled();
+    #[test]
+    fn test_receive_inside_receive_predicate() {
+        let vm = vm();
+        let process = process(&vm);
+        let receive = Instruction::new(InstructionType::ReceiveProcessMessage,
+                                       vec![2],
+                                       1,
+                                       1);
 
-            let request =
-                GcRequest::new(GcGeneration::Young, thread, process.clone());
+        set_predicate(&vm, &process);
 
-            self.state.gc_requests.push(request);
-        }
+        process.send_message(integer(&vm, &process, 1)).unwrap();
+
+        assert!(receive_matching(&vm, &process));
+
+        let error = vm.ins_receive_process_message(process.clone(),
+                                                   process.compiled_code(),
+                                                   &receive)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.message,
+                   "Messages can not be received while running the \
+                    predicate of a selective receive"
+                       .to_string());
+
+        // The message being checked is left as-is.
+        assert_eq!(process.mailbox.len(), 1);
+    }
+
+    #[test]
+    fn test_freeze_shares_object() {
+        let vm = vm();
+        let receiver = process(&vm);
+        let process = process(&vm);
+        let original = integer(&vm, &process, 10);
+
+        process.set_register(1, original);
+
+        let instruction =
+            Instruction::new(InstructionType::Freeze, vec![0, 1], 1, 1);
+
+        vm.ins_freeze(process.clone(), process.compiled_code(), &instruction)
+            .unwrap();
+
+        let frozen = process.get_register(0).unwrap();
+
+        assert!(original.get().is_frozen());
+        assert!(frozen.is_permanent());
+        assert!(frozen.get().is_frozen());
+
+        receiver.send_message(frozen).unwrap();
+
+        // Frozen objects are shared, instead of being copied into the mailbox
+        // heap and the heap of the receiver.
+        assert_eq!(receiver.heap_info().mailbox_blocks, 0);
+        assert!(receiver.receive_message().unwrap() == frozen);
     }
 }
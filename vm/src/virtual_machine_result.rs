@@ -6,3 +6,5 @@ pub type BooleanResult = Result<bool, VirtualMachineError>;
 pub type IntegerResult = Result<Option<usize>, VirtualMachineError>;
 pub type ObjectResult = Result<Option<ObjectPointer>, VirtualMachineError>;
 pub type ObjectVecResult = Result<Vec<ObjectPointer>, VirtualMachineError>;
+pub type KeywordArgumentsResult = Result<Vec<(usize, ObjectPointer)>,
+                                         VirtualMachineError>;